@@ -0,0 +1,111 @@
+//! Benchmarks of the prime generation and the searches, run with `cargo bench`.
+//!
+//! The `bench` subcommand of the CLI gives a quicker overview, these are
+//! meant to compare the performance before and after a change.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+use primes::{
+    find_prime_quadruplet, gen_primes_upto_n, sieve_primes_upto_n, HamiltonianCycle, Hankel,
+    SearchLimits, SearchResult, SearchStats,
+};
+
+/// The sizes of the graphs, like the `bench` subcommand.
+const SIZES: [usize; 4] = [100, 500, 1_000, 2_000];
+
+/// Trial division by the smaller primes against the sieve of Eratosthenes.
+fn primes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("primes");
+    for n in [10_000, 1_000_000, 10_000_000] {
+        group.bench_with_input(BenchmarkId::new("trial_division", n), &n, |b, &n| {
+            b.iter(|| gen_primes_upto_n(black_box(n)))
+        });
+        group.bench_with_input(BenchmarkId::new("sieve", n), &n, |b, &n| {
+            b.iter(|| sieve_primes_upto_n(black_box(n)))
+        });
+    }
+    group.finish();
+}
+
+/// The recursive against the iterative backtracking, starting from 1,
+/// and the iterative one with the neighbor lists.
+fn backtracking(c: &mut Criterion) {
+    let primes = gen_primes_upto_n(2 * SIZES[SIZES.len() - 1]);
+    let limits = SearchLimits::default();
+    let mut group = c.benchmark_group("backtracking");
+    for n in SIZES {
+        let mat = Hankel::prime_sum_matrix(n, Some(&primes));
+        let start = |n| {
+            let mut path = vec![0; n];
+            path[0] = 1;
+            path
+        };
+        group.bench_with_input(BenchmarkId::new("recursive", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut path = start(n);
+                let mut stats = SearchStats::default();
+                let result = mat.hamiltonian_cycle_with_limits(&mut path, 1, &limits, &mut stats);
+                assert_eq!(result, SearchResult::Found);
+                path
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("iterative", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut path = start(n);
+                let mut stats = SearchStats::default();
+                let result = mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats);
+                assert_eq!(result, SearchResult::Found);
+                path
+            })
+        });
+        let adjacency = mat.adjacency();
+        group.bench_with_input(BenchmarkId::new("adjacency", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut path = start(n);
+                let mut stats = SearchStats::default();
+                let result =
+                    adjacency.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats);
+                assert_eq!(result, SearchResult::Found);
+                path
+            })
+        });
+        let by_degree = mat.adjacency_by_degree();
+        group.bench_with_input(BenchmarkId::new("by degree", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut path = start(n);
+                let mut stats = SearchStats::default();
+                let result =
+                    by_degree.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats);
+                assert_eq!(result, SearchResult::Found);
+                path
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Constructing a cycle from a prime quadruplet against searching for one.
+fn construction(c: &mut Criterion) {
+    let primes = gen_primes_upto_n(4 * SIZES[SIZES.len() - 1]);
+    let mut group = c.benchmark_group("construction");
+    for n in SIZES {
+        group.bench_with_input(BenchmarkId::new("quadruplet", n), &n, |b, &n| {
+            b.iter(|| {
+                let (p1, p2) = find_prime_quadruplet(n / 2, Some(&primes)).unwrap();
+                HamiltonianCycle::new(p1, p2, n / 2).collect::<Vec<_>>()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("search", n), &n, |b, &n| {
+            b.iter(|| {
+                Hankel::prime_sum_matrix(n, Some(&primes))
+                    .is_hamiltonian()
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, primes, backtracking, construction);
+criterion_main!(benches);
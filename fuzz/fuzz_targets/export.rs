@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primes::fuzzing::SearchCase;
+
+fuzz_target!(|case: SearchCase| {
+    let matrix = case.matrix;
+    // An invalid cycle is an error, but it should never panic.
+    for cycle in [None, Some(&case.path[..])] {
+        let _ = matrix.write_dot(Vec::new(), cycle);
+        let _ = matrix.write_graphml(Vec::new(), cycle);
+        let _ = matrix.write_matrix_market(Vec::new(), cycle);
+        let _ = matrix.write_svg(Vec::new(), cycle);
+    }
+});
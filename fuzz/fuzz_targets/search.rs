@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primes::fuzzing::SearchCase;
+use primes::{SearchResult, SearchStats};
+
+fuzz_target!(|case: SearchCase| {
+    let SearchCase {
+        matrix,
+        path,
+        pos,
+        limits,
+    } = case;
+    let mut recursive = path.clone();
+    let mut stats = SearchStats::default();
+    let result = matrix.hamiltonian_cycle_with_limits(&mut recursive, pos, &limits, &mut stats);
+    if result == SearchResult::Found {
+        assert!(matrix.valid_cycle(&recursive));
+    }
+    let mut iterative = path;
+    let mut stats = SearchStats::default();
+    let result = matrix.hamiltonian_cycle_iterative(&mut iterative, pos, &limits, &mut stats);
+    if result == SearchResult::Found {
+        assert!(matrix.valid_cycle(&iterative));
+    }
+});
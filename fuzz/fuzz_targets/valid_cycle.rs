@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use primes::fuzzing::SearchCase;
+
+fuzz_target!(|case: SearchCase| {
+    let matrix = case.matrix;
+    if matrix.valid_cycle(&case.path) {
+        assert!(matrix.valid_path(&case.path));
+        assert_eq!(case.path.len(), matrix.size);
+    }
+    for (i, j) in matrix.edges() {
+        assert_eq!(matrix.try_get(i, j), Some(1));
+        assert_eq!(matrix.try_get(j, i), Some(1));
+    }
+});
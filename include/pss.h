@@ -0,0 +1,37 @@
+/* C interface to the prime sum sequences library, built with
+ * `cargo rustc --release --lib --crate-type cdylib --no-default-features --features capi`.
+ * See src/capi.rs for the documentation of the functions. */
+#ifndef PSS_H
+#define PSS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PSS_FOUND 1
+#define PSS_NOT_FOUND 0
+#define PSS_ABORTED (-1)
+#define PSS_INVALID_ARGUMENT (-2)
+
+/* Writes a Hamiltonian cycle of the prime sum graph of size n to out_buf,
+ * which has room for len >= n vertices. */
+int pss_find_cycle(size_t n, size_t *out_buf, size_t len);
+
+/* Same as pss_find_cycle, but returns PSS_ABORTED after max_nodes nodes,
+ * unless max_nodes is 0. */
+int pss_find_cycle_limited(size_t n, uint64_t max_nodes, size_t *out_buf, size_t len);
+
+/* Checks if the len vertices in cycle form a prime sum cycle. */
+int pss_verify_cycle(const size_t *cycle, size_t len);
+
+/* Finds the primes p1 < p2 <= 2n that give a cycle of size 2n. */
+int pss_find_prime_quadruplet(size_t half_size, size_t *prime1, size_t *prime2);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
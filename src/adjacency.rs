@@ -0,0 +1,388 @@
+//! Neighbor lists for the backtracking search.
+//!
+//! The neighbors of every vertex are stored next to each other as `u32`s,
+//! so the search reads the memory in order, instead of every other entry
+//! of the diagonals. For the prime sum graphs that is about 4n²/ln(2n)
+//! bytes, which fits in the L2 cache up to a size of around 1000.
+//!
+//! With [`Hankel::adjacency_by_degree`] the search tries the neighbors with
+//! the fewest neighbors of their own first. Those are the hardest to fit into
+//! a cycle, so dead ends are found sooner.
+//!
+//! The path of the search can be stored as `u32`s as well, see [`Vertex`],
+//! which halves the memory it reads and writes on 64-bit machines.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Hankel, SearchLimits, SearchResult, SearchStats};
+
+/// The type of the vertices in the path of
+/// [`Adjacency::hamiltonian_cycle_iterative`]: `usize`, or `u32` to use half
+/// the memory. Every size that fits in an [`Adjacency`] fits in a `u32`.
+pub trait Vertex: Copy + Default + Eq + core::fmt::Debug {
+    /// Returns the vertex as an index.
+    fn index(self) -> usize;
+    /// Returns the vertex with the index `v`, which fits in the type.
+    fn from_index(v: usize) -> Self;
+}
+
+impl Vertex for usize {
+    fn index(self) -> usize {
+        self
+    }
+    fn from_index(v: usize) -> Self {
+        v
+    }
+}
+
+impl Vertex for u32 {
+    fn index(self) -> usize {
+        self as usize
+    }
+    fn from_index(v: usize) -> Self {
+        debug_assert!(u32::try_from(v).is_ok(), "the vertex doesn't fit in a u32");
+        v as u32
+    }
+}
+
+/// The neighbors of every vertex of a [`Hankel`] matrix, in increasing order.
+/// The vertices are 1-indexed, like in [`Hankel::get`].
+#[derive(Clone, Debug)]
+pub struct Adjacency {
+    /// The neighbors of v are at `offsets[v - 1]..offsets[v]`.
+    offsets: Vec<usize>,
+    neighbors: Vec<u32>,
+    /// The neighbors in the order the search tries them, at the same
+    /// offsets, or `None` to try them from the largest one down.
+    order: Option<Vec<u32>>,
+}
+
+impl Hankel {
+    /// Returns the neighbor lists of the graph, see [`Adjacency`].
+    ///
+    /// # Panics
+    ///
+    /// If the size doesn't fit in a `u32`.
+    pub fn adjacency(&self) -> Adjacency {
+        assert!(
+            u32::try_from(self.size).is_ok(),
+            "the size doesn't fit in a u32"
+        );
+        let mut offsets = Vec::with_capacity(self.size + 1);
+        let mut neighbors = Vec::with_capacity(self.degrees.iter().sum());
+        offsets.push(0);
+        for v in 1..=self.size {
+            neighbors.extend(
+                (1..=self.size)
+                    .filter(|&w| w != v && self.get(v, w) != 0)
+                    .map(|w| w as u32),
+            );
+            offsets.push(neighbors.len());
+        }
+        Adjacency {
+            offsets,
+            neighbors,
+            order: None,
+        }
+    }
+
+    /// Same as [`Hankel::adjacency`], but the search tries the neighbors with
+    /// the smallest degree first, and the largest one first among those.
+    ///
+    /// # Panics
+    ///
+    /// If the size doesn't fit in a `u32`.
+    pub fn adjacency_by_degree(&self) -> Adjacency {
+        let mut adjacency = self.adjacency();
+        let mut order = adjacency.neighbors.clone();
+        for v in 1..=self.size {
+            order[adjacency.offsets[v - 1]..adjacency.offsets[v]]
+                .sort_unstable_by_key(|&w| (self.degrees[w as usize - 1], core::cmp::Reverse(w)));
+        }
+        adjacency.order = Some(order);
+        adjacency
+    }
+}
+
+impl Adjacency {
+    /// Returns the neighbor lists without the edges of `cycle`, where the
+    /// last vertex is joined to the first one, in the same order.
+    pub fn without_cycle(&self, cycle: &[usize]) -> Adjacency {
+        let n = self.size();
+        // The two neighbors of every vertex on the cycle.
+        let mut removed = vec![[0; 2]; n + 1];
+        for (i, &v) in cycle.iter().enumerate() {
+            let next = cycle[(i + 1) % cycle.len()];
+            removed[v][1] = next;
+            removed[next][0] = v;
+        }
+        let keep = |v: usize, w: u32| !removed[v].contains(&(w as usize));
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut neighbors = Vec::with_capacity(self.neighbors.len());
+        let mut order = self.order.as_ref().map(|_| Vec::new());
+        offsets.push(0);
+        for v in 1..=n {
+            neighbors.extend(self.neighbors(v).iter().filter(|&&w| keep(v, w)));
+            if let (Some(order), Some(old)) = (&mut order, &self.order) {
+                let range = self.offsets[v - 1]..self.offsets[v];
+                order.extend(old[range].iter().filter(|&&w| keep(v, w)));
+            }
+            offsets.push(neighbors.len());
+        }
+        Adjacency {
+            offsets,
+            neighbors,
+            order,
+        }
+    }
+
+    /// Returns the number of vertices.
+    pub fn size(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns the neighbors of `v` in increasing order, without `v` itself.
+    pub fn neighbors(&self, v: usize) -> &[u32] {
+        &self.neighbors[self.offsets[v - 1]..self.offsets[v]]
+    }
+
+    /// Returns the `k`th neighbor of `v` that the search tries, if any.
+    fn candidate(&self, v: usize, k: usize) -> Option<usize> {
+        let range = self.offsets[v - 1]..self.offsets[v];
+        let index = match &self.order {
+            Some(order) => return order[range].get(k).map(|&w| w as usize),
+            None => range.len().checked_sub(k + 1)?,
+        };
+        Some(self.neighbors[range.start + index] as usize)
+    }
+
+    /// Returns how many neighbors of `v` the search tried up to `w`.
+    fn tried_up_to(&self, v: usize, w: usize) -> usize {
+        let range = self.offsets[v - 1]..self.offsets[v];
+        match &self.order {
+            Some(order) => order[range].iter().position(|&u| u as usize == w).unwrap() + 1,
+            None => {
+                let neighbors = &self.neighbors[range];
+                neighbors.len() - neighbors.partition_point(|&u| (u as usize) < w)
+            }
+        }
+    }
+
+    /// Checks if `v` and `w` are adjacent, where `v` is a vertex.
+    fn adjacent(&self, v: usize, w: usize) -> bool {
+        u32::try_from(w).is_ok_and(|w| self.neighbors(v).binary_search(&w).is_ok())
+    }
+
+    /// Same as [`Hankel::hamiltonian_cycle_iterative`], which finds the same
+    /// cycle, but reads the neighbors from the lists. If they come from
+    /// [`Hankel::adjacency_by_degree`], the neighbors are tried in a different
+    /// order, so the cycle can be a different one.
+    ///
+    /// The vertices of `path` can be `usize`s or `u32`s, see [`Vertex`].
+    pub fn hamiltonian_cycle_iterative<V: Vertex>(
+        &self,
+        path: &mut [V],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        let n = self.size();
+        if !self.valid_start(path, pos) {
+            return SearchResult::NotFound;
+        }
+        let start = pos;
+        if start == n {
+            if self.adjacent(path[0].index(), path[start - 1].index()) {
+                return SearchResult::Found;
+            }
+            stats.record_dead_end(path, start);
+            return SearchResult::NotFound;
+        }
+        let mut used = vec![false; n + 1];
+        for &v in &path[..pos] {
+            used[v.index()] = true;
+        }
+        let mut pos = start;
+        // The same symmetry breaking as in `Hankel::orient`.
+        let oriented = start == 1 && n > 2;
+        let mut open = 0;
+        // The number of neighbors of the previous vertex that were tried.
+        let mut tried = 0;
+        loop {
+            let last = path[pos - 1].index();
+            // the sequence alternates between odd and even
+            let parity = (pos + 1) % 2;
+            let mut placed = false;
+            while let Some(w) = self.candidate(last, tried) {
+                tried += 1;
+                // The other searches never try 1, which is normally the start.
+                if w < 2 || w % 2 != parity || used[w] {
+                    continue;
+                }
+                if oriented {
+                    match self.orient(path[0].index(), path[1].index(), pos, w, open) {
+                        Some(next_open) => open = next_open,
+                        None => continue,
+                    }
+                }
+                path[pos] = V::from_index(w);
+                used[w] = true;
+                stats.nodes += 1;
+                if limits.reached(stats, pos + 1) {
+                    return SearchResult::Aborted;
+                }
+                placed = true;
+                break;
+            }
+            if placed {
+                pos += 1;
+                if pos < n {
+                    tried = 0;
+                    continue;
+                }
+                if self.adjacent(path[0].index(), path[pos - 1].index()) {
+                    return SearchResult::Found;
+                }
+                stats.record_dead_end(path, pos);
+            } else {
+                stats.record_dead_end(path, pos);
+                if pos == start {
+                    return SearchResult::NotFound;
+                }
+            }
+            // Backtrack
+            pos -= 1;
+            let v = core::mem::take(&mut path[pos]).index();
+            used[v] = false;
+            if oriented && pos >= 2 && self.is_closer(path[0].index(), path[1].index(), v) {
+                open += 1;
+            }
+            tried = self.tried_up_to(path[pos - 1].index(), v);
+        }
+    }
+
+    /// Same as `Hankel::is_closer`.
+    fn is_closer(&self, first: usize, second: usize, w: usize) -> bool {
+        w > second && w != first && w % 2 == self.size() % 2 && self.adjacent(first, w)
+    }
+
+    /// Same as `Hankel::orient`, where `first` and `second` are the first
+    /// two vertices of the path, or `w` is the second one if `pos` is 1.
+    fn orient(
+        &self,
+        first: usize,
+        second: usize,
+        pos: usize,
+        w: usize,
+        open: usize,
+    ) -> Option<usize> {
+        if pos == 1 {
+            let closers = self
+                .neighbors(first)
+                .iter()
+                .filter(|&&v| self.is_closer(first, w, v as usize))
+                .count();
+            return (closers > 0).then_some(closers);
+        }
+        let closer = self.is_closer(first, second, w);
+        if pos + 1 == self.size() {
+            return closer.then(|| open - 1);
+        }
+        let open = open - usize::from(closer);
+        (open > 0).then_some(open)
+    }
+
+    /// The same check of the start of `path` as for the other searches.
+    pub(crate) fn valid_start<V: Vertex>(&self, path: &[V], pos: usize) -> bool {
+        let n = self.size();
+        let mut used = vec![false; n + 1];
+        path.len() == n
+            && (1..=n).contains(&pos)
+            && path[..pos].iter().enumerate().all(|(i, &v)| {
+                let v = v.index();
+                (1..=n).contains(&v) && !core::mem::replace(&mut used[v], true) && v % 2 != i % 2
+            })
+            && path[..pos]
+                .windows(2)
+                .all(|w| self.adjacent(w[0].index(), w[1].index()))
+    }
+}
+
+#[test]
+fn same_search_as_the_matrix() {
+    let limits = SearchLimits {
+        max_nodes: Some(5_000),
+        ..Default::default()
+    };
+    for n in [1, 2, 7, 10, 30, 64, 150] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        let adjacency = mat.adjacency();
+        assert_eq!(adjacency.size(), n);
+        for pos in [1, n / 2, n] {
+            let mut path = mat.is_hamiltonian().map_or_else(|| vec![1; n], Vec::from);
+            if pos == 0 {
+                continue;
+            }
+            path[pos..].fill(0);
+            let (mut expected, mut stats) = (path.clone(), SearchStats::default());
+            let result = mat.hamiltonian_cycle_iterative(&mut expected, pos, &limits, &mut stats);
+            let mut other = SearchStats::default();
+            assert_eq!(
+                adjacency.hamiltonian_cycle_iterative(&mut path, pos, &limits, &mut other),
+                result
+            );
+            assert_eq!(other.best_path, stats.best_path);
+            // The same search with a path of u32s.
+            let mut short = path.iter().map(|&v| v as u32).collect::<Vec<_>>();
+            short[pos..].fill(0);
+            let mut third = SearchStats::default();
+            assert_eq!(
+                adjacency.hamiltonian_cycle_iterative(&mut short, pos, &limits, &mut third),
+                result
+            );
+            assert!(short
+                .iter()
+                .map(|&v| v as usize)
+                .eq(expected.iter().copied()));
+            assert_eq!(
+                (third.nodes, third.best_path),
+                (stats.nodes, stats.best_path.clone())
+            );
+            assert_eq!((path, other.nodes), (expected, stats.nodes));
+        }
+    }
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(mat.adjacency().neighbors(1), [2, 4, 6]);
+}
+
+#[test]
+fn rarest_neighbors_first() {
+    let limits = SearchLimits::default();
+    for n in [2, 4, 10, 30, 64, 150] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        let adjacency = mat.adjacency_by_degree();
+        assert_eq!(adjacency.neighbors(1), mat.adjacency().neighbors(1));
+        let mut path = vec![0; n];
+        path[0] = 1;
+        let mut stats = SearchStats::default();
+        assert_eq!(
+            adjacency.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats),
+            SearchResult::Found
+        );
+        assert!(mat.valid_cycle(&path));
+    }
+    // 6 has the neighbors 1 and 5, of degrees 3 and 2.
+    let adjacency = Hankel::prime_sum_matrix(6, None).adjacency_by_degree();
+    assert_eq!(adjacency.candidate(6, 0), Some(5));
+    assert_eq!(adjacency.candidate(6, 1), Some(1));
+    assert_eq!(adjacency.tried_up_to(6, 5), 1);
+}
+
+#[test]
+fn cycle_removed_from_the_neighbors() {
+    let adjacency = Hankel::prime_sum_matrix(6, None).adjacency_by_degree();
+    let rest = adjacency.without_cycle(&[1, 4, 3, 2, 5, 6]);
+    assert_eq!(rest.neighbors(1), [2]);
+    assert_eq!(rest.candidate(2, 0), Some(1));
+}
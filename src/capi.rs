@@ -0,0 +1,138 @@
+//! A C interface, enabled with the `capi` feature. The declarations are in
+//! `include/pss.h`.
+//!
+//! The functions return one of the `PSS_*` codes. Vertices are 1-indexed,
+//! like in [`Hankel::get`].
+
+use core::ffi::c_int;
+use core::slice;
+
+use crate::{Hankel, SearchLimits, SearchResult, SearchStats};
+
+/// A cycle (or quadruplet) was found, or the cycle is valid.
+pub const PSS_FOUND: c_int = 1;
+/// There is no cycle (or quadruplet), or the cycle is not valid.
+pub const PSS_NOT_FOUND: c_int = 0;
+/// The search reached `max_nodes` before it finished.
+pub const PSS_ABORTED: c_int = -1;
+/// A pointer is null, or a size is invalid.
+pub const PSS_INVALID_ARGUMENT: c_int = -2;
+
+/// Searches for a Hamiltonian cycle in the prime sum graph of size `n`,
+/// and writes it to `out_buf`.
+///
+/// # Safety
+///
+/// `out_buf` should point to `len` writable `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn pss_find_cycle(n: usize, out_buf: *mut usize, len: usize) -> c_int {
+    pss_find_cycle_limited(n, 0, out_buf, len)
+}
+
+/// Same as [`pss_find_cycle`], but gives up after visiting `max_nodes`
+/// nodes, or never if it is 0.
+///
+/// # Safety
+///
+/// `out_buf` should point to `len` writable `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn pss_find_cycle_limited(
+    n: usize,
+    max_nodes: u64,
+    out_buf: *mut usize,
+    len: usize,
+) -> c_int {
+    if out_buf.is_null() || len < n {
+        return PSS_INVALID_ARGUMENT;
+    }
+    let Ok(mat) = Hankel::try_prime_sum_matrix(n, None) else {
+        return PSS_INVALID_ARGUMENT;
+    };
+    let mut path = vec![0; n];
+    path[0] = 1;
+    let limits = SearchLimits {
+        max_nodes: (max_nodes > 0).then_some(max_nodes),
+        ..Default::default()
+    };
+    let mut stats = SearchStats::default();
+    match mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats) {
+        SearchResult::Found => {
+            slice::from_raw_parts_mut(out_buf, n).copy_from_slice(&path);
+            PSS_FOUND
+        }
+        SearchResult::NotFound => PSS_NOT_FOUND,
+        SearchResult::Aborted => PSS_ABORTED,
+    }
+}
+
+/// Checks if the `len` vertices in `cycle` are a Hamiltonian cycle in
+/// the prime sum graph of size `len`.
+///
+/// # Safety
+///
+/// `cycle` should point to `len` readable `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn pss_verify_cycle(cycle: *const usize, len: usize) -> c_int {
+    if cycle.is_null() {
+        return PSS_INVALID_ARGUMENT;
+    }
+    let Ok(mat) = Hankel::try_prime_sum_matrix(len, None) else {
+        return PSS_INVALID_ARGUMENT;
+    };
+    match mat.valid_cycle(slice::from_raw_parts(cycle, len)) {
+        true => PSS_FOUND,
+        false => PSS_NOT_FOUND,
+    }
+}
+
+/// Finds primes p1 < p2 <= 2n for the size 2n, see
+/// [`crate::find_prime_quadruplet`], and writes them to `prime1` and `prime2`.
+///
+/// # Safety
+///
+/// `prime1` and `prime2` should point to writable `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn pss_find_prime_quadruplet(
+    half_size: usize,
+    prime1: *mut usize,
+    prime2: *mut usize,
+) -> c_int {
+    if prime1.is_null() || prime2.is_null() {
+        return PSS_INVALID_ARGUMENT;
+    }
+    match crate::try_find_prime_quadruplet(half_size, None) {
+        Ok(Some((p1, p2))) => {
+            *prime1 = p1;
+            *prime2 = p2;
+            PSS_FOUND
+        }
+        Ok(None) => PSS_NOT_FOUND,
+        Err(_) => PSS_INVALID_ARGUMENT,
+    }
+}
+
+#[test]
+fn find_and_verify() {
+    let mut cycle = [0; 12];
+    unsafe {
+        assert_eq!(pss_find_cycle(10, cycle.as_mut_ptr(), 12), PSS_FOUND);
+        assert_eq!(pss_verify_cycle(cycle.as_ptr(), 10), PSS_FOUND);
+        assert_eq!(pss_verify_cycle(cycle.as_ptr(), 9), PSS_NOT_FOUND);
+        assert_eq!(
+            pss_find_cycle(13, cycle.as_mut_ptr(), 12),
+            PSS_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            pss_find_cycle(0, core::ptr::null_mut(), 0),
+            PSS_INVALID_ARGUMENT
+        );
+        let mut big = vec![0; 2_000];
+        assert_eq!(
+            pss_find_cycle_limited(2_000, 10, big.as_mut_ptr(), 2_000),
+            PSS_ABORTED
+        );
+        let (mut p1, mut p2) = (0, 0);
+        assert_eq!(pss_find_prime_quadruplet(5, &mut p1, &mut p2), PSS_FOUND);
+        assert_eq!((p1, p2), (1, 3));
+    }
+}
@@ -0,0 +1,135 @@
+//! Cliques and independent sets. An independent set is a clique in the
+//! complement of the graph, so both are found with the same searches.
+//!
+//! Loops, where 2v is in the sequence, are ignored.
+
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns the size of the largest clique, which is only feasible for small sizes.
+    pub fn clique_number(&self) -> usize {
+        self.maximum_clique_by(|v, w| self.get_0_based(v, w) != 0)
+            .len()
+    }
+
+    /// Returns the size of the largest independent set, which is only feasible
+    /// for small sizes.
+    ///
+    /// A Hamiltonian cycle contains at most n / 2 vertices of an independent set,
+    /// so if it is larger, there is no Hamiltonian cycle.
+    pub fn independence_number(&self) -> usize {
+        self.maximum_clique_by(|v, w| self.get_0_based(v, w) == 0)
+            .len()
+    }
+
+    /// Returns a clique, found by repeatedly taking the vertex with the most
+    /// neighbors in the clique so far. Its size is a lower bound for
+    /// [`Hankel::clique_number`].
+    pub fn greedy_clique(&self) -> Vec<usize> {
+        self.greedy_clique_by(|v, w| self.get_0_based(v, w) != 0)
+            .into_iter()
+            .map(|v| v + 1)
+            .collect()
+    }
+
+    /// Returns an independent set, found by repeatedly taking the vertex with the
+    /// fewest neighbors left. Its size is a lower bound for
+    /// [`Hankel::independence_number`].
+    pub fn greedy_independent_set(&self) -> Vec<usize> {
+        self.greedy_clique_by(|v, w| self.get_0_based(v, w) == 0)
+            .into_iter()
+            .map(|v| v + 1)
+            .collect()
+    }
+
+    /// Returns a largest clique of the graph on the vertices 0 to n - 1
+    /// where `v` and `w` are adjacent if `adjacent(v, w)`.
+    ///
+    /// The greedy clique is the first candidate, and a branch is cut if it
+    /// can't give a larger one.
+    fn maximum_clique_by(&self, adjacent: impl Fn(usize, usize) -> bool) -> Vec<usize> {
+        let mut best = self.greedy_clique_by(&adjacent);
+        let mut candidates = (0..self.size).collect::<Vec<_>>();
+        // Vertices with many neighbors first, to find large cliques early.
+        candidates.sort_by_cached_key(|&v| {
+            core::cmp::Reverse((0..self.size).filter(|&w| w != v && adjacent(v, w)).count())
+        });
+        extend_clique(&adjacent, &mut Vec::new(), &candidates, &mut best);
+        best
+    }
+
+    /// Returns a clique of the graph like in [`Hankel::maximum_clique_by`],
+    /// found by repeatedly taking the candidate with the most neighbors
+    /// among the candidates, which are the vertices adjacent to the clique.
+    fn greedy_clique_by(&self, adjacent: impl Fn(usize, usize) -> bool) -> Vec<usize> {
+        let mut clique = Vec::new();
+        let mut candidates = (0..self.size).collect::<Vec<_>>();
+        while let Some(&v) = candidates.iter().max_by_key(|&&v| {
+            candidates
+                .iter()
+                .filter(|&&w| w != v && adjacent(v, w))
+                .count()
+        }) {
+            clique.push(v);
+            candidates.retain(|&w| w != v && adjacent(v, w));
+        }
+        clique.sort_unstable();
+        clique
+    }
+}
+
+/// Extends `clique` with the `candidates`, which are adjacent to every
+/// vertex in it, and replaces `best` with every larger clique found.
+fn extend_clique(
+    adjacent: &impl Fn(usize, usize) -> bool,
+    clique: &mut Vec<usize>,
+    candidates: &[usize],
+    best: &mut Vec<usize>,
+) {
+    if clique.len() > best.len() {
+        best.clone_from(clique);
+        best.sort_unstable();
+    }
+    for (i, &v) in candidates.iter().enumerate() {
+        if clique.len() + candidates.len() - i <= best.len() {
+            return;
+        }
+        let next = candidates[i + 1..]
+            .iter()
+            .copied()
+            .filter(|&w| adjacent(v, w))
+            .collect::<Vec<_>>();
+        clique.push(v);
+        extend_clique(adjacent, clique, &next, best);
+        clique.pop();
+    }
+}
+
+#[test]
+fn cliques_and_independent_sets() {
+    use alloc::vec;
+
+    // The prime sum graphs are bipartite, with the odd numbers on one side.
+    let mat = Hankel::prime_sum_matrix(12, None);
+    assert_eq!(mat.clique_number(), 2);
+    assert_eq!(mat.independence_number(), 6);
+    assert!(mat.greedy_independent_set().len() <= 6);
+    let clique = mat.greedy_clique();
+    assert_eq!(clique.len(), 2);
+    assert_eq!(mat.get(clique[0], clique[1]), 1);
+    // 1, 2 and 3 are a triangle, 4 is only adjacent to 1.
+    let triangle = Hankel::from_sequence(4, &[3, 4, 5]);
+    assert_eq!(triangle.clique_number(), 3);
+    assert_eq!(triangle.greedy_clique(), vec![1, 2, 3]);
+    assert_eq!(triangle.independence_number(), 2);
+    for graph in [triangle, mat] {
+        let set = graph.greedy_independent_set();
+        assert!(set
+            .iter()
+            .all(|&v| set.iter().all(|&w| v == w || graph.get(v, w) == 0)));
+    }
+    let empty = Hankel::from_sequence(0, &[]);
+    assert_eq!((empty.clique_number(), empty.independence_number()), (0, 0));
+}
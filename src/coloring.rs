@@ -0,0 +1,188 @@
+//! Proper edge colorings, where the edges of every color are a matching.
+//!
+//! The colors are kept in a table with, for every vertex and color, the
+//! neighbor joined by an edge of that color. The vertices are 0-based here.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns the edges `(i, j)` with `i < j` of every color of a proper
+    /// edge coloring, which has Δ or Δ + 1 colors, where Δ is the largest degree.
+    ///
+    /// A graph that is bipartite by parity, like the prime sum graphs, gets Δ
+    /// colors by swapping the colors along alternating paths. Other graphs get
+    /// at most Δ + 1 with the algorithm of Misra and Gries.
+    pub fn edge_coloring(&self) -> Vec<Vec<(usize, usize)>> {
+        let n = self.size;
+        let edges = self
+            .edges()
+            .map(|(i, j)| (i - 1, j - 1))
+            .collect::<Vec<_>>();
+        let mut degrees = vec![0; n];
+        for &(u, v) in &edges {
+            degrees[u] += 1;
+            degrees[v] += 1;
+        }
+        let max_degree = degrees.into_iter().max().unwrap_or(0);
+        let bipartite = self.is_bipartite_by_parity();
+        let mut colors = EdgeColors {
+            at: vec![vec![None; max_degree + usize::from(!bipartite)]; n],
+        };
+        for (u, v) in edges {
+            if bipartite {
+                colors.add_bipartite(u, v);
+            } else {
+                colors.add_with_fan(u, v);
+            }
+        }
+        let mut classes = vec![Vec::new(); colors.at.first().map_or(0, Vec::len)];
+        for (u, row) in colors.at.iter().enumerate() {
+            for (c, &v) in row.iter().enumerate() {
+                if let Some(v) = v.filter(|&v| u < v) {
+                    classes[c].push((u + 1, v + 1));
+                }
+            }
+        }
+        classes.retain(|class| !class.is_empty());
+        classes
+    }
+}
+
+/// The neighbor of every vertex by an edge of every color, if any.
+struct EdgeColors {
+    at: Vec<Vec<Option<usize>>>,
+}
+
+impl EdgeColors {
+    fn set(&mut self, u: usize, v: usize, c: usize) {
+        self.at[u][c] = Some(v);
+        self.at[v][c] = Some(u);
+    }
+
+    fn unset(&mut self, u: usize, v: usize, c: usize) {
+        self.at[u][c] = None;
+        self.at[v][c] = None;
+    }
+
+    fn is_free(&self, u: usize, c: usize) -> bool {
+        self.at[u][c].is_none()
+    }
+
+    /// Returns a color of no edge at `u`.
+    fn free(&self, u: usize) -> usize {
+        self.at[u]
+            .iter()
+            .position(Option::is_none)
+            .expect("there are more colors than edges at a vertex")
+    }
+
+    /// Returns the color of the edge between `u` and `v`, if it is colored.
+    fn color(&self, u: usize, v: usize) -> Option<usize> {
+        self.at[u].iter().position(|&w| w == Some(v))
+    }
+
+    /// Swaps the colors `c` and `d` along the path starting at `u` with an edge
+    /// of color `d`, where `c` is free at `u`.
+    fn invert_path(&mut self, u: usize, c: usize, d: usize) {
+        let mut path = Vec::new();
+        let (mut v, mut col) = (u, d);
+        while let Some(w) = self.at[v][col] {
+            path.push((v, w, col));
+            (v, col) = (w, if col == c { d } else { c });
+        }
+        for &(v, w, col) in &path {
+            self.unset(v, w, col);
+        }
+        for (v, w, col) in path {
+            self.set(v, w, if col == c { d } else { c });
+        }
+    }
+
+    /// Colors the edge between `u` and `v` in a bipartite graph, where
+    /// every vertex has less edges than colors.
+    fn add_bipartite(&mut self, u: usize, v: usize) {
+        let (a, b) = (self.free(u), self.free(v));
+        if !self.is_free(v, a) {
+            // The path from v alternating between a and b can't end in u,
+            // since it would have an even length, and end with an edge of b.
+            self.invert_path(v, b, a);
+        }
+        self.set(u, v, a);
+    }
+
+    /// Colors the edge between `x` and `v` with a rotation of the fan of `x`
+    /// starting at `v`, where every vertex has less edges than colors.
+    fn add_with_fan(&mut self, x: usize, v: usize) {
+        // Every next vertex of the fan is joined to x by an edge with a color
+        // that is free at the previous one.
+        let mut fan = vec![v];
+        loop {
+            let last = fan[fan.len() - 1];
+            let next = (0..self.at[x].len())
+                .find_map(|c| self.at[x][c].filter(|w| self.is_free(last, c) && !fan.contains(w)));
+            match next {
+                Some(w) => fan.push(w),
+                None => break,
+            }
+        }
+        let c = self.free(x);
+        let d = self.free(fan[fan.len() - 1]);
+        self.invert_path(x, c, d);
+        // Take the first vertex where d is free, as long as the start of
+        // the fan is still a fan.
+        let mut end = 0;
+        while !self.is_free(fan[end], d) {
+            let next = self
+                .color(x, fan[end + 1])
+                .expect("the fan has colored edges");
+            debug_assert!(self.is_free(fan[end], next));
+            end += 1;
+        }
+        for i in 0..end {
+            let next = self
+                .color(x, fan[i + 1])
+                .expect("the fan has colored edges");
+            self.unset(x, fan[i + 1], next);
+            self.set(x, fan[i], next);
+        }
+        self.set(x, fan[end], d);
+    }
+}
+
+#[test]
+fn proper_edge_colorings() {
+    let check = |mat: &Hankel, colors: usize| {
+        let classes = mat.edge_coloring();
+        assert_eq!(classes.len(), colors);
+        assert_eq!(
+            classes.iter().map(Vec::len).sum::<usize>(),
+            mat.edge_count()
+        );
+        for class in classes {
+            let mut touched = vec![false; mat.size + 1];
+            for (i, j) in class {
+                assert_eq!(mat.get(i, j), 1);
+                assert!(!touched[i] && !touched[j]);
+                (touched[i], touched[j]) = (true, true);
+            }
+        }
+    };
+    for n in [2, 10, 31, 64] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        check(&mat, mat.vertex_degrees().into_iter().max().unwrap());
+    }
+    // A triangle needs 3 colors, while every vertex has 2 edges.
+    check(&Hankel::from_sequence(3, &[3, 4, 5]), 3);
+    // The sums of two squares, which isn't bipartite.
+    let squares = (1..=60)
+        .filter(|&s| (0..=8).any(|a: usize| (0..=8).any(|b| a * a + b * b == s)))
+        .collect::<Vec<_>>();
+    let mat = Hankel::from_sequence(30, &squares);
+    let classes = mat.edge_coloring();
+    assert!(classes.len() <= mat.vertex_degrees().into_iter().max().unwrap() + 1);
+    check(&mat, classes.len());
+    assert!(Hankel::from_sequence(0, &[]).edge_coloring().is_empty());
+}
@@ -0,0 +1,125 @@
+//! The vertex and edge connectivity, found with maximum flows.
+//!
+//! Loops, where 2v is in the sequence, are ignored.
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns the smallest number of vertices whose removal disconnects the
+    /// graph, or leaves a single vertex. A Hamiltonian cycle only exists if
+    /// this is at least 2.
+    ///
+    /// This is the smallest number of vertex-disjoint paths between two
+    /// non-adjacent vertices. Only the first `k + 1` vertices need to be tried
+    /// as the first one, since one of them is not in a separator of size `k`.
+    pub fn vertex_connectivity(&self) -> usize {
+        let n = self.size;
+        let mut best = n.saturating_sub(1);
+        let mut s = 0;
+        while s < n && s <= best {
+            for t in (s + 1..n).filter(|&t| self.get_0_based(s, t) == 0) {
+                // Every vertex v is split into 2v, with the incoming edges,
+                // and 2v + 1, with the outgoing edges.
+                let mut capacity = vec![vec![0; 2 * n]; 2 * n];
+                for v in 0..n {
+                    capacity[2 * v][2 * v + 1] = if v == s || v == t { n } else { 1 };
+                    for w in (0..n).filter(|&w| w != v && self.get_0_based(v, w) != 0) {
+                        capacity[2 * v + 1][2 * w] = n;
+                    }
+                }
+                best = best.min(max_flow(&mut capacity, 2 * s + 1, 2 * t));
+            }
+            s += 1;
+        }
+        best
+    }
+
+    /// Returns the smallest number of edges whose removal disconnects the graph,
+    /// which is 0 if there are less than 2 vertices. A Hamiltonian cycle only
+    /// exists if this is at least 2.
+    ///
+    /// This is the smallest number of edge-disjoint paths from 1 to another vertex.
+    pub fn edge_connectivity(&self) -> usize {
+        let n = self.size;
+        (1..n)
+            .map(|t| {
+                let mut capacity = vec![vec![0; n]; n];
+                for (v, row) in capacity.iter_mut().enumerate() {
+                    for (w, c) in row.iter_mut().enumerate() {
+                        *c = usize::from(v != w && self.get_0_based(v, w) != 0);
+                    }
+                }
+                max_flow(&mut capacity, 0, t)
+            })
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// Returns the value of a maximum flow from `source` to `sink`, found with
+/// shortest augmenting paths. Afterwards `capacity` holds the residual
+/// capacities.
+pub(crate) fn max_flow(capacity: &mut [Vec<usize>], source: usize, sink: usize) -> usize {
+    let n = capacity.len();
+    let mut flow = 0;
+    let mut parent = vec![usize::MAX; n];
+    let mut queue = VecDeque::new();
+    loop {
+        parent.fill(usize::MAX);
+        parent[source] = source;
+        queue.clear();
+        queue.push_back(source);
+        while let Some(v) = queue.pop_front() {
+            for w in 0..n {
+                if parent[w] == usize::MAX && capacity[v][w] > 0 {
+                    parent[w] = v;
+                    queue.push_back(w);
+                }
+            }
+        }
+        if parent[sink] == usize::MAX {
+            return flow;
+        }
+        let mut bottleneck = usize::MAX;
+        let mut w = sink;
+        while w != source {
+            bottleneck = bottleneck.min(capacity[parent[w]][w]);
+            w = parent[w];
+        }
+        let mut w = sink;
+        while w != source {
+            capacity[parent[w]][w] -= bottleneck;
+            capacity[w][parent[w]] += bottleneck;
+            w = parent[w];
+        }
+        flow += bottleneck;
+    }
+}
+
+#[test]
+fn connectivity_of_small_graphs() {
+    // 1 has degree 2 in the prime sum graphs, with the neighbors 2 and 4 for n = 6.
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(mat.vertex_connectivity(), 2);
+    assert_eq!(mat.edge_connectivity(), 2);
+    // 4 - 1 - 2 - 3 is a path.
+    let path = Hankel::from_sequence(4, &[3, 5]);
+    assert_eq!(
+        (path.vertex_connectivity(), path.edge_connectivity()),
+        (1, 1)
+    );
+    let split = Hankel::from_sequence(4, &[3, 7]);
+    assert_eq!(
+        (split.vertex_connectivity(), split.edge_connectivity()),
+        (0, 0)
+    );
+    // Every vertex is adjacent to every other one.
+    let complete = Hankel::from_sequence(4, &[3, 4, 5, 6, 7]);
+    assert_eq!(complete.vertex_connectivity(), 3);
+    assert_eq!(complete.edge_connectivity(), 3);
+    assert_eq!(Hankel::from_sequence(1, &[]).edge_connectivity(), 0);
+}
@@ -0,0 +1,459 @@
+//! Cycles of other lengths than Hamiltonian cycles, Hamiltonian paths
+//! between given vertices, the paths with a given number of vertices, and
+//! Hamiltonian cycles without common edges.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Cycle, Hankel, Path, SearchLimits, SearchResult, SearchStats};
+
+impl Hankel {
+    /// Returns a cycle with `length` vertices, or `None` if there is none.
+    ///
+    /// Hamiltonian cycles are found with [`Hankel::is_hamiltonian`], the
+    /// shorter ones with a backtracking search where the first vertex is
+    /// the smallest. Cycles have at least 3 vertices.
+    pub fn cycle_of_length(&self, length: usize) -> Option<Vec<usize>> {
+        let mut cycle = Vec::new();
+        self.cycle_of_length_into(length, &mut cycle)
+            .then_some(cycle)
+    }
+
+    /// Same as [`Hankel::cycle_of_length`], but the cycle is written to
+    /// `cycle_buf`, so that its memory can be reused for the next search.
+    ///
+    /// Returns false if there is no such cycle, in which case the contents
+    /// of `cycle_buf` are unspecified.
+    pub fn cycle_of_length_into(&self, length: usize, cycle_buf: &mut Vec<usize>) -> bool {
+        if length < 3 || length > self.size {
+            return false;
+        }
+        // In a bipartite graph every cycle has an even length.
+        let bipartite = self.is_bipartite_by_parity();
+        if bipartite && length % 2 == 1 {
+            return false;
+        }
+        if bipartite && length == self.size {
+            return self.is_hamiltonian_into(cycle_buf);
+        }
+        let mut used = vec![false; self.size + 1];
+        (1..=self.size).any(|first| {
+            cycle_buf.clear();
+            cycle_buf.push(first);
+            used[first] = true;
+            let found = self.extend_to_cycle(cycle_buf, &mut used, length);
+            used[first] = false;
+            found
+        })
+    }
+
+    /// Returns the lengths of the cycles in the graph, in increasing order.
+    pub fn cycle_lengths_present(&self) -> Vec<usize> {
+        (3..=self.size)
+            .filter(|&length| self.cycle_of_length(length).is_some())
+            .collect()
+    }
+
+    /// Checks if the graph has a cycle of every length from 3 up to the size.
+    ///
+    /// Bipartite graphs, like the prime sum graphs, only have cycles of even
+    /// length, see [`Hankel::is_bipancyclic`] for those.
+    pub fn is_pancyclic(&self) -> bool {
+        self.size >= 3 && (3..=self.size).all(|length| self.cycle_of_length(length).is_some())
+    }
+
+    /// Checks if the graph has a cycle of every even length from 4 up to the size.
+    pub fn is_bipancyclic(&self) -> bool {
+        self.size >= 4
+            && (4..=self.size)
+                .step_by(2)
+                .all(|length| self.cycle_of_length(length).is_some())
+    }
+
+    /// Returns a Hamiltonian path from `from` to `to`, or `None` if there is none.
+    ///
+    /// In a bipartite graph the path alternates between the two sides, so if the
+    /// size is even the ends should be on different sides, and otherwise both on
+    /// the larger side. If not, `None` is returned without searching.
+    pub fn hamiltonian_path_between(&self, from: usize, to: usize) -> Option<Path> {
+        let mut path = Vec::new();
+        self.hamiltonian_path_between_into(from, to, &mut path)
+            .then(|| Path::found(path, self))
+    }
+
+    /// Same as [`Hankel::hamiltonian_path_between`], but the path is written
+    /// to `path_buf`, so that its memory can be reused for the next search.
+    ///
+    /// Returns false if there is no such path, in which case the contents
+    /// of `path_buf` are unspecified.
+    pub fn hamiltonian_path_between_into(
+        &self,
+        from: usize,
+        to: usize,
+        path_buf: &mut Vec<usize>,
+    ) -> bool {
+        let vertices = 1..=self.size;
+        if !vertices.contains(&from) || !vertices.contains(&to) || (from == to) != (self.size == 1)
+        {
+            return false;
+        }
+        path_buf.clear();
+        path_buf.push(from);
+        if self.size == 1 {
+            return true;
+        }
+        if self.is_bipartite_by_parity() {
+            // The odd numbers are the larger side.
+            let possible = match self.size % 2 {
+                0 => from % 2 != to % 2,
+                _ => from % 2 == 1 && to % 2 == 1,
+            };
+            if !possible {
+                return false;
+            }
+        }
+        let mut used = vec![false; self.size + 1];
+        used[from] = true;
+        used[to] = true;
+        self.extend_to_path(path_buf, &mut used, to)
+    }
+
+    /// Checks if there is a Hamiltonian path between every two vertices.
+    ///
+    /// A bipartite graph with at least 3 vertices never is, see
+    /// [`Hankel::is_hamiltonian_laceable`] for those.
+    pub fn is_hamiltonian_connected(&self) -> bool {
+        if self.size >= 3 && self.is_bipartite_by_parity() {
+            return false;
+        }
+        (1..=self.size).all(|from| {
+            (from + 1..=self.size).all(|to| self.hamiltonian_path_between(from, to).is_some())
+        })
+    }
+
+    /// Checks if there is a Hamiltonian path between every odd and every even
+    /// vertex, which is the analogue of [`Hankel::is_hamiltonian_connected`] for
+    /// bipartite graphs of even size, like the prime sum graphs.
+    pub fn is_hamiltonian_laceable(&self) -> bool {
+        self.size >= 2
+            && self.size.is_multiple_of(2)
+            && self.is_bipartite_by_parity()
+            && (1..=self.size).step_by(2).all(|from| {
+                (2..=self.size)
+                    .step_by(2)
+                    .all(|to| self.hamiltonian_path_between(from, to).is_some())
+            })
+    }
+
+    /// Returns a basis of the cycle space, with a fundamental cycle for every
+    /// edge that is not in a breadth-first spanning forest. The cycle of an
+    /// edge from i to j is the edge with the paths from i and j in the tree
+    /// to the vertex where they meet.
+    ///
+    /// Every cycle of the graph is a sum modulo 2 of the edges of some of
+    /// these, and there are m - n + c of them for m edges and c components.
+    /// Loops are left out.
+    pub fn cycle_space_basis(&self) -> Vec<Vec<usize>> {
+        let n = self.size;
+        let mut parent = vec![None; n + 1];
+        let mut depth = vec![0; n + 1];
+        let mut queue = alloc::collections::VecDeque::new();
+        for root in 1..=n {
+            if parent[root].is_some() {
+                continue;
+            }
+            parent[root] = Some(root);
+            queue.push_back(root);
+            while let Some(v) = queue.pop_front() {
+                for w in 1..=n {
+                    if parent[w].is_none() && self.get(v, w) != 0 {
+                        parent[w] = Some(v);
+                        depth[w] = depth[v] + 1;
+                        queue.push_back(w);
+                    }
+                }
+            }
+        }
+        let parent = parent
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect::<Vec<_>>();
+        self.edges()
+            .filter(|&(i, j)| parent[i] != j && parent[j] != i)
+            .map(|(i, j)| {
+                let (mut up, mut down) = (vec![i], vec![j]);
+                let (mut a, mut b) = (i, j);
+                while a != b {
+                    if depth[a] >= depth[b] {
+                        a = parent[a];
+                        up.push(a);
+                    } else {
+                        b = parent[b];
+                        down.push(b);
+                    }
+                }
+                // The vertex where they meet is at the end of both.
+                down.pop();
+                up.extend(down.into_iter().rev());
+                up
+            })
+            .collect()
+    }
+
+    /// Returns every path with `len` distinct vertices, in lexicographic
+    /// order, so a path and its reverse are both returned. Loops are left
+    /// out, and there are no paths without vertices.
+    ///
+    /// For the prime sum graphs these are the ways to arrange `len` distinct
+    /// numbers up to n such that every two consecutive ones add up to a
+    /// prime. The paths are found one at a time by a depth-first search.
+    pub fn chains(&self, len: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
+        let n = self.size;
+        let mut path = Vec::with_capacity(len);
+        let mut used = vec![false; n + 1];
+        // The next vertex to try at every position of the path.
+        let mut next = if (1..=n).contains(&len) {
+            vec![1]
+        } else {
+            Vec::new()
+        };
+        core::iter::from_fn(move || loop {
+            let start = *next.last()?;
+            let found =
+                (start..=n).find(|&w| !used[w] && path.last().is_none_or(|&v| self.get(v, w) != 0));
+            let Some(w) = found else {
+                next.pop();
+                if let Some(v) = path.pop() {
+                    used[v] = false;
+                }
+                continue;
+            };
+            *next.last_mut()? = w + 1;
+            if path.len() + 1 == len {
+                let mut chain = path.clone();
+                chain.push(w);
+                return Some(chain);
+            }
+            path.push(w);
+            used[w] = true;
+            next.push(1);
+        })
+    }
+
+    /// Checks if only odd sums are adjacent, in which case the graph is
+    /// bipartite between the odd and the even numbers.
+    pub(crate) fn is_bipartite_by_parity(&self) -> bool {
+        // The entries on an even diagonal have an even sum.
+        self.diagonals.iter().step_by(2).all(|&d| d == 0)
+    }
+
+    /// Extends `path` to a Hamiltonian path ending in `to`, which is
+    /// already marked as used. Returns false if that isn't possible.
+    fn extend_to_path(&self, path: &mut Vec<usize>, used: &mut [bool], to: usize) -> bool {
+        let last = path[path.len() - 1];
+        if path.len() + 1 == self.size {
+            if self.get(last, to) == 0 {
+                return false;
+            }
+            path.push(to);
+            return true;
+        }
+        for next in (1..=self.size).rev() {
+            if used[next] || self.get(last, next) == 0 {
+                continue;
+            }
+            path.push(next);
+            used[next] = true;
+            if self.extend_to_path(path, used, to) {
+                return true;
+            }
+            used[next] = false;
+            path.pop();
+        }
+        false
+    }
+
+    /// Extends `path` to a cycle with `length` vertices, which are all
+    /// larger than the first one. Returns false if that isn't possible.
+    fn extend_to_cycle(&self, path: &mut Vec<usize>, used: &mut [bool], length: usize) -> bool {
+        let first = path[0];
+        let last = path[path.len() - 1];
+        if path.len() == length {
+            return self.get(last, first) != 0;
+        }
+        for next in first + 1..=self.size {
+            if used[next] || self.get(last, next) == 0 {
+                continue;
+            }
+            // The last vertex also has to be adjacent to the first one.
+            if path.len() + 1 == length && self.get(next, first) == 0 {
+                continue;
+            }
+            path.push(next);
+            used[next] = true;
+            if self.extend_to_cycle(path, used, length) {
+                return true;
+            }
+            used[next] = false;
+            path.pop();
+        }
+        false
+    }
+
+    /// Tries to find `k` Hamiltonian cycles without common edges, by removing
+    /// the edges of every cycle that is found before searching for the next
+    /// one. Returns `None` if a search fails, which doesn't mean that there
+    /// are no such cycles, since other first cycles could leave more room.
+    ///
+    /// Like [`Hankel::is_hamiltonian`], the cycles start at 1 and alternate
+    /// between odd and even numbers. A failed search can take very long.
+    pub fn hamiltonian_decomposition(&self, k: usize) -> Option<Vec<Cycle>> {
+        let n = self.size;
+        let mut adjacency = self.adjacency();
+        let mut cycles = Vec::with_capacity(k);
+        let limits = SearchLimits::default();
+        for _ in 0..k {
+            let mut path = vec![0; n];
+            *path.first_mut()? = 1;
+            let mut stats = SearchStats::default();
+            if adjacency.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats)
+                != SearchResult::Found
+            {
+                return None;
+            }
+            adjacency = adjacency.without_cycle(&path);
+            cycles.push(Cycle::found(path, self));
+        }
+        Some(cycles)
+    }
+}
+
+#[test]
+fn cycle_lengths() {
+    let mat = Hankel::prime_sum_matrix(10, None);
+    assert_eq!(mat.cycle_lengths_present(), vec![4, 6, 8, 10]);
+    assert!(mat.is_bipancyclic());
+    assert!(!mat.is_pancyclic());
+    for length in [4, 6, 8, 10] {
+        let cycle = mat.cycle_of_length(length).unwrap();
+        assert_eq!(cycle.len(), length);
+        assert!(mat.valid_cycle(&cycle));
+    }
+    // 1 + 2, 1 + 3 and 2 + 3 are in the sequence, which gives a triangle.
+    let triangle = Hankel::from_sequence(3, &[3, 4, 5]);
+    assert_eq!(triangle.cycle_of_length(3), Some(vec![1, 2, 3]));
+    assert!(triangle.is_pancyclic());
+    assert!(Hankel::prime_sum_matrix(2, None)
+        .cycle_lengths_present()
+        .is_empty());
+}
+
+#[test]
+fn hamiltonian_paths_between() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let path = mat.hamiltonian_path_between(1, 6).unwrap();
+    assert_eq!((path[0], path[5]), (1, 6));
+    assert!(mat.valid_path(&path) && path.len() == 6);
+    // Both ends are odd, so the path can't alternate.
+    assert_eq!(mat.hamiltonian_path_between(1, 3), None);
+    assert!(!mat.is_hamiltonian_connected());
+    assert_eq!(mat.hamiltonian_path_between(0, 3), None);
+    let single = Hankel::from_sequence(1, &[]);
+    assert_eq!(
+        single.hamiltonian_path_between(1, 1).as_deref(),
+        Some(&[1][..])
+    );
+    // The triangle is Hamiltonian-connected, the path 1 - 2 - 3 isn't.
+    assert!(Hankel::from_sequence(3, &[3, 4, 5]).is_hamiltonian_connected());
+    assert!(!Hankel::from_sequence(3, &[3, 5]).is_hamiltonian_connected());
+    // 1 - 4 - 3 - 2 - 1 is a 4-cycle, so every odd and even vertex are joined.
+    assert!(Hankel::prime_sum_matrix(4, None).is_hamiltonian_laceable());
+    assert!(Hankel::prime_sum_matrix(10, None).is_hamiltonian_laceable());
+    assert!(!Hankel::from_sequence(4, &[3, 4, 5]).is_hamiltonian_laceable());
+}
+
+#[test]
+fn fundamental_cycles() {
+    for mat in [
+        Hankel::prime_sum_matrix(12, None),
+        Hankel::from_sequence(9, &[4, 5, 7, 11]),
+        Hankel::from_sequence(6, &[3, 7]),
+    ] {
+        let basis = mat.cycle_space_basis();
+        // The vertices of a component have the same row in the closure.
+        let mut components = mat.transitive_closure();
+        components.sort_unstable();
+        components.dedup();
+        assert_eq!(basis.len() + mat.size, mat.edge_count() + components.len());
+        for cycle in &basis {
+            assert!(cycle.len() >= 3);
+            assert!(cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .all(|(&v, &w)| mat.get(v, w) != 0));
+            let mut sorted = cycle.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(sorted.len(), cycle.len());
+        }
+    }
+    // 1 - 2 - 3 - 4 - 1 is the only cycle of the prime sum graph of size 4.
+    let basis = Hankel::prime_sum_matrix(4, None).cycle_space_basis();
+    assert_eq!(basis.len(), 1);
+    assert_eq!(basis[0].len(), 4);
+}
+
+#[test]
+fn fixed_length_chains() {
+    let mat = Hankel::prime_sum_matrix(10, None);
+    assert_eq!(mat.chains(1).count(), 10);
+    assert_eq!(mat.chains(2).count(), 2 * mat.edge_count());
+    let cherries = mat.degrees().iter().map(|d| d * (d - 1)).sum::<usize>();
+    assert_eq!(mat.chains(3).count(), cherries);
+    assert!(mat
+        .chains(5)
+        .all(|chain| chain.len() == 5 && mat.valid_path(&chain)));
+    // Every Hamiltonian cycle gives a path from each vertex, in both directions.
+    let closed = mat
+        .chains(10)
+        .filter(|chain| mat.valid_cycle(chain))
+        .count();
+    assert_eq!(closed as u64, 20 * mat.count_hamiltonian_cycles());
+    assert_eq!(
+        Hankel::from_sequence(4, &[3, 5])
+            .chains(4)
+            .collect::<Vec<_>>(),
+        vec![vec![3, 2, 1, 4], vec![4, 1, 2, 3]]
+    );
+    assert_eq!(mat.chains(0).count(), 0);
+    assert_eq!(mat.chains(11).count(), 0);
+}
+
+#[test]
+fn disjoint_hamiltonian_cycles() {
+    for n in [12, 20, 30] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        let cycles = mat.hamiltonian_decomposition(2).unwrap();
+        let mut edges = cycles
+            .iter()
+            .flat_map(|cycle| {
+                assert!(mat.valid_cycle(cycle));
+                (0..n).map(|i| {
+                    let (v, w) = (cycle[i], cycle[(i + 1) % n]);
+                    (v.min(w), v.max(w))
+                })
+            })
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges.dedup();
+        assert_eq!(edges.len(), 2 * n);
+    }
+    // Every vertex of the prime sum graph of size 6 but 1 and 2 has degree 2.
+    assert!(Hankel::prime_sum_matrix(6, None)
+        .hamiltonian_decomposition(2)
+        .is_none());
+    assert_eq!(
+        Hankel::prime_sum_matrix(6, None).hamiltonian_decomposition(0),
+        Some(Vec::new())
+    );
+}
@@ -0,0 +1,77 @@
+//! The distribution of the degrees, and which degree sequences are possible.
+//!
+//! A Hamiltonian cycle needs every vertex to have degree at least 2, and the
+//! vertices of degree 2 fix both of their edges, so the low degrees are the
+//! ones that matter for the searches.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns the number of vertices of every degree, so the entry at index d
+    /// is the number of vertices of degree d. The last entry is the maximum
+    /// degree, and a loop counts once, like in [`Hankel::degrees`].
+    pub fn degree_histogram(&self) -> Vec<usize> {
+        let Some(max) = self.degrees.iter().copied().max() else {
+            return Vec::new();
+        };
+        let mut histogram = vec![0; max + 1];
+        for &d in &self.degrees {
+            histogram[d] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the smallest degree, or `None` if the matrix is empty.
+    pub fn min_degree(&self) -> Option<usize> {
+        self.degrees.iter().copied().min()
+    }
+}
+
+/// Checks if `degrees` is the degree sequence of a graph without loops or
+/// multiple edges, with the theorem of Erdős and Gallai. The degrees can be
+/// in any order.
+///
+/// The sum has to be even, and for every k the k largest degrees have to add
+/// up to at most k(k - 1), for the edges between them, plus the sum of the
+/// other degrees, each at most k, for the edges to the others.
+pub fn is_graphical(degrees: &[usize]) -> bool {
+    let mut degrees = degrees.to_vec();
+    degrees.sort_unstable_by(|a, b| b.cmp(a));
+    if degrees.iter().sum::<usize>() % 2 == 1 {
+        return false;
+    }
+    let mut left = 0;
+    for k in 1..=degrees.len() {
+        left += degrees[k - 1];
+        let right = k * (k - 1) + degrees[k..].iter().map(|&d| d.min(k)).sum::<usize>();
+        if left > right {
+            return false;
+        }
+    }
+    true
+}
+
+#[test]
+fn degree_distributions() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    // The degrees are 3, 3, 2, 2, 2 and 2.
+    assert_eq!(mat.degree_histogram(), vec![0, 0, 4, 2]);
+    assert_eq!(mat.min_degree(), Some(2));
+    let empty = Hankel::from_sequence(0, &[]);
+    assert!(empty.degree_histogram().is_empty());
+    assert_eq!(empty.min_degree(), None);
+    for n in [1, 10, 101] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        assert_eq!(mat.degree_histogram().iter().sum::<usize>(), n);
+        assert!(is_graphical(mat.degrees()));
+    }
+    assert!(is_graphical(&[]));
+    assert!(is_graphical(&[1, 1, 2, 2]));
+    assert!(!is_graphical(&[1, 1, 1]));
+    // 3 vertices can't have degree 3 among 4 vertices if one has degree 0.
+    assert!(!is_graphical(&[3, 3, 3, 0]));
+    assert!(is_graphical(&[4, 1, 1, 1, 1, 0, 0, 0, 2]));
+}
@@ -0,0 +1,225 @@
+//! Distances in the graphs, found by breadth-first searches.
+//!
+//! Loops, where 2v is in the sequence, don't change any distance
+//! and aren't cycles here, like in [`Hankel::cycle_of_length`].
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns the distance from `vertex` to every vertex, where the first
+    /// entry is the distance to 1. Unreachable vertices have distance `None`.
+    ///
+    /// # Panics
+    ///
+    /// If `vertex` is not between 1 and the size.
+    pub fn bfs_distances(&self, vertex: usize) -> Vec<Option<usize>> {
+        assert!((1..=self.size).contains(&vertex), "no vertex {vertex}");
+        let mut dist = vec![None; self.size];
+        dist[vertex - 1] = Some(0);
+        let mut queue = VecDeque::from([vertex - 1]);
+        while let Some(v) = queue.pop_front() {
+            let next = dist[v].map(|d| d + 1);
+            for (w, d) in dist.iter_mut().enumerate() {
+                if d.is_none() && self.get_0_based(v, w) != 0 {
+                    *d = next;
+                    queue.push_back(w);
+                }
+            }
+        }
+        dist
+    }
+
+    /// Returns a shortest path from `from` to `to`, including both, or `None`
+    /// if `to` can't be reached.
+    ///
+    /// # Panics
+    ///
+    /// If `from` or `to` is not between 1 and the size.
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        assert!((1..=self.size).contains(&from), "no vertex {from}");
+        assert!((1..=self.size).contains(&to), "no vertex {to}");
+        // The vertex before every vertex on a shortest path from `from`.
+        let mut parent = vec![None; self.size];
+        parent[from - 1] = Some(from - 1);
+        let mut queue = VecDeque::from([from - 1]);
+        while let Some(v) = queue.pop_front() {
+            if v == to - 1 {
+                break;
+            }
+            for (w, p) in parent.iter_mut().enumerate() {
+                if p.is_none() && self.get_0_based(v, w) != 0 {
+                    *p = Some(v);
+                    queue.push_back(w);
+                }
+            }
+        }
+        parent[to - 1]?;
+        let mut path = vec![to];
+        while path[path.len() - 1] != from {
+            path.push(parent[path[path.len() - 1] - 1]? + 1);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns the distances between all the vertices, where row i is
+    /// [`Hankel::bfs_distances`] of vertex i + 1.
+    ///
+    /// The searches keep the reached vertices as bitsets, and reach the
+    /// neighbors of 64 vertices at a time, which takes about n³ / 64 steps.
+    pub fn all_pairs_distances(&self) -> Vec<Vec<Option<usize>>> {
+        let n = self.size;
+        let words = n.div_ceil(64);
+        let rows = self.bit_rows().collect::<Vec<_>>();
+        let mut reached = vec![0u64; words];
+        let mut frontier = vec![0u64; words];
+        let mut next = vec![0u64; words];
+        (0..n)
+            .map(|source| {
+                let mut dist = vec![None; n];
+                dist[source] = Some(0);
+                reached.fill(0);
+                frontier.fill(0);
+                reached[source / 64] |= 1 << (source % 64);
+                frontier[source / 64] |= 1 << (source % 64);
+                for d in 1.. {
+                    next.fill(0);
+                    for (w, &word) in frontier.iter().enumerate() {
+                        let mut bits = word;
+                        while bits != 0 {
+                            let v = 64 * w + bits.trailing_zeros() as usize;
+                            bits &= bits - 1;
+                            for (x, &r) in next.iter_mut().zip(&rows[v]) {
+                                *x |= r;
+                            }
+                        }
+                    }
+                    for (x, r) in next.iter_mut().zip(&mut reached) {
+                        *x &= !*r;
+                        *r |= *x;
+                    }
+                    if next.iter().all(|&x| x == 0) {
+                        break;
+                    }
+                    for v in (0..n).filter(|&v| next[v / 64] >> (v % 64) & 1 == 1) {
+                        dist[v] = Some(d);
+                    }
+                    core::mem::swap(&mut frontier, &mut next);
+                }
+                dist
+            })
+            .collect()
+    }
+
+    /// Returns the largest distance from `vertex` to another vertex, or `None`
+    /// if some vertex can't be reached. See [`Hankel::bfs_distances`].
+    pub fn eccentricity(&self, vertex: usize) -> Option<usize> {
+        self.bfs_distances(vertex)
+            .into_iter()
+            .try_fold(0, |max, d| d.map(|d| max.max(d)))
+    }
+
+    /// Returns the largest distance between two vertices, or `None` if the
+    /// graph is not connected or empty.
+    ///
+    /// A Hamiltonian cycle visits every vertex within n / 2 steps, so a larger
+    /// diameter rules it out without searching.
+    pub fn diameter(&self) -> Option<usize> {
+        (1..=self.size)
+            .map(|v| self.eccentricity(v))
+            .try_fold(None, |max: Option<usize>, e| {
+                e.map(|e| Some(max.map_or(e, |m| m.max(e))))
+            })?
+    }
+
+    /// Returns the length of the shortest cycle, or `None` if there is no cycle.
+    ///
+    /// A breadth-first search is done from every vertex. The first edge found
+    /// between two vertices which are already reached closes a shortest cycle
+    /// through the start, or one which is at most one longer.
+    pub fn girth(&self) -> Option<usize> {
+        let n = self.size;
+        let mut girth = None::<usize>;
+        let mut dist = vec![usize::MAX; n];
+        let mut parent = vec![usize::MAX; n];
+        let mut queue = VecDeque::new();
+        for start in 0..n {
+            dist.fill(usize::MAX);
+            dist[start] = 0;
+            queue.clear();
+            queue.push_back(start);
+            'search: while let Some(v) = queue.pop_front() {
+                // Longer cycles through `start` can't improve the girth.
+                if girth.is_some_and(|g| 2 * dist[v] + 1 >= g) {
+                    break;
+                }
+                for w in (0..n).filter(|&w| w != v && self.get_0_based(v, w) != 0) {
+                    if dist[w] == usize::MAX {
+                        dist[w] = dist[v] + 1;
+                        parent[w] = v;
+                        queue.push_back(w);
+                    } else if parent[v] != w {
+                        let length = dist[v] + dist[w] + 1;
+                        girth = Some(girth.map_or(length, |g| g.min(length)));
+                        break 'search;
+                    }
+                }
+            }
+        }
+        girth
+    }
+}
+
+#[test]
+fn girth_of_small_graphs() {
+    // The prime sum graphs are bipartite, and 1 - 2 - 3 - 4 - 1 is a cycle.
+    assert_eq!(Hankel::prime_sum_matrix(10, None).girth(), Some(4));
+    // 4 - 1 - 2 - 3 is a path.
+    assert_eq!(Hankel::from_sequence(4, &[3, 5]).girth(), None);
+    assert_eq!(Hankel::from_sequence(5, &[3, 4, 5]).girth(), Some(3));
+    // 1 - 4 - 2 - 3 - 1 and the loops at 2 and 3.
+    assert_eq!(Hankel::from_sequence(4, &[4, 5, 6]).girth(), Some(4));
+    assert_eq!(Hankel::from_sequence(0, &[]).girth(), None);
+}
+
+#[test]
+fn distances_and_diameter() {
+    // 4 - 1 - 2 - 3 is a path.
+    let path = Hankel::from_sequence(4, &[3, 5]);
+    assert_eq!(
+        path.bfs_distances(4),
+        vec![Some(1), Some(2), Some(3), Some(0)]
+    );
+    assert_eq!(path.eccentricity(1), Some(2));
+    assert_eq!(path.diameter(), Some(3));
+    // 1 - 2 and 3 - 4 aren't connected.
+    let split = Hankel::from_sequence(4, &[3, 7]);
+    assert_eq!(split.bfs_distances(1), vec![Some(0), Some(1), None, None]);
+    assert_eq!(split.eccentricity(1), None);
+    assert_eq!(split.diameter(), None);
+    assert_eq!(Hankel::from_sequence(0, &[]).diameter(), None);
+    assert_eq!(path.shortest_path(4, 3), Some(vec![4, 1, 2, 3]));
+    assert_eq!(path.shortest_path(2, 2), Some(vec![2]));
+    assert_eq!(split.shortest_path(1, 4), None);
+    for mat in [&path, &split, &Hankel::prime_sum_matrix(130, None)] {
+        let distances = mat.all_pairs_distances();
+        for (i, row) in distances.iter().enumerate() {
+            assert_eq!(*row, mat.bfs_distances(i + 1));
+        }
+    }
+    assert!(Hankel::from_sequence(0, &[])
+        .all_pairs_distances()
+        .is_empty());
+    let mat = Hankel::prime_sum_matrix(10, None);
+    assert!(mat.diameter().unwrap() <= 10 / 2);
+    // 5 + 12 = 17, and the sums along the path are prime.
+    let mat = Hankel::prime_sum_matrix(12, None);
+    assert_eq!(mat.shortest_path(5, 12), Some(vec![5, 12]));
+    let path = mat.shortest_path(1, 9).unwrap();
+    assert_eq!(path.len() - 1, mat.bfs_distances(1)[8].unwrap());
+    assert!(mat.valid_path(&path));
+}
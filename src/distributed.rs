@@ -0,0 +1,430 @@
+//! The `serve` and `worker` subcommands, which spread the search over
+//! multiple machines.
+//!
+//! The coordinator (`serve`) splits the sizes into ranges and hands them
+//! out to the workers. Every message is a UTF-8 string preceded by its
+//! length as a big-endian `u32`. A worker sends `REQUEST` to ask for work,
+//! to which the coordinator answers with one of:
+//!
+//! - `RANGE <start> <end>`: check the even sizes from `start` upto and including `end`.
+//! - `WAIT`: no work right now, but another worker might still fail, so ask again later.
+//! - `DONE`: all the sizes are checked, so the worker can stop.
+//!
+//! While working on a range, the worker sends `CYCLE <n> <v1>,<v2>,...` with the
+//! found cycle as a certificate, or `FAILED <n>` or `TIMEOUT <n>` for every size
+//! and finally `FINISHED` when the range is done. If a worker disconnects, or stays
+//! silent for too long, the sizes of its range that were not reported yet are handed
+//! out again.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use clap::Args;
+use primes::{extend_primes_upto_n, Hankel};
+
+use crate::{
+    test_for_cycles, Outcome, SearchConfig, SearchStrategy, EXIT_ABORTED, EXIT_FAILED, EXIT_IO,
+    EXIT_USAGE,
+};
+
+/// Messages longer than this are rejected, to avoid allocating
+/// huge buffers because of a corrupted length.
+const MAX_MESSAGE_LENGTH: u32 = 1 << 30;
+
+/// Writes `message` preceded by its length.
+fn write_message<W: Write>(output: &mut W, message: &str) -> io::Result<()> {
+    let length = u32::try_from(message.len())
+        .ok()
+        .filter(|&l| l <= MAX_MESSAGE_LENGTH)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "message is too long"))?;
+    output.write_all(&length.to_be_bytes())?;
+    output.write_all(message.as_bytes())?;
+    output.flush()
+}
+
+/// Reads a message written by [`write_message`].
+fn read_message<R: Read>(input: &mut R) -> io::Result<String> {
+    let mut length = [0; 4];
+    input.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length);
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(invalid_data("message is too long"));
+    }
+    let mut message = vec![0; length as usize];
+    input.read_exact(&mut message)?;
+    String::from_utf8(message).map_err(|_| invalid_data("message is not valid UTF-8"))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Parses the number in a message.
+fn parse_number(word: Option<&str>) -> io::Result<usize> {
+    word.and_then(|w| w.parse().ok())
+        .ok_or_else(|| invalid_data("expected a number"))
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ServeArgs {
+    /// Address to listen on for workers
+    #[arg(short, long, default_value = "0.0.0.0:7878")]
+    listen: String,
+    /// Sequence length to start at
+    #[arg(short, long, default_value_t = 12)]
+    start: usize,
+    /// Maximum sequence length to search for
+    #[arg(short, long)]
+    max: usize,
+    /// Number of sizes handed out to a worker at once
+    #[arg(short, long, default_value_t = 100)]
+    chunk: usize,
+    /// Seconds without any message after which a worker is considered dead
+    #[arg(short, long, default_value_t = 600)]
+    timeout: u64,
+    /// Write the received cycles to this file
+    #[arg(long)]
+    certificates: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct WorkerArgs {
+    /// Address of the coordinator
+    #[arg(short, long)]
+    connect: String,
+    /// Number of connections to the coordinator, each searching on its own thread
+    #[arg(short = 'n', long = "threads", default_value_t = 1)]
+    num_threads: usize,
+    /// Skip a size if no cycle is found within this many seconds
+    #[arg(long, value_name = "SECS")]
+    time_limit_per_n: Option<f64>,
+    /// Skip a size if no cycle is found after visiting this many nodes
+    #[arg(long, value_name = "NODES")]
+    max_nodes: Option<u64>,
+}
+
+/// Keeps track of which sizes still have to be checked.
+#[derive(Default)]
+struct Scheduler {
+    /// Ranges of even sizes, including both ends, that were not handed out yet.
+    pending: VecDeque<(usize, usize)>,
+    /// The number of ranges that are being worked on.
+    in_progress: usize,
+    verified: usize,
+    failed: Vec<usize>,
+    timeouts: Vec<usize>,
+}
+
+impl Scheduler {
+    fn finished(&self) -> bool {
+        self.pending.is_empty() && self.in_progress == 0
+    }
+}
+
+/// The state shared by all the connections to the coordinator.
+struct Coordinator {
+    scheduler: Mutex<Scheduler>,
+    primes: Vec<usize>,
+    certificates: Option<Mutex<BufWriter<File>>>,
+}
+
+impl Coordinator {
+    /// Checks that `cycle` is a Hamiltonian cycle of length `n`.
+    fn verify(&self, n: usize, cycle: &[usize]) -> bool {
+        if cycle.len() != n {
+            return false;
+        }
+        let mut seen = vec![false; n + 1];
+        for &v in cycle {
+            if v == 0 || v > n || seen[v] {
+                return false;
+            }
+            seen[v] = true;
+        }
+        Hankel::prime_sum_matrix(n, Some(&self.primes)).valid_cycle(cycle)
+    }
+
+    /// Handles the messages of a single worker until it disconnects.
+    ///
+    /// If the worker was still working on a range, the sizes it did not
+    /// report yet are put back, so another worker can pick them up.
+    fn handle(&self, stream: TcpStream, timeout: Duration) {
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "unknown".to_string(), |a| a.to_string());
+        // The range being worked on, and the next size that should be reported.
+        let mut assigned: Option<(usize, usize)> = None;
+        let mut next = 0;
+        if let Err(e) = self.serve_worker(stream, timeout, &mut assigned, &mut next) {
+            eprintln!("Lost worker {}: {}", peer, e);
+        }
+        if let Some((_, end)) = assigned {
+            let mut scheduler = self.scheduler.lock().unwrap();
+            if next <= end {
+                scheduler.pending.push_front((next, end));
+            }
+            scheduler.in_progress -= 1;
+        }
+    }
+
+    fn serve_worker(
+        &self,
+        stream: TcpStream,
+        timeout: Duration,
+        assigned: &mut Option<(usize, usize)>,
+        next: &mut usize,
+    ) -> io::Result<()> {
+        stream.set_read_timeout(Some(timeout))?;
+        let mut output = stream.try_clone()?;
+        let mut input = BufReader::new(stream);
+        loop {
+            let message = read_message(&mut input)?;
+            let mut words = message.split(' ');
+            match (words.next(), *assigned) {
+                (Some("REQUEST"), None) => {
+                    let mut scheduler = self.scheduler.lock().unwrap();
+                    if let Some((start, end)) = scheduler.pending.pop_front() {
+                        scheduler.in_progress += 1;
+                        *assigned = Some((start, end));
+                        *next = start;
+                        write_message(&mut output, &format!("RANGE {} {}", start, end))?;
+                    } else if scheduler.finished() {
+                        write_message(&mut output, "DONE")?;
+                        return Ok(());
+                    } else {
+                        write_message(&mut output, "WAIT")?;
+                    }
+                }
+                (Some(kind @ ("CYCLE" | "FAILED" | "TIMEOUT")), Some(_)) => {
+                    let n = parse_number(words.next())?;
+                    if n != *next {
+                        return Err(invalid_data("unexpected size"));
+                    }
+                    if kind == "CYCLE" {
+                        let certificate = words.next().unwrap_or("");
+                        let cycle = certificate
+                            .split(',')
+                            .map(|v| parse_number(Some(v)))
+                            .collect::<io::Result<Vec<_>>>()?;
+                        if !self.verify(n, &cycle) {
+                            return Err(invalid_data("invalid cycle"));
+                        }
+                        if let Some(file) = &self.certificates {
+                            let mut file = file.lock().unwrap();
+                            writeln!(file, "{}\t{}", n, certificate)?;
+                        }
+                    }
+                    let mut scheduler = self.scheduler.lock().unwrap();
+                    match kind {
+                        "CYCLE" => scheduler.verified += 1,
+                        "FAILED" => scheduler.failed.push(n),
+                        _ => scheduler.timeouts.push(n),
+                    }
+                    *next = n + 2;
+                }
+                (Some("FINISHED"), Some((_, end))) => {
+                    if *next <= end {
+                        return Err(invalid_data("not all sizes were reported"));
+                    }
+                    self.scheduler.lock().unwrap().in_progress -= 1;
+                    *assigned = None;
+                }
+                _ => return Err(invalid_data("unexpected message")),
+            }
+        }
+    }
+}
+
+/// Runs the coordinator, until all the sizes are checked.
+pub(crate) fn serve(args: &ServeArgs) -> ExitCode {
+    if args.start < 2 || args.start > args.max {
+        eprintln!("The start should be at least 2 and at most the max");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let start = args.start + args.start % 2;
+    let mut scheduler = Scheduler::default();
+    let mut range_start = start;
+    while range_start <= args.max {
+        let range_end = (range_start + 2 * (args.chunk.max(1) - 1)).min(args.max);
+        scheduler.pending.push_back((range_start, range_end));
+        range_start = range_end - range_end % 2 + 2;
+    }
+    let certificates = match &args.certificates {
+        Some(path) => match File::create(path) {
+            Ok(file) => Some(Mutex::new(BufWriter::new(file))),
+            Err(e) => {
+                eprintln!("Failed to create {}: {}", path.display(), e);
+                return ExitCode::from(EXIT_IO);
+            }
+        },
+        None => None,
+    };
+    let mut primes = Vec::new();
+    extend_primes_upto_n(&mut primes, 2 * args.max);
+    let coordinator = Coordinator {
+        scheduler: Mutex::new(scheduler),
+        primes,
+        certificates,
+    };
+    let listener = match TcpListener::bind(&args.listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {}", args.listen, e);
+            return ExitCode::from(EXIT_IO);
+        }
+    };
+    println!("Listening on {}", args.listen);
+    let timeout = Duration::from_secs(args.timeout.max(1));
+    let result = thread::scope(|s| -> io::Result<()> {
+        // Poll, so we can stop accepting workers once everything is done.
+        listener.set_nonblocking(true)?;
+        while !coordinator.scheduler.lock().unwrap().finished() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    let coordinator = &coordinator;
+                    s.spawn(move || coordinator.handle(stream, timeout));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to accept workers: {}", e);
+        return ExitCode::from(EXIT_IO);
+    }
+    if let Some(file) = &coordinator.certificates {
+        if let Err(e) = file.lock().unwrap().flush() {
+            eprintln!("Failed to write the certificates: {}", e);
+            return ExitCode::from(EXIT_IO);
+        }
+    }
+    let scheduler = coordinator.scheduler.into_inner().unwrap();
+    println!("Verified cycles for {} sizes", scheduler.verified);
+    if !scheduler.failed.is_empty() {
+        println!("No cycle found for the sizes {:?}", scheduler.failed);
+        ExitCode::from(EXIT_FAILED)
+    } else if !scheduler.timeouts.is_empty() {
+        println!(
+            "Reached the time limit for the sizes {:?}",
+            scheduler.timeouts
+        );
+        ExitCode::from(EXIT_ABORTED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Runs a worker, until the coordinator has no more work.
+pub(crate) fn work(args: &WorkerArgs) -> ExitCode {
+    let time_limit = args.time_limit_per_n.map(Duration::from_secs_f64);
+    let failed = AtomicBool::new(false);
+    thread::scope(|s| {
+        for _ in 0..args.num_threads.max(1) {
+            s.spawn(|| {
+                if let Err(e) = work_on_connection(&args.connect, time_limit, args.max_nodes) {
+                    eprintln!("Worker stopped: {}", e);
+                    failed.store(true, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    if failed.load(Ordering::Relaxed) {
+        ExitCode::from(EXIT_IO)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Asks the coordinator at `address` for ranges and checks them,
+/// until there is no more work.
+fn work_on_connection(
+    address: &str,
+    time_limit: Option<Duration>,
+    max_nodes: Option<u64>,
+) -> io::Result<()> {
+    let stream = TcpStream::connect(address)?;
+    let output = Mutex::new(stream.try_clone()?);
+    let mut input = BufReader::new(stream);
+    let mut primes = Vec::new();
+    loop {
+        write_message(&mut *output.lock().unwrap(), "REQUEST")?;
+        let message = read_message(&mut input)?;
+        let mut words = message.split(' ');
+        match words.next() {
+            Some("RANGE") => {
+                let start = parse_number(words.next())?;
+                let end = parse_number(words.next())?;
+                extend_primes_upto_n(&mut primes, 2 * end - 1);
+                let sink = |n: usize, outcome: Outcome, witness: &[usize]| {
+                    let message = match outcome {
+                        Outcome::Cycle => {
+                            let cycle = witness
+                                .iter()
+                                .map(|v| v.to_string())
+                                .collect::<Vec<_>>()
+                                .join(",");
+                            format!("CYCLE {} {}", n, cycle)
+                        }
+                        Outcome::Timeout | Outcome::OutOfNodes => format!("TIMEOUT {}", n),
+                        _ => format!("FAILED {}", n),
+                    };
+                    write_message(&mut *output.lock().unwrap(), &message)
+                };
+                let config = SearchConfig {
+                    divisor: 0,
+                    include_odd: false,
+                    primes: &primes,
+                    sequence: None,
+                    failures: None,
+                    quiet: false,
+                    explain: false,
+                    time_limit,
+                    max_nodes,
+                    stop: AtomicBool::new(false),
+                    sink: Some(&sink),
+                    cross_check: false,
+                    verify_construction: false,
+                    monitor: None,
+                    degree_stats: false,
+                    checkpoint: None,
+                    quadruplets: None,
+                    strategy: SearchStrategy::Backtracking,
+                    seed: 0,
+                };
+                test_for_cycles(&config, &AtomicUsize::new(start), end, 0);
+                if config.stopped() {
+                    return Err(io::Error::other("lost the connection to the coordinator"));
+                }
+                write_message(&mut *output.lock().unwrap(), "FINISHED")?;
+            }
+            Some("WAIT") => thread::sleep(Duration::from_secs(1)),
+            Some("DONE") => return Ok(()),
+            _ => return Err(invalid_data("unexpected message")),
+        }
+    }
+}
+
+#[test]
+fn message_round_trip() {
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, "RANGE 10 20").unwrap();
+    write_message(&mut buffer, "").unwrap();
+    assert_eq!(&buffer[..4], &[0, 0, 0, 11]);
+    let mut input = &buffer[..];
+    assert_eq!(read_message(&mut input).unwrap(), "RANGE 10 20");
+    assert_eq!(read_message(&mut input).unwrap(), "");
+    assert!(read_message(&mut input).is_err());
+}
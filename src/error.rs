@@ -0,0 +1,92 @@
+use core::fmt;
+
+/// The errors returned by the fallible functions of the library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The size is smaller than the function supports.
+    SizeTooSmall { size: usize, minimum: usize },
+    /// The size is so large that the arithmetic on it would overflow.
+    SizeTooLarge { size: usize, maximum: usize },
+    /// The table of primes does not contain all the primes up to `needed`.
+    InsufficientPrimes { needed: usize },
+    /// The primes do not satisfy the conditions of [`crate::HamiltonianCycle::new`].
+    InvalidQuadruplet {
+        prime1: usize,
+        prime2: usize,
+        half_size: usize,
+    },
+    /// The number of diagonals is not the one for the size, see
+    /// [`crate::Hankel::from_raw_parts`].
+    InvalidDiagonals { expected: usize, len: usize },
+    /// An entry of the diagonals is not 0 or 1.
+    InvalidEntry { index: usize, value: u8 },
+    /// The vertex is not between 1 and the size of the graph.
+    InvalidVertex { vertex: usize, size: usize },
+    /// The matrices that are combined have different sizes.
+    SizeMismatch { left: usize, right: usize },
+    /// The matrices that are combined have different kinds, see
+    /// [`crate::SumOrDiff`].
+    KindMismatch {
+        left: crate::SumOrDiff,
+        right: crate::SumOrDiff,
+    },
+    /// The vertices are not a Hamiltonian path of the graph, see [`crate::Path`].
+    InvalidPath,
+    /// The vertices are not a Hamiltonian cycle of the graph, see [`crate::Cycle`].
+    InvalidCycle,
+    /// The vertex at `index` can't be added to a [`crate::PartialPath`].
+    InvalidPrefix { index: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SizeTooSmall { size, minimum } => {
+                write!(f, "the size {} is smaller than {}", size, minimum)
+            }
+            Error::SizeTooLarge { size, maximum } => {
+                write!(f, "the size {} is larger than {}", size, maximum)
+            }
+            Error::InsufficientPrimes { needed } => {
+                write!(f, "the primes should be given up to at least {}", needed)
+            }
+            Error::InvalidQuadruplet {
+                prime1,
+                prime2,
+                half_size,
+            } => write!(
+                f,
+                "{} and {} do not give a Hamiltonian cycle of size {}",
+                prime1,
+                prime2,
+                2 * half_size
+            ),
+            Error::InvalidDiagonals { expected, len } => {
+                write!(f, "expected {} diagonals, but got {}", expected, len)
+            }
+            Error::InvalidEntry { index, value } => {
+                write!(f, "the diagonal {} is {} instead of 0 or 1", index, value)
+            }
+            Error::InvalidVertex { vertex, size } => {
+                write!(f, "the vertex {} is not between 1 and {}", vertex, size)
+            }
+            Error::SizeMismatch { left, right } => {
+                write!(f, "the sizes {} and {} are different", left, right)
+            }
+            Error::KindMismatch { left, right } => {
+                write!(f, "the kinds {:?} and {:?} are different", left, right)
+            }
+            Error::InvalidPath => write!(f, "the vertices are not a Hamiltonian path"),
+            Error::InvalidCycle => write!(f, "the vertices are not a Hamiltonian cycle"),
+            Error::InvalidPrefix { index } => {
+                write!(
+                    f,
+                    "the vertex at index {} can't be added to the path",
+                    index
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for Error {}
@@ -0,0 +1,118 @@
+//! Walks that use every edge once, the Eulerian counterpart of the
+//! Hamiltonian cycles. Loops are left out, like in [`Hankel::edges`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Checks if there is an Eulerian circuit, so if every vertex has an even
+    /// number of neighbors and the edges are connected. A graph without
+    /// edges has none.
+    pub fn is_eulerian(&self) -> bool {
+        self.eulerian_circuit().is_some()
+    }
+
+    /// Returns a closed walk which uses every edge once, with Hierholzer's
+    /// algorithm, or `None` if there is none. The first vertex is repeated
+    /// at the end.
+    pub fn eulerian_circuit(&self) -> Option<Vec<usize>> {
+        if !self.odd_vertices().is_empty() {
+            return None;
+        }
+        let start = (1..=self.size).find(|&v| self.neighbor_count(v) > 0)?;
+        self.eulerian_trail(start)
+    }
+
+    /// Returns a walk which uses every edge once, or `None` if there is none.
+    /// If two vertices have an odd number of neighbors, the walk goes from
+    /// the smaller to the larger one, otherwise it is an Eulerian circuit.
+    pub fn eulerian_path(&self) -> Option<Vec<usize>> {
+        match self.odd_vertices()[..] {
+            [] => self.eulerian_circuit(),
+            [start, _] => self.eulerian_trail(start),
+            _ => None,
+        }
+    }
+
+    /// Returns the vertices with an odd number of neighbors other than itself.
+    fn odd_vertices(&self) -> Vec<usize> {
+        (1..=self.size)
+            .filter(|&v| self.neighbor_count(v) % 2 == 1)
+            .collect()
+    }
+
+    /// Returns the degree of `v` without its loop.
+    fn neighbor_count(&self, v: usize) -> usize {
+        self.degrees[v - 1] - usize::from(self.get(v, v) != 0)
+    }
+
+    /// Returns the walk of Hierholzer's algorithm from `start`, or `None`
+    /// if it doesn't use every edge, because they aren't connected.
+    fn eulerian_trail(&self, start: usize) -> Option<Vec<usize>> {
+        let edges = self.edges().collect::<Vec<_>>();
+        // The edges at every vertex, by their index in `edges`.
+        let mut incident = vec![Vec::new(); self.size + 1];
+        for (e, &(i, j)) in edges.iter().enumerate() {
+            incident[i].push(e);
+            incident[j].push(e);
+        }
+        let mut used = vec![false; edges.len()];
+        let mut stack = vec![start];
+        let mut walk = Vec::with_capacity(edges.len() + 1);
+        while let Some(&v) = stack.last() {
+            // Drop the edges that were used from the other end.
+            while incident[v].last().is_some_and(|&e| used[e]) {
+                incident[v].pop();
+            }
+            match incident[v].pop() {
+                Some(e) => {
+                    used[e] = true;
+                    let (i, j) = edges[e];
+                    stack.push(if i == v { j } else { i });
+                }
+                None => walk.extend(stack.pop()),
+            }
+        }
+        walk.reverse();
+        (walk.len() == edges.len() + 1).then_some(walk)
+    }
+}
+
+#[test]
+fn eulerian_walks() {
+    // 1 + 2, 2 + 3, 3 + 4 and 4 + 1 are odd, but 1 + 3 and 2 + 4 aren't.
+    let square = Hankel::from_sequence(4, &[3, 5, 7]);
+    assert!(square.is_eulerian());
+    let circuit = square.eulerian_circuit().unwrap();
+    assert_eq!(circuit.len(), 5);
+    assert_eq!(circuit[0], circuit[4]);
+    // 4 - 1 - 2 - 3 is a path, so only the path is Eulerian.
+    let path = Hankel::from_sequence(4, &[3, 5]);
+    assert!(!path.is_eulerian());
+    assert_eq!(path.eulerian_circuit(), None);
+    let walk = path.eulerian_path().unwrap();
+    assert_eq!(walk, vec![3, 2, 1, 4]);
+    for mat in [
+        Hankel::prime_sum_matrix(12, None),
+        Hankel::from_sequence(9, &[4, 6, 9, 10, 13]),
+        Hankel::prime_difference_matrix(10, None),
+    ] {
+        let walk = mat.eulerian_path();
+        let odd = mat.odd_vertices().len();
+        assert_eq!(walk.is_some(), odd == 0 || odd == 2);
+        if let Some(walk) = walk {
+            let mut edges = walk
+                .windows(2)
+                .map(|w| (w[0].min(w[1]), w[0].max(w[1])))
+                .collect::<Vec<_>>();
+            edges.sort_unstable();
+            assert_eq!(edges, mat.edges().collect::<Vec<_>>());
+        }
+    }
+    // The triangles 1 - 3 - 5 and 2 - 4 - 6 aren't connected.
+    let triangles = Hankel::from_differences(6, &[2, 4]);
+    assert_eq!(triangles.eulerian_circuit(), None);
+    assert_eq!(Hankel::from_sequence(3, &[]).eulerian_path(), None);
+}
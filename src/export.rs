@@ -0,0 +1,462 @@
+use std::cmp::min;
+use std::fmt::Write as _;
+use std::io;
+use std::io::Write;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::Hankel;
+
+/// The number of rows of the matrix whose edges are formatted at once, when
+/// the edges are written in parallel.
+const ROWS_PER_BATCH: usize = 1024;
+
+impl Hankel {
+    /// Writes the graph in the Graphviz DOT format.
+    ///
+    /// If `cycle` is given, the edges of the cycle are highlighted.
+    pub fn write_dot<W: Write>(&self, mut output: W, cycle: Option<&[usize]>) -> io::Result<()> {
+        let successors = cycle.map(|c| cycle_successors(self.size, c)).transpose()?;
+        writeln!(output, "graph prime_sum_{} {{", self.size)?;
+        for vertex in 1..=self.size {
+            writeln!(output, "    {};", vertex)?;
+        }
+        self.write_edges(&mut output, |line, i, j| {
+            if in_cycle(successors.as_deref(), i, j) {
+                writeln!(line, "    {} -- {} [color=red, penwidth=2];", i, j)
+            } else {
+                writeln!(line, "    {} -- {};", i, j)
+            }
+        })?;
+        writeln!(output, "}}")?;
+        output.flush()
+    }
+
+    /// Writes the graph in the GraphML format.
+    ///
+    /// Every edge gets a boolean `in_cycle` attribute, which is true for
+    /// the edges of `cycle` (if given).
+    pub fn write_graphml<W: Write>(
+        &self,
+        mut output: W,
+        cycle: Option<&[usize]>,
+    ) -> io::Result<()> {
+        let successors = cycle.map(|c| cycle_successors(self.size, c)).transpose()?;
+        writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            output,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            output,
+            r#"  <key id="in_cycle" for="edge" attr.name="in_cycle" attr.type="boolean">"#
+        )?;
+        writeln!(output, "    <default>false</default>")?;
+        writeln!(output, "  </key>")?;
+        writeln!(
+            output,
+            r#"  <graph id="prime_sum_{}" edgedefault="undirected">"#,
+            self.size
+        )?;
+        for vertex in 1..=self.size {
+            writeln!(output, r#"    <node id="{}"/>"#, vertex)?;
+        }
+        self.write_edges(&mut output, |line, i, j| {
+            if in_cycle(successors.as_deref(), i, j) {
+                writeln!(
+                    line,
+                    r#"    <edge source="{}" target="{}"><data key="in_cycle">true</data></edge>"#,
+                    i, j
+                )
+            } else {
+                writeln!(line, r#"    <edge source="{}" target="{}"/>"#, i, j)
+            }
+        })?;
+        writeln!(output, "  </graph>")?;
+        writeln!(output, "</graphml>")?;
+        output.flush()
+    }
+
+    /// Writes the adjacency matrix in the Matrix Market coordinate format.
+    ///
+    /// Since the matrix is symmetric, only the entries below the diagonal are
+    /// written. The format has no way to mark edges, so if `cycle` is given,
+    /// it is written as a comment in the header.
+    pub fn write_matrix_market<W: Write>(
+        &self,
+        mut output: W,
+        cycle: Option<&[usize]>,
+    ) -> io::Result<()> {
+        writeln!(output, "%%MatrixMarket matrix coordinate pattern symmetric")?;
+        if let Some(cycle) = cycle {
+            write!(output, "% cycle:")?;
+            for vertex in cycle {
+                write!(output, " {}", vertex)?;
+            }
+            writeln!(output)?;
+        }
+        writeln!(output, "{} {} {}", self.size, self.size, self.edge_count())?;
+        self.write_edges(&mut output, |line, i, j| writeln!(line, "{} {}", j, i))?;
+        output.flush()
+    }
+
+    /// Writes the adjacency matrix as text, with a row per line and the
+    /// entries separated by commas, like [`Hankel::print`].
+    ///
+    /// The text is put together in chunks of about `buffer_size` bytes, which
+    /// are written as soon as they are full, so even for millions of vertices
+    /// no row has to be in memory at once.
+    pub fn write_matrix<W: Write>(&self, mut output: W, buffer_size: usize) -> io::Result<()> {
+        let n = self.size;
+        // Every entry takes at most 3 bytes, with the separator.
+        let mut chunk = Vec::with_capacity(buffer_size.max(3));
+        for row in 0..n {
+            for col in 0..n {
+                chunk.push(b'0' + self.get_0_based(row, col));
+                chunk.extend_from_slice(if col + 1 < n { b", " } else { b"\n" });
+                if chunk.len() + 3 > chunk.capacity() {
+                    output.write_all(&chunk)?;
+                    chunk.clear();
+                }
+            }
+        }
+        output.write_all(&chunk)?;
+        output.flush()
+    }
+
+    /// Writes the rows of the adjacency matrix as bits, with n / 8 bytes per
+    /// row, rounded up. Column j is in byte j / 8, starting from the most
+    /// significant bit, like the rows of a PBM image.
+    ///
+    /// Like [`Hankel::write_matrix`], the bytes are written in chunks of about
+    /// `buffer_size` bytes.
+    pub fn write_bit_rows<W: Write>(&self, mut output: W, buffer_size: usize) -> io::Result<()> {
+        let n = self.size;
+        let mut chunk = Vec::with_capacity(buffer_size.max(1));
+        for row in 0..n {
+            for first in (0..n).step_by(8) {
+                let byte = (first..min(first + 8, n))
+                    .filter(|&col| self.get_0_based(row, col) != 0)
+                    .fold(0u8, |byte, col| byte | 0x80 >> (col - first));
+                chunk.push(byte);
+                if chunk.len() == chunk.capacity() {
+                    output.write_all(&chunk)?;
+                    chunk.clear();
+                }
+            }
+        }
+        output.write_all(&chunk)?;
+        output.flush()
+    }
+
+    /// Writes a drawing of the graph in the SVG format, with the vertices
+    /// on a circle.
+    ///
+    /// If `cycle` is given, the vertices are placed in the order of the cycle,
+    /// so the cycle goes around the circle, and its edges are highlighted.
+    pub fn write_svg<W: Write>(&self, mut output: W, cycle: Option<&[usize]>) -> io::Result<()> {
+        const SIZE: f64 = 800.0;
+        let radius = SIZE / 2.0 - 40.0;
+        // Make the vertices smaller when there are many of them, so they don't overlap.
+        let vertex_radius = (std::f64::consts::PI * radius / self.size as f64 * 0.8).min(14.0);
+        let successors = cycle.map(|c| cycle_successors(self.size, c)).transpose()?;
+        let order = match cycle {
+            Some(c) => c.to_vec(),
+            None => (1..=self.size).collect(),
+        };
+        let mut position = vec![(0.0, 0.0); self.size + 1];
+        for (k, &vertex) in order.iter().enumerate() {
+            let angle = 2.0 * std::f64::consts::PI * k as f64 / self.size as f64
+                - std::f64::consts::FRAC_PI_2;
+            position[vertex] = (
+                SIZE / 2.0 + radius * angle.cos(),
+                SIZE / 2.0 + radius * angle.sin(),
+            );
+        }
+        writeln!(
+            output,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+            SIZE
+        )?;
+        writeln!(
+            output,
+            r#"  <rect width="100%" height="100%" fill="white"/>"#
+        )?;
+        // Draw the cycle last, so it is on top of the other edges.
+        let (cycle_edges, other_edges): (Vec<_>, Vec<_>) = self
+            .edges()
+            .partition(|&(i, j)| in_cycle(successors.as_deref(), i, j));
+        for (edges, style) in [
+            (other_edges, r##"stroke="#bbbbbb" stroke-width="1""##),
+            (cycle_edges, r##"stroke="red" stroke-width="3""##),
+        ] {
+            for (i, j) in edges {
+                let (x1, y1) = position[i];
+                let (x2, y2) = position[j];
+                writeln!(
+                    output,
+                    r#"  <line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" {}/>"#,
+                    x1, y1, x2, y2, style
+                )?;
+            }
+        }
+        for (vertex, &(x, y)) in position.iter().enumerate().skip(1) {
+            writeln!(
+                output,
+                r#"  <circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="white" stroke="black"/>"#,
+                x, y, vertex_radius
+            )?;
+            writeln!(
+                output,
+                r#"  <text x="{:.2}" y="{:.2}" font-size="{:.2}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                x, y, vertex_radius, vertex
+            )?;
+        }
+        writeln!(output, "</svg>")?;
+        output.flush()
+    }
+
+    /// Writes the edges in the same order as [`Hankel::edges`], where `format`
+    /// appends the text for an edge to a line. With the `rayon` feature, the
+    /// rows are formatted in parallel.
+    fn write_edges<W: Write>(
+        &self,
+        output: &mut W,
+        format: impl Fn(&mut String, usize, usize) -> std::fmt::Result + Sync,
+    ) -> io::Result<()> {
+        let row = |i: usize| {
+            let mut text = String::new();
+            for j in (i + 1..=self.size).filter(|&j| self.get(i, j) != 0) {
+                // Writing to a `String` can't fail.
+                let _ = format(&mut text, i, j);
+            }
+            text
+        };
+        for start in (1..=self.size).step_by(ROWS_PER_BATCH) {
+            let rows = start..min(start + ROWS_PER_BATCH, self.size + 1);
+            #[cfg(feature = "rayon")]
+            let rows = rows.into_par_iter();
+            for text in rows.map(row).collect::<Vec<_>>() {
+                output.write_all(text.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// For every vertex of `cycle` store the vertex that comes after it.
+/// Index 0 is unused, because the vertices are 1-indexed.
+///
+/// Returns an error if the cycle contains numbers that are not vertices.
+fn cycle_successors(size: usize, cycle: &[usize]) -> io::Result<Vec<usize>> {
+    let mut successors = vec![0; size + 1];
+    for (i, &vertex) in cycle.iter().enumerate() {
+        if !(1..=size).contains(&vertex) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the cycle contains {}, which is not a vertex", vertex),
+            ));
+        }
+        successors[vertex] = cycle[(i + 1) % cycle.len()];
+    }
+    Ok(successors)
+}
+
+/// Checks if the edge between `i` and `j` is part of the cycle.
+fn in_cycle(successors: Option<&[usize]>, i: usize, j: usize) -> bool {
+    match successors {
+        Some(s) => s[i] == j || s[j] == i,
+        None => false,
+    }
+}
+
+impl Hankel {
+    /// Returns the graph in the graph6 format of nauty, without a newline.
+    ///
+    /// The format has no loops, so those are left out. It takes n² / 12 bytes,
+    /// see [`Hankel::to_sparse6`] for graphs with few edges.
+    pub fn to_graph6(&self) -> String {
+        let mut bits = SixBits::new(self.size);
+        for j in 1..self.size {
+            for i in 0..j {
+                bits.push(self.get_0_based(i, j) != 0);
+            }
+        }
+        bits.finish(false)
+    }
+
+    /// Returns the graph in the sparse6 format of nauty, without a newline.
+    /// Unlike graph6, it has loops, and takes a few bytes per edge.
+    pub fn to_sparse6(&self) -> String {
+        let n = self.size;
+        // The number of bits of n - 1.
+        let width = usize::BITS - n.saturating_sub(1).leading_zeros();
+        let mut bits = SixBits::new(n);
+        bits.text.insert(0, ':');
+        let mut current = 0;
+        for j in 0..n {
+            for i in (0..=j).filter(|&i| self.get_0_based(i, j) != 0) {
+                if j == current {
+                    bits.push(false);
+                } else {
+                    bits.push(true);
+                    if j > current + 1 {
+                        bits.push_number(j, width);
+                        bits.push(false);
+                    }
+                    current = j;
+                }
+                bits.push_number(i, width);
+            }
+        }
+        // Padding with ones could be read as an edge to the vertex n - 1 if
+        // it fits, then a zero is put first.
+        let ambiguous =
+            width < 6 && n == 1 << width && current + 2 == n && 6 - bits.len > width as u8;
+        bits.finish(ambiguous)
+    }
+}
+
+/// The bits of a graph in the graph6 or sparse6 format, which are stored as
+/// printable characters of 6 bits each, after the number of vertices.
+struct SixBits {
+    text: String,
+    /// The bits of the character that isn't full yet.
+    word: u8,
+    len: u8,
+}
+
+impl SixBits {
+    /// Starts with the number of vertices `n`.
+    fn new(n: usize) -> Self {
+        let mut bits = Self {
+            text: String::new(),
+            word: 0,
+            len: 0,
+        };
+        match n {
+            0..=62 => bits.push_number(n, 6),
+            63..=258_047 => {
+                bits.text.push('~');
+                bits.push_number(n, 18);
+            }
+            _ => {
+                bits.text.push_str("~~");
+                bits.push_number(n, 36);
+            }
+        }
+        bits
+    }
+
+    fn push(&mut self, bit: bool) {
+        self.word = (self.word << 1) | u8::from(bit);
+        self.len += 1;
+        if self.len == 6 {
+            self.text.push(char::from(self.word + 63));
+            (self.word, self.len) = (0, 0);
+        }
+    }
+
+    /// Pushes the lowest `width` bits of `x`, the highest first.
+    fn push_number(&mut self, x: usize, width: u32) {
+        for bit in (0..width).rev() {
+            self.push(x >> bit & 1 == 1);
+        }
+    }
+
+    /// Fills the last character with ones for sparse6, or with zeros for
+    /// graph6, after a zero if `zero_first`.
+    fn finish(mut self, zero_first: bool) -> String {
+        let sparse = self.text.starts_with(':');
+        if self.len > 0 && zero_first {
+            self.push(false);
+        }
+        while self.len > 0 {
+            self.push(sparse);
+        }
+        self.text
+    }
+}
+
+#[test]
+fn dot_highlights_cycle() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let mut output = Vec::new();
+    mat.write_dot(&mut output, Some(&[1, 4, 3, 2, 5, 6]))
+        .unwrap();
+    let dot = String::from_utf8(output).unwrap();
+    assert!(dot.contains("1 -- 4 [color=red, penwidth=2];"));
+    assert!(dot.contains("1 -- 6 [color=red, penwidth=2];"));
+    assert!(dot.contains("    1 -- 2;\n"));
+}
+
+#[test]
+fn invalid_cycle_is_an_error() {
+    let mat = Hankel::prime_sum_matrix(4, None);
+    assert!(mat.write_dot(io::sink(), Some(&[1, 2, 3, 5])).is_err());
+    assert!(mat.write_svg(io::sink(), Some(&[0])).is_err());
+}
+
+#[test]
+fn matrix_market_entries() {
+    let mat = Hankel::prime_sum_matrix(4, None);
+    let mut output = Vec::new();
+    mat.write_matrix_market(&mut output, None).unwrap();
+    let mtx = String::from_utf8(output).unwrap();
+    assert_eq!(
+        mtx,
+        "%%MatrixMarket matrix coordinate pattern symmetric\n4 4 4\n2 1\n4 1\n3 2\n4 3\n"
+    );
+}
+
+#[test]
+fn svg_highlights_cycle() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let mut output = Vec::new();
+    mat.write_svg(&mut output, Some(&[1, 4, 3, 2, 5, 6]))
+        .unwrap();
+    let svg = String::from_utf8(output).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<circle").count(), 6);
+    assert_eq!(svg.matches(r#"stroke="red""#).count(), 6);
+    assert_eq!(svg.matches("<line").count(), mat.edges().count());
+}
+
+#[test]
+fn graph6_and_sparse6() {
+    // Checked with networkx.
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(mat.to_graph6(), "ElQG");
+    assert_eq!(mat.to_sparse6(), ":EaWIWR");
+    // The sparse6 format keeps the loop at 2, where 2 + 2 = 4.
+    assert_eq!(Hankel::from_sequence(4, &[4]).to_sparse6(), ":Ck");
+    assert_eq!(Hankel::from_sequence(3, &[3, 5]).to_graph6(), "Bg");
+    assert_eq!(Hankel::from_sequence(0, &[]).to_graph6(), "?");
+}
+
+#[test]
+fn streamed_matrices() {
+    let mat = Hankel::prime_sum_matrix(4, None);
+    for buffer_size in [0, 5, 1 << 16] {
+        let mut output = Vec::new();
+        mat.write_matrix(&mut output, buffer_size).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0, 1, 0, 1\n1, 0, 1, 0\n0, 1, 0, 1\n1, 0, 1, 0\n"
+        );
+    }
+    let mat = Hankel::prime_sum_matrix(10, None);
+    for buffer_size in [1, 7, 1 << 16] {
+        let mut output = Vec::new();
+        mat.write_bit_rows(&mut output, buffer_size).unwrap();
+        assert_eq!(output.len(), 20);
+        for (i, row) in output.chunks(2).enumerate() {
+            for j in 0..10 {
+                let bit = row[j / 8] >> (7 - j % 8) & 1;
+                assert_eq!(bit, mat.get_0_based(i, j));
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! [`Arbitrary`] implementations for fuzzing, enabled with the `arbitrary`
+//! feature. The fuzz targets are in the `fuzz` directory.
+//!
+//! The sizes and the number of nodes are bounded, so that a
+//! single fuzz input can't take too long.
+
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{Hankel, SearchLimits, SumOrDiff};
+
+/// The largest size of an arbitrary [`Hankel`].
+pub const MAX_SIZE: usize = 64;
+/// The largest node budget of arbitrary [`SearchLimits`].
+pub const MAX_NODES: u64 = 100_000;
+
+/// A Hankel matrix with random diagonals.
+impl<'a> Arbitrary<'a> for Hankel {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let size = u.int_in_range(0..=MAX_SIZE)?;
+        let diagonals = (0..(2 * size).saturating_sub(1))
+            .map(|_| u.arbitrary::<bool>().map(u8::from))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::with_diagonals(diagonals, size, SumOrDiff::Sum))
+    }
+}
+
+/// Limits with a node budget of at most [`MAX_NODES`], and without
+/// a deadline, since that would make the fuzzing nondeterministic.
+impl<'a> Arbitrary<'a> for SearchLimits<'_> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            max_nodes: Some(u.int_in_range(0..=MAX_NODES)?),
+            ..Default::default()
+        })
+    }
+}
+
+/// The arguments of a search, like [`Hankel::hamiltonian_cycle_with_limits`].
+///
+/// The entries of `path` and `pos` are mostly valid, so that the search itself
+/// is exercised, but they can be one too large, or 0.
+#[derive(Debug)]
+pub struct SearchCase {
+    pub matrix: Hankel,
+    pub path: Vec<usize>,
+    pub pos: usize,
+    pub limits: SearchLimits<'static>,
+}
+
+impl<'a> Arbitrary<'a> for SearchCase {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let matrix = Hankel::arbitrary(u)?;
+        let size = matrix.size;
+        let len = u.int_in_range(0..=size + 1)?;
+        let path = (0..len)
+            .map(|_| u.int_in_range(0..=size + 1))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            matrix,
+            path,
+            pos: u.int_in_range(0..=size + 1)?,
+            limits: SearchLimits::arbitrary(u)?,
+        })
+    }
+}
+
+#[test]
+fn arbitrary_search_case() {
+    let bytes = (0..=255).cycle().take(4096).collect::<Vec<u8>>();
+    let mut u = Unstructured::new(&bytes);
+    for _ in 0..10 {
+        let SearchCase {
+            matrix,
+            mut path,
+            pos,
+            limits,
+        } = SearchCase::arbitrary(&mut u).unwrap();
+        assert!(matrix.size <= MAX_SIZE);
+        assert_eq!(matrix.diagonals.len(), (2 * matrix.size).saturating_sub(1));
+        assert!(path.len() <= matrix.size + 1);
+        let mut stats = Default::default();
+        matrix.hamiltonian_cycle_iterative(&mut path, pos, &limits, &mut stats);
+        assert!(stats.nodes <= MAX_NODES);
+    }
+}
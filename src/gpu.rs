@@ -0,0 +1,269 @@
+//! The rotation-extension search of [`Hankel::rotation_extension`] on the
+//! GPU, enabled with the `gpu` feature.
+//!
+//! Every invocation of the compute shader in `src/rotation.wgsl` runs one
+//! attempt, with its own path in a storage buffer, so thousands of attempts
+//! run at once. Every run of the shader only does a limited amount of work,
+//! and after a few runs the host checks whether an attempt found a cycle.
+//!
+//! The random numbers are not the ones of the CPU search, but they also only
+//! depend on the seed, so the same cycle is found every time.
+
+use std::borrow::Cow;
+
+use wgpu::util::DeviceExt;
+
+use crate::{Cycle, Hankel, SumOrDiff};
+
+/// The number of invocations in a workgroup, as in the shader.
+const WORKGROUP_SIZE: u64 = 64;
+
+/// The number of times the shader runs before the host checks whether an
+/// attempt found a cycle.
+const DISPATCHES_PER_CHECK: usize = 16;
+
+/// The value of `progress.found` in the shader while no cycle has been found.
+const NOT_FOUND: u32 = u32::MAX;
+
+impl Hankel {
+    /// Same as [`Hankel::rotation_extension_attempts`], but the attempts run
+    /// in parallel on the GPU, which is much faster for many attempts.
+    ///
+    /// If there is no GPU, or the graph doesn't fit in its memory, the
+    /// attempts run on the CPU instead. The attempts on the GPU use other
+    /// random numbers, so they can find another cycle, and take at most
+    /// `u32::MAX` steps.
+    pub fn rotation_extension_gpu(&self, attempts: u64, max_steps: u64) -> Option<Cycle> {
+        match self.rotation_extension_on_gpu(attempts, max_steps) {
+            Ok(cycle) => cycle,
+            Err(Unavailable) => self.rotation_extension_attempts(attempts, max_steps),
+        }
+    }
+
+    /// Runs the attempts of [`Hankel::rotation_extension_gpu`] on the GPU,
+    /// in batches of as many attempts as fit in the memory.
+    fn rotation_extension_on_gpu(
+        &self,
+        attempts: u64,
+        max_steps: u64,
+    ) -> Result<Option<Cycle>, Unavailable> {
+        let n = u32::try_from(self.size).map_err(|_| Unavailable)?;
+        let max_steps = u32::try_from(max_steps).unwrap_or(u32::MAX);
+        if n == 0 || self.degrees.contains(&0) {
+            return Ok(None);
+        }
+        let gpu = Gpu::new()?;
+        let limits = gpu.device.limits();
+        let max_binding = limits
+            .max_storage_buffer_binding_size
+            .min(limits.max_buffer_size);
+        // The positions take the most memory, n + 1 per attempt.
+        let per_attempt = 4 * (u64::from(n) + 1);
+        let batch_size = (max_binding / per_attempt)
+            .min(u64::from(limits.max_compute_workgroups_per_dimension) * WORKGROUP_SIZE)
+            .min(u64::from(u32::MAX));
+        if batch_size == 0 || 4 * self.diagonals.len() as u64 > max_binding {
+            return Err(Unavailable);
+        }
+        let diagonals = self
+            .diagonals
+            .iter()
+            .map(|&d| u32::from(d))
+            .collect::<Vec<_>>();
+        let diagonals = gpu.buffer_init("diagonals", &diagonals, wgpu::BufferUsages::STORAGE);
+        let kind = match self.kind {
+            SumOrDiff::Sum => 0,
+            SumOrDiff::Diff => 1,
+        };
+        let mut first = 0;
+        while first < attempts {
+            let batch = (attempts - first).min(batch_size) as u32;
+            let paths = gpu.buffer(
+                "paths",
+                u64::from(batch) * u64::from(n),
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            );
+            // The buffers start out as zeros, so no attempt has started.
+            let positions = gpu.buffer(
+                "positions",
+                u64::from(batch) * (u64::from(n) + 1),
+                wgpu::BufferUsages::STORAGE,
+            );
+            let states = gpu.buffer("states", 3 * u64::from(batch), wgpu::BufferUsages::STORAGE);
+            let progress = gpu.buffer_init(
+                "progress",
+                &[NOT_FOUND, 0],
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            );
+            // The seeds of the attempts wrap around after 2^32.
+            let params = gpu.buffer_init(
+                "params",
+                &[n, kind, batch, first as u32, max_steps],
+                wgpu::BufferUsages::UNIFORM,
+            );
+            let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("rotation extension"),
+                layout: &gpu.pipeline.get_bind_group_layout(0),
+                entries: &[&params, &diagonals, &paths, &positions, &states, &progress]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, buffer)| wgpu::BindGroupEntry {
+                        binding: i as u32,
+                        resource: buffer.as_entire_binding(),
+                    })
+                    .collect::<Vec<_>>(),
+            });
+            loop {
+                let mut encoder = gpu.device.create_command_encoder(&Default::default());
+                for _ in 0..DISPATCHES_PER_CHECK {
+                    let mut pass = encoder.begin_compute_pass(&Default::default());
+                    pass.set_pipeline(&gpu.pipeline);
+                    pass.set_bind_group(0, &bind_group, &[]);
+                    pass.dispatch_workgroups(
+                        u64::from(batch).div_ceil(WORKGROUP_SIZE) as u32,
+                        1,
+                        1,
+                    );
+                }
+                gpu.queue.submit([encoder.finish()]);
+                let progress = gpu.read(&progress, 0, 2)?;
+                let (found, finished) = (progress[0], progress[1]);
+                if found != NOT_FOUND {
+                    let path = gpu.read(&paths, u64::from(found) * u64::from(n), u64::from(n))?;
+                    let path = path.into_iter().map(|v| v as usize).collect();
+                    return Ok(Some(Cycle::found(path, self)));
+                }
+                if finished == batch {
+                    break;
+                }
+            }
+            first += u64::from(batch);
+        }
+        Ok(None)
+    }
+}
+
+/// There is no GPU to run the search on, or the graph doesn't fit in its
+/// memory.
+#[derive(Debug)]
+struct Unavailable;
+
+/// The device and the compiled shader of the search.
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl Gpu {
+    /// Requests the default adapter, with the largest limits it supports,
+    /// and compiles the shader.
+    fn new() -> Result<Self, Unavailable> {
+        let instance = wgpu::Instance::default();
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .map_err(|_| Unavailable)?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("rotation extension"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .map_err(|_| Unavailable)?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("rotation extension"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("rotation.wgsl"))),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("rotation extension"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Makes a buffer of `len` numbers, which are all 0.
+    fn buffer(&self, label: &str, len: u64, usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: 4 * len,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Makes a buffer with the numbers in `contents`.
+    fn buffer_init(
+        &self,
+        label: &str,
+        contents: &[u32],
+        usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: &to_bytes(contents),
+                usage,
+            })
+    }
+
+    /// Copies `len` numbers from `buffer`, starting at the number `start`,
+    /// back to the host, after the work that was submitted is done.
+    fn read(&self, buffer: &wgpu::Buffer, start: u64, len: u64) -> Result<Vec<u32>, Unavailable> {
+        let staging = self.buffer(
+            "staging",
+            len,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(buffer, 4 * start, &staging, 0, 4 * len);
+        self.queue.submit([encoder.finish()]);
+        let slice = staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|_| Unavailable)?;
+        let values = slice
+            .get_mapped_range()
+            .map_err(|_| Unavailable)?
+            .chunks_exact(4)
+            .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        Ok(values)
+    }
+}
+
+/// Returns the bytes of `values`, in the byte order of the GPU.
+fn to_bytes(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+#[test]
+fn rotations_and_extensions_on_the_gpu() {
+    let mat = Hankel::prime_sum_matrix(500, None);
+    let cycle = mat.rotation_extension_gpu(256, 1_000_000).unwrap();
+    assert!(mat.valid_cycle(&cycle));
+    // The same seeds find the same cycle.
+    assert_eq!(mat.rotation_extension_gpu(256, 1_000_000), Some(cycle));
+    // Odd prime sum graphs are bipartite with unequal sides.
+    assert_eq!(
+        Hankel::prime_sum_matrix(7, None).rotation_extension_gpu(64, 1000),
+        None
+    );
+    // A loop doesn't make a single vertex a cycle.
+    assert_eq!(
+        Hankel::from_sequence(1, &[2]).rotation_extension_gpu(4, 100),
+        None
+    );
+    // The differences 1 and 39 give the cycle 1, 2, ..., 40.
+    let mat = Hankel::from_differences(40, &[1, 3, 39]);
+    if let Ok(cycle) = mat.rotation_extension_on_gpu(64, 100_000) {
+        assert!(mat.valid_cycle(&cycle.unwrap()));
+    }
+}
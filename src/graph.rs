@@ -0,0 +1,380 @@
+//! The [`SumGraph`] trait, for the algorithms that only need to know which
+//! vertices are adjacent.
+//!
+//! [`Hankel`] (also for the difference graphs), [`SparseHankel`] and
+//! [`Adjacency`] store the same graphs in different ways. The validators,
+//! the degrees, a backtracking search and the edge list export are written
+//! once for all of them here, so a new way of storing the graphs only has to
+//! implement the three required methods. The inherent validators of
+//! [`Hankel`] call the ones here, and the search goes to the faster searches
+//! of [`Hankel`] and [`Adjacency`] when the graph alternates between odd and
+//! even vertices.
+
+use alloc::vec;
+
+use crate::{Adjacency, Hankel, SearchLimits, SearchResult, SearchStats, SparseHankel};
+
+/// A graph on the vertices 1 to n. Loops are ignored, so a vertex is never
+/// adjacent to itself.
+pub trait SumGraph {
+    /// Returns the number of vertices n.
+    fn size(&self) -> usize;
+
+    /// Checks if `u` and `v` are different vertices which are adjacent.
+    /// Returns false if one of them is not between 1 and n.
+    fn adjacent(&self, u: usize, v: usize) -> bool;
+
+    /// Returns the neighbors of the vertex `v` in increasing order, without
+    /// `v` itself.
+    ///
+    /// # Panics
+    ///
+    /// If `v` is not between 1 and n.
+    fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_;
+
+    /// Returns the number of neighbors of `v`, so its degree without a loop.
+    ///
+    /// # Panics
+    ///
+    /// If `v` is not between 1 and n.
+    fn neighbor_count(&self, v: usize) -> usize {
+        self.neighbors(v).count()
+    }
+
+    /// Returns the number of edges, without the loops.
+    fn edge_count(&self) -> usize {
+        (1..=self.size())
+            .map(|v| self.neighbor_count(v))
+            .sum::<usize>()
+            / 2
+    }
+
+    /// Checks if `path` is a path without repeated vertices.
+    ///
+    /// Returns false if the path contains vertices that are not in the graph.
+    fn valid_path(&self, path: &[usize]) -> bool {
+        let n = self.size();
+        let mut used = vec![false; n + 1];
+        path.iter()
+            .all(|&v| (1..=n).contains(&v) && !core::mem::replace(&mut used[v], true))
+            && path.windows(2).all(|w| self.adjacent(w[0], w[1]))
+    }
+
+    /// Checks if `cycle` is a cycle without repeated vertices.
+    ///
+    /// Returns false if the cycle is empty, or contains vertices that are not
+    /// in the graph.
+    fn valid_cycle(&self, cycle: &[usize]) -> bool {
+        match (cycle.first(), cycle.last()) {
+            (Some(&first), Some(&last)) => self.valid_path(cycle) && self.adjacent(last, first),
+            _ => false,
+        }
+    }
+
+    /// Tries to complete the first `pos` vertices of `path` to a Hamiltonian
+    /// cycle. Like the validators, loops are ignored, so a single vertex is
+    /// never a cycle.
+    ///
+    /// By default this is a backtracking search, which tries the neighbors of
+    /// the last vertex in increasing order. Returns [`SearchResult::NotFound`]
+    /// if `path` doesn't have n entries, or if its first `pos` vertices aren't
+    /// a valid path.
+    fn find_hamiltonian_cycle(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        backtrack(self, path, pos, limits, stats)
+    }
+
+    /// Writes the edges as lines `u v` with u < v, in increasing order.
+    #[cfg(feature = "std")]
+    fn write_edge_list<W: std::io::Write>(&self, mut output: W) -> std::io::Result<()> {
+        for u in 1..=self.size() {
+            for v in self.neighbors(u).filter(|&v| v > u) {
+                writeln!(output, "{} {}", u, v)?;
+            }
+        }
+        output.flush()
+    }
+}
+
+impl SumGraph for Hankel {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn adjacent(&self, u: usize, v: usize) -> bool {
+        u != v && self.try_get(u, v).is_some_and(|e| e != 0)
+    }
+
+    fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        assert!(
+            (1..=self.size).contains(&v),
+            "the vertex isn't in the graph"
+        );
+        (1..=self.size).filter(move |&w| w != v && self.get(v, w) != 0)
+    }
+
+    fn neighbor_count(&self, v: usize) -> usize {
+        self.degrees()[v - 1] - usize::from(self.get(v, v))
+    }
+
+    fn edge_count(&self) -> usize {
+        Hankel::edge_count(self)
+    }
+
+    /// Uses [`Hankel::hamiltonian_cycle_iterative`] if every edge joins an odd
+    /// and an even vertex, so that it finds every cycle, and the path starts
+    /// with an odd vertex.
+    fn find_hamiltonian_cycle(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        // The entries on the even diagonals, other than the loop at 1, join
+        // two odd or two even vertices.
+        let alternates = self.kind() == crate::SumOrDiff::Sum
+            && self.diagonals().iter().skip(2).step_by(2).all(|&d| d == 0);
+        if alternates && self.valid_start(path, pos) {
+            self.hamiltonian_cycle_iterative(path, pos, limits, stats)
+        } else {
+            backtrack(self, path, pos, limits, stats)
+        }
+    }
+}
+
+impl SumGraph for SparseHankel {
+    fn size(&self) -> usize {
+        SparseHankel::size(self)
+    }
+
+    fn adjacent(&self, u: usize, v: usize) -> bool {
+        u != v && self.try_get(u, v).is_some_and(|e| e != 0)
+    }
+
+    fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        SparseHankel::neighbors(self, v)
+    }
+}
+
+impl SumGraph for Adjacency {
+    fn size(&self) -> usize {
+        Adjacency::size(self)
+    }
+
+    fn adjacent(&self, u: usize, v: usize) -> bool {
+        (1..=Adjacency::size(self)).contains(&u)
+            && u32::try_from(v)
+                .is_ok_and(|v| Adjacency::neighbors(self, u).binary_search(&v).is_ok())
+    }
+
+    fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        Adjacency::neighbors(self, v).iter().map(|&w| w as usize)
+    }
+
+    fn neighbor_count(&self, v: usize) -> usize {
+        Adjacency::neighbors(self, v).len()
+    }
+
+    /// Uses [`Adjacency::hamiltonian_cycle_iterative`] if every edge joins an
+    /// odd and an even vertex, and the path starts with an odd vertex.
+    fn find_hamiltonian_cycle(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        let alternates = (1..=Adjacency::size(self)).all(|v| {
+            Adjacency::neighbors(self, v)
+                .iter()
+                .all(|&w| (v + w as usize) % 2 == 1)
+        });
+        if alternates && self.valid_start(path, pos) {
+            self.hamiltonian_cycle_iterative(path, pos, limits, stats)
+        } else {
+            backtrack(self, path, pos, limits, stats)
+        }
+    }
+}
+
+/// The backtracking search of [`SumGraph::find_hamiltonian_cycle`], which
+/// doesn't use that the prime sum graphs are bipartite, so it also works for
+/// the other graphs.
+fn backtrack<G: SumGraph + ?Sized>(
+    graph: &G,
+    path: &mut [usize],
+    pos: usize,
+    limits: &SearchLimits,
+    stats: &mut SearchStats,
+) -> SearchResult {
+    let n = graph.size();
+    if path.len() != n || !(1..=n).contains(&pos) || !graph.valid_path(&path[..pos]) {
+        return SearchResult::NotFound;
+    }
+    let mut used = vec![false; n + 1];
+    for &v in &path[..pos] {
+        used[v] = true;
+    }
+    // The number of neighbors of the previous vertex that were tried at
+    // every position.
+    let mut tried = vec![0; n + 1];
+    let start = pos;
+    let mut pos = pos;
+    loop {
+        if pos == n {
+            if graph.adjacent(path[n - 1], path[0]) {
+                return SearchResult::Found;
+            }
+            stats.record_dead_end(path, pos);
+        } else {
+            let mut next = None;
+            for w in graph.neighbors(path[pos - 1]).skip(tried[pos]) {
+                tried[pos] += 1;
+                if !used[w] {
+                    next = Some(w);
+                    break;
+                }
+            }
+            if let Some(w) = next {
+                path[pos] = w;
+                used[w] = true;
+                stats.nodes += 1;
+                if limits.reached(stats, pos + 1) {
+                    return SearchResult::Aborted;
+                }
+                pos += 1;
+                tried[pos] = 0;
+                continue;
+            }
+            stats.record_dead_end(path, pos);
+        }
+        // Backtrack
+        if pos == start {
+            return SearchResult::NotFound;
+        }
+        pos -= 1;
+        used[core::mem::take(&mut path[pos])] = false;
+    }
+}
+
+#[test]
+fn same_graph_in_every_storage() {
+    fn check<G: SumGraph>(graph: &G, mat: &Hankel) {
+        let n = mat.size;
+        assert_eq!(graph.size(), n);
+        assert_eq!(graph.edge_count(), mat.edge_count());
+        for v in 1..=n {
+            assert!(graph.neighbors(v).eq(SumGraph::neighbors(mat, v)));
+            assert!(!graph.adjacent(v, v));
+            assert!(!graph.adjacent(0, v) && !graph.adjacent(v, n + 1));
+        }
+        for v in 1..=n {
+            assert_eq!(graph.neighbor_count(v), SumGraph::neighbor_count(mat, v));
+        }
+        if let Some(cycle) = mat.is_hamiltonian() {
+            assert!(graph.valid_cycle(&cycle));
+            assert!(graph.valid_path(&cycle));
+        }
+        if n == 0 {
+            return;
+        }
+        let search = |path: &mut [usize]| {
+            let limits = SearchLimits::default();
+            let mut stats = SearchStats::default();
+            path[0] = 1;
+            let found = graph.find_hamiltonian_cycle(path, 1, &limits, &mut stats);
+            path.fill(0);
+            path[0] = 1;
+            let plain = backtrack(mat, path, 1, &limits, &mut SearchStats::default());
+            (found, plain)
+        };
+        let mut path = vec![0; n];
+        let (found, plain) = search(&mut path);
+        assert_eq!(found, plain);
+        if found == SearchResult::Found {
+            assert!(graph.valid_cycle(&path));
+        }
+        // The prime sum graphs alternate, and then the searches of the matrix
+        // find every cycle.
+        if mat.kind() == crate::SumOrDiff::Sum {
+            assert_eq!(found == SearchResult::Found, mat.is_hamiltonian().is_some());
+        }
+    }
+    for n in [1, 2, 6, 8, 11, 14] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        check(&mat, &mat);
+        check(&SparseHankel::from(&mat), &mat);
+        check(&mat.adjacency(), &mat);
+        let squares = [1, 4, 9, 16, 25];
+        let mat = Hankel::from_differences(n, &squares);
+        check(&mat, &mat);
+        check(&SparseHankel::from_differences(n, &squares), &mat);
+    }
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert!(!mat.valid_cycle(&[]));
+    assert!(!mat.valid_path(&[1, 3]));
+    assert!(!mat.valid_path(&[1, 2, 1]));
+    let limits = SearchLimits {
+        max_nodes: Some(2),
+        ..SearchLimits::default()
+    };
+    let mut path = vec![1, 0, 0, 0, 0, 0];
+    assert_eq!(
+        mat.find_hamiltonian_cycle(&mut path, 1, &limits, &mut SearchStats::default()),
+        SearchResult::Aborted
+    );
+    // The prefix 1, 3 isn't a path.
+    let mut path = vec![1, 3, 0, 0, 0, 0];
+    assert_eq!(
+        mat.find_hamiltonian_cycle(&mut path, 2, &limits, &mut SearchStats::default()),
+        SearchResult::NotFound
+    );
+    // A path that starts with an even vertex isn't searched by the matrix.
+    let mut path = vec![2, 0, 0, 0, 0, 0];
+    let limits = SearchLimits::default();
+    assert_eq!(
+        mat.find_hamiltonian_cycle(&mut path, 1, &limits, &mut SearchStats::default()),
+        SearchResult::Found
+    );
+    assert!(mat.valid_cycle(&path));
+    #[cfg(feature = "std")]
+    {
+        let mut output = alloc::vec::Vec::new();
+        mat.write_edge_list(&mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("1 2\n1 4\n1 6\n2 3\n"));
+    }
+}
+
+#[test]
+fn single_vertex_with_a_loop() {
+    // 1 + 1 = 2, so the only vertex is adjacent to itself, which is not a
+    // cycle for the searches nor for the validators.
+    let mat = Hankel::from_sequence(1, &[2]);
+    assert_eq!(mat.get(1, 1), 1);
+    assert!(!mat.valid_cycle(&[1]));
+    assert!(mat.valid_path(&[1]));
+    assert_eq!(mat.is_hamiltonian(), None);
+    assert!(!mat.hamiltonian_cycle(&mut [1], 1));
+    let mut path = [1];
+    let limits = SearchLimits::default();
+    for result in [
+        mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut SearchStats::default()),
+        mat.adjacency().hamiltonian_cycle_iterative(
+            &mut path,
+            1,
+            &limits,
+            &mut SearchStats::default(),
+        ),
+        mat.find_hamiltonian_cycle(&mut path, 1, &limits, &mut SearchStats::default()),
+    ] {
+        assert_eq!(result, SearchResult::NotFound);
+    }
+}
@@ -0,0 +1,144 @@
+//! Randomized searches for Hamiltonian cycles, which can find cycles for
+//! sizes where the backtracking is too slow, but can't prove that there
+//! is no cycle.
+//!
+//! The random numbers come from a small xorshift generator, so that the
+//! results only depend on the seed.
+//!
+//! The attempts run on every core with the `rayon` feature, and on the GPU
+//! with the `gpu` feature, see [`Hankel::rotation_extension_gpu`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{Cycle, Hankel, SumGraph};
+
+impl Hankel {
+    /// Searches for a Hamiltonian cycle with Pósa's rotations and extensions,
+    /// taking at most `max_steps` steps. Returns `None` if no cycle was found,
+    /// which doesn't mean there is none.
+    ///
+    /// Every step takes a random neighbor of the end of the path. If it isn't
+    /// in the path, the path is extended with it. Otherwise the part of the
+    /// path after it is reversed, which gives a path with another end, unless
+    /// the path is complete and the neighbor is its start.
+    pub fn rotation_extension(&self, seed: u64, max_steps: u64) -> Option<Cycle> {
+        let n = self.size;
+        if n == 0 || self.degrees.contains(&0) {
+            return None;
+        }
+        let mut rng = Rng::new(seed);
+        // `index[v]` is the position of v in the path, or `usize::MAX`.
+        let mut index = vec![usize::MAX; n + 1];
+        let start = 1 + rng.below(n);
+        let mut path = Vec::with_capacity(n);
+        path.push(start);
+        index[start] = 0;
+        for _ in 0..max_steps {
+            let end = path[path.len() - 1];
+            if path.len() == n && SumGraph::adjacent(self, end, path[0]) {
+                return Some(Cycle::found(path, self));
+            }
+            let next = self.random_neighbor(end, &mut rng);
+            if next == end {
+                continue;
+            }
+            match index[next] {
+                usize::MAX => {
+                    index[next] = path.len();
+                    path.push(next);
+                }
+                i => {
+                    path[i + 1..].reverse();
+                    for (j, &v) in path.iter().enumerate().skip(i + 1) {
+                        index[v] = j;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Runs [`Hankel::rotation_extension`] with the seeds from 0 to `attempts`,
+    /// in parallel if the `rayon` feature is enabled, and returns the first
+    /// cycle that is found.
+    pub fn rotation_extension_attempts(&self, attempts: u64, max_steps: u64) -> Option<Cycle> {
+        #[cfg(feature = "rayon")]
+        return (0..attempts)
+            .into_par_iter()
+            .find_map_any(|seed| self.rotation_extension(seed, max_steps));
+        #[cfg(not(feature = "rayon"))]
+        (0..attempts).find_map(|seed| self.rotation_extension(seed, max_steps))
+    }
+
+    /// Returns a random neighbor of `v`, which has at least one neighbor,
+    /// by trying random vertices until one is adjacent.
+    fn random_neighbor(&self, v: usize, rng: &mut Rng) -> usize {
+        loop {
+            let w = 1 + rng.below(self.size);
+            if self.get(v, w) != 0 {
+                return w;
+            }
+        }
+    }
+}
+
+/// A xorshift* generator of pseudorandom numbers.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    /// Makes a generator from `seed`, which is mixed first, since the
+    /// state can't be 0 and close seeds should give different numbers.
+    pub(crate) fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self((z ^ (z >> 31)).max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number below `n`, which is nearly uniform if `n` is small.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Returns true with probability `p`, so never if `p` is at most 0,
+    /// and always if it is at least 1.
+    pub(crate) fn chance(&mut self, p: f64) -> bool {
+        // The 53 highest bits give a uniform float in [0, 1).
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[test]
+fn rotations_and_extensions() {
+    let mat = Hankel::prime_sum_matrix(1000, None);
+    let cycle = mat.rotation_extension_attempts(8, 1_000_000).unwrap();
+    assert!(mat.valid_cycle(&cycle) && cycle.len() == 1000);
+    assert_eq!(
+        mat.rotation_extension(3, 1_000_000),
+        mat.rotation_extension(3, 1_000_000)
+    );
+    // Odd prime sum graphs are bipartite with unequal sides.
+    assert_eq!(
+        Hankel::prime_sum_matrix(7, None).rotation_extension_attempts(4, 1000),
+        None
+    );
+    assert_eq!(
+        Hankel::from_sequence(3, &[3]).rotation_extension(0, 1000),
+        None
+    );
+    // A loop doesn't make a single vertex a cycle.
+    assert_eq!(
+        Hankel::from_sequence(1, &[2]).rotation_extension(0, 1000),
+        None
+    );
+}
@@ -0,0 +1,131 @@
+//! Accessors, validators and searches that take the indexing of the
+//! vertices as a parameter.
+//!
+//! Most of the library numbers the vertices from 1, like the numbers in the
+//! sequences, but [`Hankel::get_0_based`] and [`Hankel::print`] number them
+//! from 0. With an [`Indexing`] the caller states which one they use, instead
+//! of adding or subtracting 1 at every call.
+
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+/// Whether the first vertex is 0 or 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Indexing {
+    /// The vertices are 0 to n - 1, like the rows of [`Hankel::print`].
+    ZeroBased,
+    /// The vertices are 1 to n, like the numbers in the sequences.
+    #[default]
+    OneBased,
+}
+
+impl Indexing {
+    /// Returns the first vertex, 0 or 1.
+    pub fn first(self) -> usize {
+        match self {
+            Indexing::ZeroBased => 0,
+            Indexing::OneBased => 1,
+        }
+    }
+
+    /// Returns the 1-based number of the vertex `v`, or `None` if there is
+    /// none, that is if `v` is 0 for 1-based indexing, or `usize::MAX`.
+    pub fn to_one_based(self, v: usize) -> Option<usize> {
+        match self {
+            Indexing::ZeroBased => v.checked_add(1),
+            Indexing::OneBased => (v > 0).then_some(v),
+        }
+    }
+
+    /// Returns the vertex with the 1-based number `v`.
+    ///
+    /// # Panics
+    ///
+    /// If `v` is 0.
+    pub fn from_one_based(self, v: usize) -> usize {
+        v.checked_sub(1).expect("0 is not a 1-based vertex") + self.first()
+    }
+
+    /// Renumbers the vertices of `path` from this indexing to `other`.
+    ///
+    /// # Panics
+    ///
+    /// If a vertex is 0 while renumbering from 1-based to 0-based indexing,
+    /// or `usize::MAX` while renumbering from 0-based to 1-based indexing.
+    pub fn convert(self, path: &mut [usize], other: Indexing) {
+        for v in path {
+            *v = match (self, other) {
+                (Indexing::ZeroBased, Indexing::OneBased) => {
+                    v.checked_add(1).expect("usize::MAX has no 1-based number")
+                }
+                (Indexing::OneBased, Indexing::ZeroBased) => {
+                    v.checked_sub(1).expect("0 is not a 1-based vertex")
+                }
+                _ => *v,
+            };
+        }
+    }
+}
+
+impl Hankel {
+    /// Same as [`Hankel::try_get`], where `row` and `col` use the `indexing`.
+    pub fn get_indexed(&self, row: usize, col: usize, indexing: Indexing) -> Option<u8> {
+        self.try_get(indexing.to_one_based(row)?, indexing.to_one_based(col)?)
+    }
+
+    /// Same as [`Hankel::valid_path`], where `path` uses the `indexing`.
+    pub fn valid_path_indexed(&self, path: &[usize], indexing: Indexing) -> bool {
+        to_one_based(path, indexing).is_some_and(|path| self.valid_path(&path))
+    }
+
+    /// Same as [`Hankel::valid_cycle`], where `cycle` uses the `indexing`.
+    pub fn valid_cycle_indexed(&self, cycle: &[usize], indexing: Indexing) -> bool {
+        to_one_based(cycle, indexing).is_some_and(|cycle| self.valid_cycle(&cycle))
+    }
+
+    /// Same as [`Hankel::is_hamiltonian`], where the cycle uses the `indexing`.
+    pub fn is_hamiltonian_indexed(&self, indexing: Indexing) -> Option<Vec<usize>> {
+        let mut cycle = self.is_hamiltonian()?.into_vec();
+        Indexing::OneBased.convert(&mut cycle, indexing);
+        Some(cycle)
+    }
+}
+
+/// Returns the vertices of `path` numbered from 1, or `None` if one of
+/// them isn't a vertex in the `indexing`.
+fn to_one_based(path: &[usize], indexing: Indexing) -> Option<Vec<usize>> {
+    path.iter().map(|&v| indexing.to_one_based(v)).collect()
+}
+
+#[test]
+fn both_indexings() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(
+        mat.get_indexed(0, 1, Indexing::ZeroBased),
+        Some(mat.get(1, 2))
+    );
+    assert_eq!(
+        mat.get_indexed(1, 2, Indexing::OneBased),
+        Some(mat.get(1, 2))
+    );
+    assert_eq!(mat.get_indexed(0, 1, Indexing::OneBased), None);
+    assert_eq!(mat.get_indexed(5, 6, Indexing::ZeroBased), None);
+    let cycle = mat.is_hamiltonian_indexed(Indexing::ZeroBased).unwrap();
+    assert_eq!(cycle[0], 0);
+    assert!(mat.valid_cycle_indexed(&cycle, Indexing::ZeroBased));
+    assert!(mat.valid_path_indexed(&cycle, Indexing::ZeroBased));
+    // The 0-based cycle misses vertex 6, and 0 isn't a 1-based vertex.
+    assert!(!mat.valid_cycle_indexed(&cycle, Indexing::OneBased));
+    let mut one_based = cycle.clone();
+    Indexing::ZeroBased.convert(&mut one_based, Indexing::OneBased);
+    assert_eq!(Some(one_based), mat.is_hamiltonian().map(Vec::from));
+    assert_eq!(Indexing::OneBased.from_one_based(3), 3);
+    assert_eq!(Indexing::ZeroBased.from_one_based(3), 2);
+    assert_eq!(Indexing::default().to_one_based(0), None);
+    let mut path = [1, 2, 3];
+    Indexing::OneBased.convert(&mut path, Indexing::OneBased);
+    assert_eq!(path, [1, 2, 3]);
+    Indexing::OneBased.convert(&mut path, Indexing::ZeroBased);
+    assert_eq!(path, [0, 1, 2]);
+}
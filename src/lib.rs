@@ -1,9 +1,71 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+#[cfg(feature = "rayon")]
 use rayon::prelude::*;
-use std::borrow::Cow;
-use std::cmp::min;
+#[cfg(feature = "std")]
 use std::io;
-use std::io::Write;
-use std::vec;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+mod adjacency;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod cliques;
+mod coloring;
+mod connectivity;
+mod cycles;
+mod degrees;
+mod distances;
+mod error;
+mod eulerian;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod graph;
+mod heuristics;
+mod indexing;
+#[cfg(any(feature = "nalgebra", feature = "sprs"))]
+mod linalg;
+mod matching;
+mod optimal;
+mod paths;
+mod primeset;
+#[cfg(feature = "pyo3")]
+mod python;
+mod quadruplet;
+mod sparse;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+mod sweep;
+mod symmetry;
+mod walks;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+pub use adjacency::{Adjacency, Vertex};
+pub use degrees::is_graphical;
+pub use error::Error;
+pub use graph::SumGraph;
+pub use indexing::Indexing;
+pub use optimal::Objective;
+pub use paths::{Cycle, PartialPath, Path};
+pub use primeset::PrimeSet;
+pub use quadruplet::Quadruplet;
+pub use sparse::{DiagonalStorage, SparseHankel};
+pub use sweep::{verify_path_range, PathOptions, PathReport};
 
 #[derive(Debug)]
 /// A Hankel matrix is a matrix such that the entries along
@@ -32,100 +94,591 @@ use std::vec;
 /// let mat = primes::Hankel::prime_sum_matrix(6, Some(&primes));
 /// ```
 pub struct Hankel {
-    /// `diagonals` contains 2n-1 entries for an n by n matrix.
+    /// `diagonals` contains 2n-1 entries for an n by n matrix, or n entries
+    /// if the entries depend on the difference, see [`SumOrDiff`].
     diagonals: Vec<u8>,
     pub size: usize,
+    kind: SumOrDiff,
+    /// The degree of every vertex, kept up to date by [`Hankel::set`]
+    /// and [`Hankel::extend_to`].
+    degrees: Vec<usize>,
+}
+
+/// Whether the entry at row i and column j of a [`Hankel`] depends on i + j,
+/// like in the prime sum graphs, or on |i - j|, which gives a Toeplitz matrix.
+/// The matrices of the second kind are made with [`Hankel::from_differences`].
+///
+/// Both are stored in the same way, so every algorithm works for both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SumOrDiff {
+    /// Two vertices are adjacent if their sum is in the sequence.
+    #[default]
+    Sum,
+    /// Two vertices are adjacent if their difference is in the sequence.
+    Diff,
 }
 
 impl Hankel {
     /// Generate the Hankel matrix for the prime sum sequences of order n.
     ///
-    /// `primes` should be generated at least upto 2n + 1, because we need to check if
-    /// n + (n - 1) is prime
+    /// `primes` should be generated at least upto 2n - 1, because we need to check if
+    /// n + (n - 1) is prime. If it doesn't go far enough, the missing primes are
+    /// generated, which is slow. Use [`Hankel::try_prime_sum_matrix`] to get an error
+    /// instead. If `n` is 0, the matrix is empty.
+    ///
+    /// # Panics
+    ///
+    /// If 2n - 1 overflows.
     pub fn prime_sum_matrix(n: usize, primes: Option<&[usize]>) -> Self {
-        let mut diagonals = vec![0; 2 * n - 1];
+        match Self::try_prime_sum_matrix(n, primes) {
+            Ok(mat) => mat,
+            Err(Error::InsufficientPrimes { needed }) => {
+                let mut primes = primes.map_or_else(Vec::new, <[usize]>::to_vec);
+                extend_primes_upto_n(&mut primes, needed);
+                Self::from_primes(n, &primes)
+            }
+            Err(Error::SizeTooSmall { .. }) => Self::from_sequence(0, &[]),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    /// Same as [`Hankel::prime_sum_matrix`], but returns an error if `primes`
+    /// doesn't contain all the primes up to 2n - 1, or if `n` is 0 or so
+    /// large that 2n - 1 overflows.
+    pub fn try_prime_sum_matrix(n: usize, primes: Option<&[usize]>) -> Result<Self, Error> {
+        let needed = diagonal_count(n)?;
+        if let Some(p) = primes {
+            if !primes_cover(p, needed) {
+                return Err(Error::InsufficientPrimes { needed });
+            }
+        }
+        Ok(match primes {
+            Some(p) => Self::from_primes(n, p),
+            None => Self::from_primes(n, &gen_primes_upto_n(needed)),
+        })
+    }
+    /// Generate the prime sum matrix, where `p` contains all the primes up to 2n - 1.
+    fn from_primes(n: usize, p: &[usize]) -> Self {
+        let len = 2 * n - 1;
+        let mut diagonals = vec![0; len];
         let mut i = 1; // index 0 is zero
-        let p = match primes {
-            Some(p) => Cow::Borrowed(p),
-            None => Cow::Owned(gen_primes_upto_n(2 * n - 1)),
-        };
-        while i < 2 * n - 1 {
+        while i < len {
             if p.binary_search(&(i + 2)).is_ok() {
                 diagonals[i] = 1;
             }
             i += 2; // skip over the even numbers.
         }
-        Self { diagonals, size: n }
+        Self::with_diagonals(diagonals, n, SumOrDiff::Sum)
     }
     /// Generate a Hankel matrix of size `n`by `n` from `values`
     /// Note that the rows and colums are 1-indexed, i.e the top
     /// left corner of the matrix is at index (1,1).
+    ///
+    /// # Panics
+    ///
+    /// If 2n - 1 overflows.
     pub fn from_sequence(n: usize, sequence: &[usize]) -> Self {
-        let mut diagonals = vec![0; 2 * n - 1];
+        let len = match diagonal_count(n) {
+            Ok(len) => len,
+            Err(Error::SizeTooSmall { .. }) => 0,
+            Err(e) => panic!("{}", e),
+        };
+        let mut diagonals = vec![0; len];
         for (i, d) in diagonals.iter_mut().enumerate() {
             if sequence.binary_search(&(i + 2)).is_ok() {
                 *d = 1;
             }
         }
-        Self { diagonals, size: n }
+        Self::with_diagonals(diagonals, n, SumOrDiff::Sum)
+    }
+    /// Generate the Toeplitz matrix of the prime difference graph of order n,
+    /// where two vertices are adjacent if their difference is prime.
+    ///
+    /// `primes` should contain all the primes up to n - 1, otherwise they are
+    /// generated. Unlike the prime sum graphs, these graphs aren't bipartite,
+    /// since 2 is a difference of two odd or two even numbers. The searches
+    /// only consider cycles that alternate between odd and even numbers,
+    /// which are the cycles without a difference of 2.
+    pub fn prime_difference_matrix(n: usize, primes: Option<&[usize]>) -> Self {
+        match primes {
+            Some(p) if primes_cover(p, n.saturating_sub(1)) => Self::from_differences(n, p),
+            _ => Self::from_differences(n, &gen_primes_upto_n(n.saturating_sub(1))),
+        }
+    }
+    /// Generate a Toeplitz matrix of size `n` by `n`, where the entry at `row`
+    /// and `col` is 1 if |row - col| is in the sorted `sequence`. The vertices
+    /// are 1-indexed, like in [`Hankel::from_sequence`].
+    pub fn from_differences(n: usize, sequence: &[usize]) -> Self {
+        let diagonals = (0..n)
+            .map(|d| u8::from(sequence.binary_search(&d).is_ok()))
+            .collect();
+        Self::with_diagonals(diagonals, n, SumOrDiff::Diff)
+    }
+    /// Generate a random Hankel matrix of size `n` by `n`, where every sum
+    /// from 2 to 2n is in the sequence with probability `density`. The same
+    /// `seed` gives the same matrix.
+    ///
+    /// # Panics
+    ///
+    /// If 2n - 1 overflows.
+    pub fn random(n: usize, density: f64, seed: u64) -> Self {
+        let len = match diagonal_count(n) {
+            Ok(len) => len,
+            Err(Error::SizeTooSmall { .. }) => 0,
+            Err(e) => panic!("{}", e),
+        };
+        let mut rng = heuristics::Rng::new(seed);
+        let diagonals = (0..len).map(|_| u8::from(rng.chance(density))).collect();
+        Self::with_diagonals(diagonals, n, SumOrDiff::Sum)
+    }
+    /// Makes the matrix from its `diagonals`, and computes the degrees.
+    pub(crate) fn with_diagonals(diagonals: Vec<u8>, size: usize, kind: SumOrDiff) -> Self {
+        let mut mat = Self {
+            diagonals,
+            size,
+            kind,
+            degrees: Vec::new(),
+        };
+        mat.degrees = mat.compute_degrees();
+        mat
+    }
+    /// Makes a matrix of size `n` from its `diagonals`, where the entry at row i
+    /// and column j is `diagonals[i + j]` or `diagonals[|i - j|]`, with 0-based i
+    /// and j, depending on the `kind`. So there should be 2n - 1 diagonals for
+    /// [`SumOrDiff::Sum`] (or none if n is 0) and n for [`SumOrDiff::Diff`].
+    ///
+    /// Returns an error if the number of diagonals is wrong, or if one
+    /// of them is not 0 or 1.
+    pub fn from_raw_parts(diagonals: Vec<u8>, n: usize, kind: SumOrDiff) -> Result<Self, Error> {
+        let expected = match kind {
+            SumOrDiff::Sum => match diagonal_count(n) {
+                Err(Error::SizeTooSmall { .. }) => 0,
+                count => count?,
+            },
+            SumOrDiff::Diff => n,
+        };
+        if diagonals.len() != expected {
+            return Err(Error::InvalidDiagonals {
+                expected,
+                len: diagonals.len(),
+            });
+        }
+        if let Some((index, &value)) = diagonals.iter().enumerate().find(|(_, &d)| d > 1) {
+            return Err(Error::InvalidEntry { index, value });
+        }
+        Ok(Self::with_diagonals(diagonals, n, kind))
+    }
+    /// Returns the diagonals, the size and the kind, which give the same
+    /// matrix with [`Hankel::from_raw_parts`].
+    pub fn into_raw_parts(self) -> (Vec<u8>, usize, SumOrDiff) {
+        (self.diagonals, self.size, self.kind)
+    }
+    /// Returns the values on the diagonals, see [`Hankel::from_raw_parts`].
+    pub fn diagonals(&self) -> &[u8] {
+        &self.diagonals
+    }
+    /// Returns the sums from 2 to 2n whose diagonal is 1, in increasing order,
+    /// so that [`Hankel::from_sequence`] gives the same matrix. For the kind
+    /// [`SumOrDiff::Diff`] these are the differences from 0 to n - 1 instead,
+    /// like in [`Hankel::from_differences`].
+    pub fn sum_set(&self) -> Vec<usize> {
+        let offset = match self.kind {
+            SumOrDiff::Sum => 2,
+            SumOrDiff::Diff => 0,
+        };
+        (0..self.diagonals.len())
+            .filter(|&d| self.diagonals[d] != 0)
+            .map(|d| d + offset)
+            .collect()
+    }
+    /// Returns the graph with the edges of both graphs, so where the sum (or
+    /// difference) is in either sequence.
+    ///
+    /// # Panics
+    ///
+    /// If the sizes or the kinds of the matrices are different, see
+    /// [`Hankel::try_union`].
+    pub fn union(&self, other: &Hankel) -> Self {
+        self.try_union(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Same as [`Hankel::union`], but returns an error if the sizes or the
+    /// kinds of the matrices are different.
+    pub fn try_union(&self, other: &Hankel) -> Result<Self, Error> {
+        self.combine(other, |a, b| a | b)
+    }
+    /// Returns the graph with the edges that are in both graphs, so where
+    /// the sum (or difference) is in both sequences.
+    ///
+    /// # Panics
+    ///
+    /// If the sizes or the kinds of the matrices are different, see
+    /// [`Hankel::try_intersection`].
+    pub fn intersection(&self, other: &Hankel) -> Self {
+        self.try_intersection(other)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Same as [`Hankel::intersection`], but returns an error if the sizes or
+    /// the kinds of the matrices are different.
+    pub fn try_intersection(&self, other: &Hankel) -> Result<Self, Error> {
+        self.combine(other, |a, b| a & b)
+    }
+    /// Combines the diagonals of two matrices of the same size and kind.
+    fn combine(&self, other: &Hankel, op: impl Fn(u8, u8) -> u8) -> Result<Self, Error> {
+        if self.size != other.size {
+            return Err(Error::SizeMismatch {
+                left: self.size,
+                right: other.size,
+            });
+        }
+        if self.kind != other.kind {
+            return Err(Error::KindMismatch {
+                left: self.kind,
+                right: other.kind,
+            });
+        }
+        let diagonals = self
+            .diagonals
+            .iter()
+            .zip(&other.diagonals)
+            .map(|(&a, &b)| op(a, b))
+            .collect();
+        Ok(Self::with_diagonals(diagonals, self.size, self.kind))
+    }
+    /// Returns whether the entries depend on the sum or the difference
+    /// of the row and the column.
+    pub fn kind(&self) -> SumOrDiff {
+        self.kind
+    }
+    /// Sets the entry at `row` and `col`, which are 1-indexed, to `value`.
+    /// Every entry on the same diagonal, with the same sum or difference,
+    /// is set as well.
+    ///
+    /// Only the degrees of the vertices on the diagonal are updated.
+    ///
+    /// # Panics
+    ///
+    /// If `row` or `col` is not between 1 and the size, or `value` is not
+    /// 0 or 1.
+    pub fn set(&mut self, row: usize, col: usize, value: u8) {
+        if let Err(e) = self.try_set(row, col, value) {
+            panic!("{}", e);
+        }
+    }
+    /// Same as [`Hankel::set`], but returns an error if `row` or `col` is not
+    /// between 1 and the size, or `value` is not 0 or 1. Nothing changes then.
+    pub fn try_set(&mut self, row: usize, col: usize, value: u8) -> Result<(), Error> {
+        for vertex in [row, col] {
+            if !(1..=self.size).contains(&vertex) {
+                return Err(Error::InvalidVertex {
+                    vertex,
+                    size: self.size,
+                });
+            }
+        }
+        let d = match self.kind {
+            SumOrDiff::Sum => row + col - 2,
+            SumOrDiff::Diff => row.abs_diff(col),
+        };
+        if value > 1 {
+            return Err(Error::InvalidEntry { index: d, value });
+        }
+        let old = core::mem::replace(&mut self.diagonals[d], value);
+        if (old != 0) == (value != 0) {
+            return Ok(());
+        }
+        // Every vertex has one entry on the diagonal, or two for a
+        // nonzero difference where both are in the matrix.
+        let n = self.size;
+        let mut change = |v: usize| {
+            if value != 0 {
+                self.degrees[v] += 1;
+            } else {
+                self.degrees[v] -= 1;
+            }
+        };
+        match self.kind {
+            SumOrDiff::Sum => (d.saturating_sub(n - 1)..=d.min(n - 1)).for_each(change),
+            SumOrDiff::Diff => {
+                (d..n).for_each(&mut change);
+                if d > 0 {
+                    (0..n - d).for_each(change);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Grows the matrix to size `n` by `n`, where the new entries are 1 if their
+    /// sum (or difference) is in the sorted `sequence`, like in
+    /// [`Hankel::from_sequence`]. A prime sum matrix is extended with the primes
+    /// up to 2n - 1. If `n` is not larger than the size, nothing changes.
+    ///
+    /// Only the entries in the new rows and columns are visited to update the
+    /// degrees, so growing the matrix one size at a time is cheaper than
+    /// making a new one for every size.
+    ///
+    /// # Panics
+    ///
+    /// If 2n - 1 overflows.
+    pub fn extend_to(&mut self, n: usize, sequence: &[usize]) {
+        let m = self.size;
+        if n <= m {
+            return;
+        }
+        let len = match self.kind {
+            SumOrDiff::Sum => diagonal_count(n).unwrap_or_else(|e| panic!("{}", e)),
+            SumOrDiff::Diff => n,
+        };
+        let first = self.diagonals.len();
+        let offset = match self.kind {
+            SumOrDiff::Sum => 2,
+            SumOrDiff::Diff => 0,
+        };
+        self.diagonals
+            .extend((first..len).map(|d| u8::from(sequence.binary_search(&(d + offset)).is_ok())));
+        self.size = n;
+        // The old vertices get the entries in the new columns.
+        for v in 0..m {
+            self.degrees[v] += (m..n)
+                .map(|w| self.get_0_based(v, w) as usize)
+                .sum::<usize>();
+        }
+        let mut new = vec![0; n - m];
+        self.degrees_from(m, &mut new);
+        self.degrees.extend(new);
     }
     /// Get the entry in the matrix at the specified
     /// `row` and `col`. The first row and collumn
     /// are 1, i.e. the indexing starts at 1.
     pub fn get(&self, row: usize, col: usize) -> u8 {
-        self.diagonals[row + col - 2]
+        self.get_0_based(row - 1, col - 1)
     }
     /// Get the entry in the matrix at the specified
     /// `row` and `col`. The first row and collumn
     /// are 0, i.e. the indexing starts at 0.
     pub fn get_0_based(&self, row: usize, col: usize) -> u8 {
-        self.diagonals[row + col]
+        match self.kind {
+            SumOrDiff::Sum => self.diagonals[row + col],
+            SumOrDiff::Diff => self.diagonals[row.abs_diff(col)],
+        }
+    }
+    /// Same as [`Hankel::get`], but returns `None` if
+    /// `row` or `col` is not between 1 and the size.
+    pub fn try_get(&self, row: usize, col: usize) -> Option<u8> {
+        if row == 0 || col == 0 {
+            return None;
+        }
+        self.try_get_0_based(row - 1, col - 1)
+    }
+    /// Same as [`Hankel::get_0_based`], but returns `None`
+    /// if `row` or `col` is not smaller than the size.
+    pub fn try_get_0_based(&self, row: usize, col: usize) -> Option<u8> {
+        if row >= self.size || col >= self.size {
+            return None;
+        }
+        Some(self.get_0_based(row, col))
     }
     /// Checks if `path` is a valid Hamiltonian path in
     /// the current graph.
+    ///
+    /// Returns false if the path contains vertices that are not in the graph,
+    /// or contains a vertex more than once.
     pub fn valid_path(&self, path: &[usize]) -> bool {
-        for i in 0..(path.len() - 1) {
-            match self.get(path[i], path[i + 1]) {
-                0 => return false,
-                _ => continue,
-            }
-        }
-        true
+        SumGraph::valid_path(self, path)
     }
     /// Checks if `cycle` is a valid Hamiltonian cycle in
     /// the current graph.
+    ///
+    /// Returns false if the cycle is empty, or contains vertices
+    /// that are not in the graph. Loops are ignored, so a single vertex is
+    /// never a cycle.
     pub fn valid_cycle(&self, cycle: &[usize]) -> bool {
-        for i in 0..(cycle.len() - 1) {
-            match self.get(cycle[i], cycle[i + 1]) {
-                0 => return false,
-                _ => continue,
-            }
-        }
-        self.get(cycle[0], cycle[cycle.len() - 1]) != 0
+        SumGraph::valid_cycle(self, cycle)
     }
     /// If there is a cycle return it. Otherwise return None.
-    pub fn is_hamiltonian(&self) -> Option<Vec<usize>> {
+    ///
+    /// Loops are ignored, like in [`Hankel::valid_cycle`], so a single vertex
+    /// is never a cycle.
+    pub fn is_hamiltonian(&self) -> Option<Cycle> {
+        let mut path = Vec::new();
+        self.is_hamiltonian_into(&mut path)
+            .then(|| Cycle::found(path, self))
+    }
+    /// Same as [`Hankel::is_hamiltonian`], but the cycle is written to
+    /// `path_buf`, so that its memory can be reused for the next search.
+    ///
+    /// Returns false if there is no cycle, in which case the contents
+    /// of `path_buf` are unspecified.
+    pub fn is_hamiltonian_into(&self, path_buf: &mut Vec<usize>) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        path_buf.clear();
+        path_buf.resize(self.size, 0);
+        path_buf[0] = 1;
+        let limits = SearchLimits::default();
+        let mut stats = SearchStats::default();
+        self.hamiltonian_cycle_iterative(path_buf, 1, &limits, &mut stats) == SearchResult::Found
+    }
+    /// Counts the Hamiltonian cycles, where a cycle and its reverse are
+    /// counted once. Like the search, only cycles that alternate between
+    /// odd and even numbers are considered, so for odd sizes this is 0.
+    ///
+    /// All the cycles are enumerated, so this is only feasible for small sizes.
+    /// Every cycle starts at 1 and is only followed in the direction where the
+    /// second vertex is smaller than the last one, and a partial cycle is
+    /// dropped as soon as no unused neighbor of 1 larger than the second
+    /// vertex is left to end it.
+    pub fn count_hamiltonian_cycles(&self) -> u64 {
+        if self.size < 2 || !self.size.is_multiple_of(2) {
+            return 0;
+        }
+        if self.size == 2 {
+            return u64::from(self.get(1, 2) != 0);
+        }
         let mut path = vec![0; self.size];
         path[0] = 1;
-        if self.hamiltonian_cycle(&mut path, 1) {
-            Some(path)
-        } else {
-            None
+        let mut used = vec![false; self.size + 1];
+        used[1] = true;
+        self.count_cycles_from(&mut path, &mut used, 1, 0)
+    }
+    /// Counts the ways to complete the first `pos` entries of `path` to a
+    /// cycle, where `open` is the number of vertices that can still be the
+    /// last one, see [`Hankel::orient`].
+    fn count_cycles_from(
+        &self,
+        path: &mut [usize],
+        used: &mut [bool],
+        pos: usize,
+        open: usize,
+    ) -> u64 {
+        if pos == self.size {
+            return (self.get(path[0], path[pos - 1]) != 0) as u64;
+        }
+        let mut count = 0;
+        // the sequence alternates between odd and even
+        for n in (1 + pos % 2..=self.size).step_by(2) {
+            if used[n] || self.get(path[pos - 1], n) == 0 {
+                continue;
+            }
+            let Some(open) = self.orient(path, pos, n, open) else {
+                continue;
+            };
+            path[pos] = n;
+            used[n] = true;
+            count += self.count_cycles_from(path, used, pos + 1, open);
+            used[n] = false;
+        }
+        count
+    }
+    /// Checks if `w` can be the last vertex of a cycle that starts with
+    /// `first` and `second`, in the direction where `second` is smaller.
+    fn is_closer(&self, first: usize, second: usize, w: usize) -> bool {
+        w > second && w != first && w % 2 == self.size % 2 && self.get(first, w) != 0
+    }
+    /// Breaks the symmetry of the searches that start from a single vertex:
+    /// every cycle is found in both directions, so only the direction where
+    /// the second vertex is smaller than the last one is searched. This
+    /// roughly halves the search space.
+    ///
+    /// `open` is the number of unused vertices that can still be the last
+    /// one. Returns the number after `w` is put at `pos`, or `None` if there
+    /// is no cycle in the right direction anymore. The size should be at
+    /// least 3.
+    fn orient(&self, path: &[usize], pos: usize, w: usize, open: usize) -> Option<usize> {
+        if pos == 1 {
+            let closers = (2..=self.size)
+                .filter(|&v| self.is_closer(path[0], w, v))
+                .count();
+            return (closers > 0).then_some(closers);
         }
+        let closer = self.is_closer(path[0], path[1], w);
+        if pos + 1 == self.size {
+            return closer.then(|| open - 1);
+        }
+        let open = open - usize::from(closer);
+        (open > 0).then_some(open)
+    }
+    /// Checks that `path` has an entry for every vertex, and that its first
+    /// `pos` entries, of which there is at least one, are a valid path.
+    ///
+    /// The searches only try odd numbers at even positions and the other way
+    /// around, so the path should also start with an odd number and alternate.
+    fn valid_start(&self, path: &[usize], pos: usize) -> bool {
+        path.len() == self.size
+            && (1..=self.size).contains(&pos)
+            && self.valid_path(&path[..pos])
+            && path[..pos].iter().enumerate().all(|(i, v)| v % 2 != i % 2)
     }
     /// Tries to make a Hamiltonian cycle out of `path` using backtracking
     ///
     /// The values in the path before `pos` are left unchanged.
-    /// Returns false if no cycle was constructed.
+    /// Returns false if no cycle was constructed. This includes the cases
+    /// where the length of `path` is not the size of the matrix, `pos` is 0,
+    /// or the first `pos` entries are not a valid path that alternates between
+    /// odd and even numbers, starting with an odd number. With a
+    /// [`PartialPath`] and [`Hankel::complete_cycle`], those are checked when
+    /// the path is made instead.
     pub fn hamiltonian_cycle(&self, path: &mut [usize], pos: usize) -> bool {
+        self.hamiltonian_cycle_with_stats(path, pos, &mut SearchStats::default())
+    }
+    /// Same as [`Hankel::hamiltonian_cycle`], but also keeps track of
+    /// statistics about the search in `stats`.
+    pub fn hamiltonian_cycle_with_stats(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        stats: &mut SearchStats,
+    ) -> bool {
+        self.hamiltonian_cycle_with_limits(path, pos, &SearchLimits::default(), stats)
+            == SearchResult::Found
+    }
+    /// Same as [`Hankel::hamiltonian_cycle_with_stats`], but the search is
+    /// aborted as soon as one of the `limits` is reached.
+    ///
+    /// If the search was aborted, the values in the path from `pos` onwards
+    /// are unspecified.
+    pub fn hamiltonian_cycle_with_limits(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        if !self.valid_start(path, pos) {
+            return SearchResult::NotFound;
+        }
+        // A longer start already fixes the direction of the cycle.
+        let open = (pos == 1 && self.size > 2).then_some(0);
+        self.extend_recursively(path, pos, limits, stats, open)
+    }
+    /// Returns the largest vertex that can be at `pos` in a path that
+    /// alternates between odd and even numbers, starting with an odd number.
+    fn largest_candidate(&self, pos: usize) -> usize {
+        self.size - (self.size + pos + 1) % 2
+    }
+    /// The recursive search of [`Hankel::hamiltonian_cycle_with_limits`],
+    /// after the arguments have been checked. If the direction of the cycle
+    /// isn't fixed yet, `open` is the number of vertices that can still be
+    /// the last one, see [`Hankel::orient`].
+    fn extend_recursively(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+        open: Option<usize>,
+    ) -> SearchResult {
         if pos == self.size {
             // println!("cur length {}", cur_length);
-            return self.get(path[0], path[pos - 1]) != 0;
+            if SumGraph::adjacent(self, path[0], path[pos - 1]) {
+                return SearchResult::Found;
+            }
+            stats.record_dead_end(path, pos);
+            return SearchResult::NotFound;
         }
         // the sequence alternates between odd and even
         // loop backwards, because we are reusing the previously found cycles
         // which are all made up of smaller numbers
-        let mut n = self.size - (pos + 1) % 2;
+        let mut n = self.largest_candidate(pos);
         'outer: while n > 1 {
             if self.get(path[pos - 1], n) == 0 {
                 n -= 2;
@@ -139,45 +692,193 @@ impl Hankel {
                 }
                 j += 2;
             }
+            let next_open = match open.map(|open| self.orient(path, pos, n, open)) {
+                Some(None) => {
+                    n -= 2;
+                    continue;
+                }
+                next_open => next_open.flatten(),
+            };
             path[pos] = n;
-            if self.hamiltonian_cycle(path, pos + 1) {
-                return true;
+            stats.nodes += 1;
+            if limits.reached(stats, pos + 1) {
+                return SearchResult::Aborted;
+            }
+            match self.extend_recursively(path, pos + 1, limits, stats, next_open) {
+                SearchResult::NotFound => {}
+                result => return result,
             }
             path[pos] = 0;
             n -= 2;
         }
-        false
+        stats.record_dead_end(path, pos);
+        SearchResult::NotFound
     }
-    /// Prints the associated adjacency matrix to stdout.
-    pub fn print(&self) -> io::Result<()> {
-        let mut output = io::BufWriter::new(io::stdout());
-        for row in 0..self.size {
-            for col in 0..(self.size - 1) {
-                write!(&mut output, "{}, ", self.get_0_based(row, col))?;
+    /// Same as [`Hankel::hamiltonian_cycle_with_limits`], but the backtracking
+    /// is done in a loop instead of recursion, with `path` itself as the stack.
+    /// This makes it suitable for large sizes, where the recursive version would
+    /// overflow the stack of the thread, and it doesn't allocate any memory.
+    ///
+    /// The candidates are tried in the same order as in the recursive version,
+    /// so both find the same cycle and gather the same statistics.
+    pub fn hamiltonian_cycle_iterative(
+        &self,
+        path: &mut [usize],
+        pos: usize,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        if !self.valid_start(path, pos) {
+            return SearchResult::NotFound;
+        }
+        let start = pos;
+        if start == self.size {
+            if SumGraph::adjacent(self, path[0], path[start - 1]) {
+                return SearchResult::Found;
             }
-            writeln!(&mut output, "{}", self.get_0_based(row, self.size - 1))?;
+            stats.record_dead_end(path, start);
+            return SearchResult::NotFound;
+        }
+        let mut pos = start;
+        // A longer start already fixes the direction of the cycle, see
+        // `Hankel::orient`.
+        let oriented = start == 1 && self.size > 2;
+        let mut open = 0;
+        // The next candidate to try at position `pos`. When backtracking,
+        // this is the one after the candidate in the path, so that no
+        // other memory is needed.
+        // the sequence alternates between odd and even
+        let mut next = self.largest_candidate(pos);
+        loop {
+            let mut n = next;
+            let mut placed = false;
+            'outer: while n > 1 {
+                if self.get(path[pos - 1], n) == 0 {
+                    n -= 2;
+                    continue;
+                }
+                let mut j = pos % 2;
+                while j < pos {
+                    if path[j] == n {
+                        n -= 2;
+                        continue 'outer;
+                    }
+                    j += 2;
+                }
+                if oriented {
+                    match self.orient(path, pos, n, open) {
+                        Some(next_open) => open = next_open,
+                        None => {
+                            n -= 2;
+                            continue;
+                        }
+                    }
+                }
+                path[pos] = n;
+                stats.nodes += 1;
+                if limits.reached(stats, pos + 1) {
+                    return SearchResult::Aborted;
+                }
+                placed = true;
+                break;
+            }
+            if placed {
+                pos += 1;
+                if pos < self.size {
+                    next = self.largest_candidate(pos);
+                    continue;
+                }
+                if SumGraph::adjacent(self, path[0], path[pos - 1]) {
+                    return SearchResult::Found;
+                }
+                stats.record_dead_end(path, pos);
+            } else {
+                stats.record_dead_end(path, pos);
+                if pos == start {
+                    return SearchResult::NotFound;
+                }
+            }
+            // Backtrack
+            pos -= 1;
+            if oriented && pos >= 2 && self.is_closer(path[0], path[1], path[pos]) {
+                open += 1;
+            }
+            next = path[pos] - 2;
+            path[pos] = 0;
         }
-        output.flush()?;
-        Ok(())
+    }
+    /// Prints the associated adjacency matrix to stdout.
+    #[cfg(feature = "std")]
+    pub fn print(&self) -> io::Result<()> {
+        self.write_matrix(io::stdout().lock(), 1 << 16)
+    }
+    /// Returns an iterator over the edges `(i, j)` with `i < j` of the
+    /// associated graph. The vertices are 1-indexed, like in [`Hankel::get`].
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (1..=self.size).flat_map(move |i| {
+            ((i + 1)..=self.size)
+                .filter(move |&j| self.get(i, j) != 0)
+                .map(move |j| (i, j))
+        })
     }
     /// Returns the degrees of all of the vertices in the graph.
     ///
     /// If the current `size` is n, then the returned vector has
     /// length n.
     ///
-    /// The degrees are recalculated at every call.
+    /// The degrees are computed when the matrix is made, and kept up to date
+    /// afterwards, see [`Hankel::degrees`] to get them without a copy.
     pub fn vertex_degrees(&self) -> Vec<usize> {
-        let mut degrees = Vec::with_capacity(self.size);
-        if self.size == 0 {
-            return degrees;
+        self.degrees.clone()
+    }
+    /// Returns the degrees of all of the vertices, like [`Hankel::vertex_degrees`].
+    pub fn degrees(&self) -> &[usize] {
+        &self.degrees
+    }
+    /// Computes the degrees of all of the vertices from the diagonals.
+    fn compute_degrees(&self) -> Vec<usize> {
+        let mut degrees = vec![0; self.size];
+        // Every chunk of vertices is done separately, so that
+        // the chunks can be done in parallel.
+        #[cfg(feature = "rayon")]
+        {
+            let chunk_size = self.size.div_ceil(rayon::current_num_threads()).max(1);
+            degrees
+                .par_chunks_mut(chunk_size)
+                .enumerate()
+                .for_each(|(i, chunk)| self.degrees_from(i * chunk_size, chunk));
         }
-        let mut first = self.diagonals[0] as usize;
-        let mut sum = first;
-        // Calculate the degree of the first vertex
-        for i in 1..self.size {
-            sum += self.diagonals[i] as usize;
+        #[cfg(not(feature = "rayon"))]
+        self.degrees_from(0, &mut degrees);
+        degrees
+    }
+    /// Fills `degrees` with the degrees of the vertices starting
+    /// from the 0-based vertex `start`.
+    fn degrees_from(&self, start: usize, degrees: &mut [usize]) {
+        if degrees.is_empty() {
+            return;
         }
-        degrees.push(sum);
+        if self.kind == SumOrDiff::Diff {
+            // The i-th vertex has the differences 0 to i with the vertices
+            // before it, and 0 to n - 1 - i with the ones after it, so its
+            // degree is a sum of two prefix sums, which share the entry for 0.
+            let mut prefix = Vec::with_capacity(self.size);
+            let mut sum = 0;
+            for &d in &self.diagonals {
+                sum += d as usize;
+                prefix.push(sum);
+            }
+            for (i, degree) in (start..).zip(degrees) {
+                *degree = prefix[i] + prefix[self.size - 1 - i] - self.diagonals[0] as usize;
+            }
+            return;
+        }
+        // Calculate the degree of the first vertex
+        let mut sum = self.diagonals[start..start + self.size]
+            .iter()
+            .map(|&d| d as usize)
+            .sum();
+        degrees[0] = sum;
         // The degree of the i-th vertex is the sum over the entries
         // in the diagonals array from i to i + n, where n is self.size
         // so if the diagonals array is something like:
@@ -189,13 +890,171 @@ impl Hankel {
         // 4th: 1+0+1+0+0+0 = 2
         // 5th: 0+1+0+0+0+1 = 2
         // 6th: 1+0+0+0+1+0 = 2
-        for i in self.size..self.diagonals.len() {
-            sum += self.diagonals[i] as usize;
-            sum -= first;
-            first = self.diagonals[i - self.size + 1] as usize;
-            degrees.push(sum);
+        for (i, degree) in (start + 1..).zip(&mut degrees[1..]) {
+            sum += self.diagonals[i + self.size - 1] as usize;
+            sum -= self.diagonals[i - 1] as usize;
+            *degree = sum;
+        }
+    }
+    /// Returns the number of edges of the associated graph, like
+    /// `self.edges().count()`, but without visiting every pair of vertices.
+    pub fn edge_count(&self) -> usize {
+        // A vertex i is only adjacent to itself if 2i is in the sequence, or
+        // every vertex if 0 is. Such a loop is counted once in the degree
+        // of i, and is not an edge.
+        let loops = match self.kind {
+            SumOrDiff::Sum => self
+                .diagonals
+                .iter()
+                .step_by(2)
+                .filter(|&&d| d != 0)
+                .count(),
+            SumOrDiff::Diff => self.size * self.diagonals.first().map_or(0, |&d| d as usize),
+        };
+        (self.degrees.iter().sum::<usize>() - loops) / 2
+    }
+}
+
+impl TryFrom<Vec<u8>> for Hankel {
+    type Error = Error;
+
+    /// Makes the matrix whose 2n - 1 diagonals are `diagonals`, like
+    /// [`Hankel::from_raw_parts`] with [`SumOrDiff::Sum`]. An even number
+    /// of diagonals gives [`Error::InvalidDiagonals`], except for none,
+    /// which gives the empty matrix.
+    fn try_from(diagonals: Vec<u8>) -> Result<Self, Error> {
+        let n = diagonals.len().div_ceil(2);
+        Self::from_raw_parts(diagonals, n, SumOrDiff::Sum)
+    }
+}
+
+/// Removes `vertex` from the Hamiltonian cycle `cycle`, which
+/// gives a Hamiltonian path in the graph without `vertex`.
+///
+/// The path starts right after `vertex` in the cycle.
+/// Returns `None` if `vertex` is not in the cycle.
+pub fn cycle_to_path(cycle: &[usize], vertex: usize) -> Option<Vec<usize>> {
+    let index = cycle.iter().position(|&v| v == vertex)?;
+    let mut path = Vec::with_capacity(cycle.len() - 1);
+    path.extend_from_slice(&cycle[(index + 1)..]);
+    path.extend_from_slice(&cycle[..index]);
+    Some(path)
+}
+
+/// Returns the number of edges that the cycles `a` and `b` have in common,
+/// where the last vertex of a cycle is joined to the first one.
+///
+/// The cycles can have different sizes, for example to see how many edges of
+/// the cycle of size n are still in the one of size n + 2 that was found from
+/// it.
+pub fn cycle_edge_overlap(a: &[usize], b: &[usize]) -> usize {
+    let edges = |c: &[usize]| {
+        let next = c.iter().cycle().skip(1);
+        let mut edges = c
+            .iter()
+            .zip(next)
+            .map(|(&v, &w)| (v.min(w), v.max(w)))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    };
+    let edges_a = edges(a);
+    edges(b)
+        .iter()
+        .filter(|e| edges_a.binary_search(e).is_ok())
+        .count()
+}
+
+/// Returns the number of positions where the cycles `a` and `b` have a
+/// different vertex, where the positions that only one of them has count
+/// as different.
+///
+/// This only makes sense for cycles that start at the same vertex, in the
+/// same direction, like the ones found by the searches, which start at 1.
+/// Otherwise [`cycle_edge_overlap`] is a better measure.
+pub fn hamming_distance(a: &[usize], b: &[usize]) -> usize {
+    let different = a.iter().zip(b).filter(|(v, w)| v != w).count();
+    different + a.len().abs_diff(b.len())
+}
+
+/// The result of a backtracking search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchResult {
+    /// A Hamiltonian cycle was found.
+    Found,
+    /// The whole search space was explored, without finding a cycle.
+    NotFound,
+    /// One of the [`SearchLimits`] was reached before the search finished.
+    Aborted,
+}
+
+/// Limits after which a backtracking search is aborted.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchLimits<'a> {
+    /// The search is aborted once this moment has passed.
+    #[cfg(feature = "std")]
+    pub deadline: Option<Instant>,
+    /// The search is aborted once this many nodes are visited.
+    pub max_nodes: Option<u64>,
+    /// If given, the search reports its progress here, so it can
+    /// be followed from another thread.
+    pub progress: Option<&'a SearchProgress>,
+}
+
+impl SearchLimits<'_> {
+    /// Checks if the search should be aborted, when the
+    /// current partial path has length `depth`.
+    ///
+    /// Looking up the time is relatively slow, so the deadline is
+    /// only checked, and the progress updated, every 1024 nodes.
+    fn reached(&self, stats: &SearchStats, depth: usize) -> bool {
+        if let Some(progress) = self.progress {
+            if stats.nodes.is_multiple_of(1024) {
+                progress.nodes.store(stats.nodes, Ordering::Relaxed);
+                progress.depth.store(depth, Ordering::Relaxed);
+            }
+        }
+        if self
+            .max_nodes
+            .is_some_and(|max_nodes| stats.nodes >= max_nodes)
+        {
+            return true;
+        }
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.deadline {
+            return stats.nodes.is_multiple_of(1024) && Instant::now() >= deadline;
+        }
+        false
+    }
+}
+
+/// The progress of a running backtracking search.
+#[derive(Debug, Default)]
+pub struct SearchProgress {
+    /// The number of nodes visited so far.
+    pub nodes: AtomicU64,
+    /// The length of the current partial path.
+    pub depth: AtomicUsize,
+}
+
+/// Statistics gathered during a backtracking search.
+#[derive(Debug, Default, Clone)]
+pub struct SearchStats {
+    /// The number of times a vertex was added to the path.
+    pub nodes: u64,
+    /// The longest partial path that the search had to backtrack from.
+    pub best_path: Vec<usize>,
+}
+
+impl SearchStats {
+    /// Called when the search could not extend the first `pos`
+    /// entries of `path`.
+    fn record_dead_end<V: Vertex>(&mut self, path: &[V], pos: usize) {
+        if pos > self.best_path.len() {
+            self.best_path.clear();
+            self.best_path.extend(path[..pos].iter().map(|v| v.index()));
         }
-        degrees
     }
 }
 
@@ -208,19 +1067,56 @@ pub struct HamiltonianCycle {
     half_size: usize,
     /// The current vertex in the cycle.
     current: usize,
+    /// The number of vertices that are left. If the primes are invalid,
+    /// this makes sure the iterator still stops.
+    remaining: usize,
 }
 
 impl HamiltonianCycle {
     /// The number of vertices in the graph is: 2n = 2 * half_size.
     /// The two primes should be such that p1 + 2n and p2 + 2n
     /// are both prime, and such that gcd((p2-p1)/2, n) = 1.
+    ///
+    /// The primes are not checked, see [`HamiltonianCycle::try_new`] for that.
     pub fn new(prime1: usize, prime2: usize, half_size: usize) -> Self {
         Self {
-            difference1: (prime1 - 1) / 2,
-            difference2: (prime2 - 1) / 2,
+            difference1: prime1.saturating_sub(1) / 2 % half_size.max(1),
+            difference2: prime2.saturating_sub(1) / 2 % half_size.max(1),
             half_size,
             current: 0,
+            remaining: half_size.saturating_mul(2),
+        }
+    }
+
+    /// Same as [`HamiltonianCycle::new`], but returns an error if the primes
+    /// don't satisfy the conditions, like the ones returned by [`find_prime_quadruplet`].
+    pub fn try_new(prime1: usize, prime2: usize, half_size: usize) -> Result<Self, Error> {
+        // The primes are at most 2n, so the sums are at most 4n.
+        if half_size.checked_mul(4).is_none() {
+            return Err(Error::SizeTooLarge {
+                size: half_size,
+                maximum: usize::MAX / 4,
+            });
+        }
+        let is_prime_or_one = |p: usize| p == 1 || is_prime_by_trial_division(p);
+        let valid = half_size >= 2
+            && prime1 < prime2
+            && prime2 <= 2 * half_size
+            && prime1 % 2 == 1
+            && prime2 % 2 == 1
+            && is_prime_or_one(prime1)
+            && is_prime_by_trial_division(prime2)
+            && is_prime_by_trial_division(prime1 + 2 * half_size)
+            && is_prime_by_trial_division(prime2 + 2 * half_size)
+            && gcd((prime2 - prime1) / 2, half_size) == 1;
+        if !valid {
+            return Err(Error::InvalidQuadruplet {
+                prime1,
+                prime2,
+                half_size,
+            });
         }
+        Ok(Self::new(prime1, prime2, half_size))
     }
 
     fn x_j(&self, j: usize) -> usize {
@@ -233,7 +1129,7 @@ impl HamiltonianCycle {
 
     // x_j = 2 j - 1, so j = (x_j + 1) / 2;
     fn j_from_x_j(&self, x_j: usize) -> usize {
-        (x_j + 1) / 2
+        x_j.div_ceil(2)
     }
 
     fn k_from_y_k(&self, y_k: usize) -> usize {
@@ -245,6 +1141,10 @@ impl Iterator for HamiltonianCycle {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
         if self.current == 0 {
             // We always start with 1.
             self.current = 1;
@@ -282,7 +1182,7 @@ impl Iterator for HamiltonianCycle {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (0, Some(self.half_size * 2))
+        (0, Some(self.remaining))
     }
 }
 
@@ -290,17 +1190,48 @@ impl Iterator for HamiltonianCycle {
 /// - p1 + 2 * n is prime
 /// - p2 + 2 * n is prime
 /// - gcd((p1 + p2)/2, n)  = 1
+///
 /// If no such primes exist, `None` is returned.
 ///
 /// `primes` should contain all the primes from 2 up to 4 * n.
 /// `half_size` should be at least 2.
 ///
 /// NOTE: we allow p1 to be equal to 1.
+///
+/// # Panics
+///
+/// If `half_size` is smaller than 2 or 4 * n overflows, or if `primes`
+/// doesn't contain all the primes up to 4 * n. See [`try_find_prime_quadruplet`] for a version that
+/// doesn't panic.
 pub fn find_prime_quadruplet(half_size: usize, primes: Option<&[usize]>) -> Option<(usize, usize)> {
-    assert!(half_size >= 2);
+    try_find_prime_quadruplet(half_size, primes).expect("invalid arguments")
+}
+
+/// Same as [`find_prime_quadruplet`], but returns an error if `half_size`
+/// is smaller than 2 or 4 * n overflows, or if `primes` doesn't contain all
+/// the primes up to 4 * n.
+pub fn try_find_prime_quadruplet(
+    half_size: usize,
+    primes: Option<&[usize]>,
+) -> Result<Option<(usize, usize)>, Error> {
+    if half_size < 2 {
+        return Err(Error::SizeTooSmall {
+            size: half_size,
+            minimum: 2,
+        });
+    }
+    let needed = half_size.checked_mul(4).ok_or(Error::SizeTooLarge {
+        size: half_size,
+        maximum: usize::MAX / 4,
+    })?;
+    if let Some(p) = primes {
+        if !primes_cover(p, needed) {
+            return Err(Error::InsufficientPrimes { needed });
+        }
+    }
     let all_primes = match primes {
         Some(p) => Cow::Borrowed(p),
-        None => Cow::Owned(gen_primes_upto_n(4 * half_size)),
+        None => Cow::Owned(gen_primes_upto_n(needed)),
     };
     let half_index = match all_primes.binary_search(&(half_size * 2)) {
         Ok(n) => n,
@@ -308,25 +1239,127 @@ pub fn find_prime_quadruplet(half_size: usize, primes: Option<&[usize]>) -> Opti
     };
 
     let (iter_primes, bigger_primes) = all_primes.split_at(half_index);
-    let mut first_prime_index = 0;
-    for &p1 in std::iter::once(&1).chain(iter_primes.iter()) {
+    // The candidates for p1 are 1 followed by `iter_primes`, so p2 can
+    // start from `iter_primes[index]` for the p1 at `index`.
+    let quadruplet_from = |index: usize| {
+        let p1 = match index {
+            0 => 1,
+            _ => iter_primes[index - 1],
+        };
         // Check if p1 + 2 * n is prime.
         if bigger_primes.binary_search(&(p1 + 2 * half_size)).is_err() {
-            first_prime_index += 1;
-            continue;
+            return None;
         }
-        for &p2 in iter_primes.iter().skip(first_prime_index) {
-            if gcd((p2 - p1) / 2, half_size) != 1 {
-                continue;
-            }
-            if bigger_primes.binary_search(&(p2 + half_size * 2)).is_err() {
-                continue;
+        iter_primes[index..]
+            .iter()
+            .find(|&&p2| {
+                gcd((p2 - p1) / 2, half_size) == 1
+                    && bigger_primes.binary_search(&(p2 + half_size * 2)).is_ok()
+            })
+            .map(|&p2| (p1, p2))
+    };
+    #[cfg(feature = "rayon")]
+    let quadruplet = (0..=iter_primes.len())
+        .into_par_iter()
+        .find_map_first(quadruplet_from);
+    #[cfg(not(feature = "rayon"))]
+    let quadruplet = (0..=iter_primes.len()).find_map(quadruplet_from);
+    Ok(quadruplet)
+}
+
+/// Finds a prime quadruplet for every half size in `half_sizes`, like
+/// [`find_prime_quadruplet`], so the entry at index i is the one of the half
+/// size `half_sizes.start + i`. With the `rayon` feature the half sizes are
+/// searched in parallel.
+///
+/// If `primes` is `None`, the primes up to 4n for the largest n are
+/// generated once, instead of for every half size.
+///
+/// # Panics
+///
+/// Like [`find_prime_quadruplet`], for any of the half sizes.
+pub fn find_prime_quadruplets_range(
+    half_sizes: Range<usize>,
+    primes: Option<&[usize]>,
+) -> Vec<Option<(usize, usize)>> {
+    let all_primes = match primes {
+        Some(p) => Cow::Borrowed(p),
+        None => Cow::Owned(gen_primes_upto_n(
+            half_sizes.end.saturating_sub(1).saturating_mul(4),
+        )),
+    };
+    #[cfg(feature = "rayon")]
+    let half_sizes = half_sizes.into_par_iter();
+    half_sizes
+        .map(|half_size| find_prime_quadruplet(half_size, Some(&all_primes)))
+        .collect()
+}
+
+/// Counts the prime quadruplets for the size 2n, where n is `half_size`,
+/// so the pairs p1 < p2 with the conditions of [`find_prime_quadruplet`].
+/// Every one of them gives a Hamiltonian cycle with [`HamiltonianCycle`].
+///
+/// `primes` should contain all the primes up to 4n.
+pub fn count_prime_quadruplets(half_size: usize, primes: &[usize]) -> usize {
+    let size = 2 * half_size;
+    // The candidates for p1 and p2, for which p + 2n is prime as well.
+    let candidates = core::iter::once(1)
+        .chain(primes.iter().copied().take_while(|&p| p < size))
+        .filter(|&p| p % 2 == 1 && primes.binary_search(&(p + size)).is_ok())
+        .collect::<Vec<_>>();
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(i, &p1)| {
+            candidates[i + 1..]
+                .iter()
+                .filter(|&&p2| gcd((p2 - p1) / 2, half_size) == 1)
+                .count()
+        })
+        .sum::<usize>()
+}
+
+/// Counts the Goldbach partitions of `even_n`, that is the pairs of primes
+/// p <= q with p + q = `even_n`.
+///
+/// `primes` should contain all the primes up to `even_n`.
+pub fn goldbach_partitions(even_n: usize, primes: &[usize]) -> usize {
+    primes
+        .iter()
+        .take_while(|&&p| p <= even_n / 2)
+        .filter(|&&p| primes.binary_search(&(even_n - p)).is_ok())
+        .count()
+}
+
+/// Counts the Goldbach partitions of every even number from `start` up to
+/// and including `end`, like [`goldbach_partitions`], and returns the pairs
+/// `(n, partitions)`.
+///
+/// `primes` should contain all the primes up to `end`. Only the pairs of primes
+/// with a sum in the range are visited, so this is faster than counting the
+/// partitions of every number separately.
+pub fn goldbach_partitions_in(start: usize, end: usize, primes: &[usize]) -> Vec<(usize, usize)> {
+    let start = start + start % 2;
+    if start > end {
+        return Vec::new();
+    }
+    let mut counts = vec![0; (end - start) / 2 + 1];
+    for (i, &p) in primes
+        .iter()
+        .enumerate()
+        .take_while(|&(_, &p)| p <= end / 2)
+    {
+        // q >= p, and start <= p + q <= end
+        let larger = &primes[i..];
+        let first = larger.partition_point(|&q| q < start.saturating_sub(p));
+        let last = larger.partition_point(|&q| q <= end - p);
+        for &q in &larger[first..last] {
+            if (p + q).is_multiple_of(2) {
+                counts[(p + q - start) / 2] += 1;
             }
-            return Some((p1, p2));
         }
-        first_prime_index += 1;
     }
-    None
+    (start..=end).step_by(2).zip(counts).collect()
 }
 
 /// Compute the greatest common divisor of `a` and `b`.
@@ -335,7 +1368,7 @@ fn gcd(mut a: usize, mut b: usize) -> usize {
         return a;
     }
     if b > a {
-        std::mem::swap(&mut a, &mut b);
+        core::mem::swap(&mut a, &mut b);
     }
     while b > 0 {
         let temp = a;
@@ -345,16 +1378,56 @@ fn gcd(mut a: usize, mut b: usize) -> usize {
     a
 }
 
+/// Checks if `primes` contains all the primes up to `n`, assuming that it
+/// contains all the primes up to its last element.
+fn primes_cover(primes: &[usize], n: usize) -> bool {
+    let last = primes.last().copied().unwrap_or(1);
+    // Between two consecutive primes there are only a few numbers,
+    // so this is fast if the table is large enough.
+    (last..=n).skip(1).all(|m| !is_prime_by_trial_division(m))
+}
+
+/// Returns the number of diagonals 2n - 1 of an `n` by `n` Hankel matrix,
+/// or an error if `n` is 0 or 2n - 1 overflows.
+fn diagonal_count(n: usize) -> Result<usize, Error> {
+    match n.checked_mul(2) {
+        Some(0) => Err(Error::SizeTooSmall {
+            size: 0,
+            minimum: 1,
+        }),
+        Some(double) => Ok(double - 1),
+        None => Err(Error::SizeTooLarge {
+            size: n,
+            maximum: usize::MAX / 2,
+        }),
+    }
+}
+
+/// Checks if `n` is a prime, without a table of primes.
+fn is_prime_by_trial_division(n: usize) -> bool {
+    if n < 4 {
+        return n >= 2;
+    }
+    if n.is_multiple_of(2) {
+        return false;
+    }
+    (3..)
+        .step_by(2)
+        .take_while(|&d| d <= n / d)
+        .all(|d| !n.is_multiple_of(d))
+}
+
 /// Checks if `n` is a prime.
 ///
 /// `primes` should contain all the primes up to sqrt(n).
 fn is_prime(n: usize, primes: &[usize]) -> bool {
-    for &prime in primes.iter() {
+    // Skip the numbers that can't be primes, in case the table is invalid.
+    for &prime in primes.iter().filter(|&&p| p >= 2) {
         if prime > n / prime {
             // In this case prime > sqrt(n), so it can not be a factor.
             return true;
         }
-        if n % prime == 0 {
+        if n.is_multiple_of(prime) {
             // Divisible, so not a prime.
             return false;
         }
@@ -365,25 +1438,145 @@ fn is_prime(n: usize, primes: &[usize]) -> bool {
 /// Generates the primes upto and including `n`.
 /// Doesn't check for overflow on `n`.
 pub fn gen_primes_upto_n(n: usize) -> Vec<usize> {
+    if n < 2 {
+        return Vec::new();
+    }
     let mut primes = vec![2];
     // Prime number theorem says there are around n / ln(n) primes less than n.
-    // Based on this we reserve space in the vec.
-    primes.reserve((n as f64 / (n as f64).log(std::f64::consts::E)).floor() as usize);
+    // Based on this we reserve space in the vec. The logarithm is rounded
+    // down, since `f64::ln` is not available without `std`.
+    let ln_n = n.ilog2() as f64 * core::f64::consts::LN_2;
+    primes.reserve((n as f64 / ln_n) as usize);
+    extend_primes_upto_n(&mut primes, n);
+    primes
+}
+
+/// Generates the primes upto and including `n` with the sieve of Eratosthenes.
+///
+/// Unlike [`gen_primes_upto_n`] this doesn't use multiple threads, but it
+/// does need a byte for every odd number upto `n`.
+pub fn sieve_primes_upto_n(n: usize) -> Vec<usize> {
+    if n < 2 {
+        return Vec::new();
+    }
+    // `composite[i]` is true if 2i + 1 is known to be composite.
+    let mut composite = vec![false; (n - 1) / 2 + 1];
+    let mut primes = vec![2];
+    for i in 1..composite.len() {
+        if composite[i] {
+            continue;
+        }
+        let p = 2 * i + 1;
+        primes.push(p);
+        if p <= n / p {
+            // The odd multiples of p are 2p apart, so p apart in `composite`.
+            for c in composite.iter_mut().skip(p * p / 2).step_by(p) {
+                *c = true;
+            }
+        }
+    }
+    primes
+}
+
+/// The number of odd numbers in a segment of [`sieve_primes_by_segments`],
+/// so that a segment fits in the L2 cache.
+const SEGMENT_LEN: usize = 1 << 17;
+
+/// Adds the primes upto and including `n` to `primes`, like
+/// [`extend_primes_upto_n`], but with a segmented sieve of Eratosthenes.
+///
+/// The numbers after the last prime in `primes` are split into segments,
+/// which are sieved in parallel if the `rayon` feature is enabled, with the
+/// primes upto the square root of `n`. Every thread only needs a byte for
+/// every odd number in its segment.
+pub fn sieve_primes_by_segments(primes: &mut Vec<usize>, n: usize) {
+    let start = primes.last().map_or(2, |&p| p + 1);
+    if n < start {
+        return;
+    }
+    if start == 2 {
+        primes.push(2);
+    }
+    let base = sieve_primes_upto_n(n.isqrt());
+    let first = start.max(3) | 1;
+    let count = if first > n { 0 } else { (n - first) / 2 + 1 };
+    let segment = |s: usize| {
+        let len = SEGMENT_LEN.min(count - s * SEGMENT_LEN);
+        sieve_segment(first + 2 * s * SEGMENT_LEN, len, &base)
+    };
+    #[cfg(feature = "rayon")]
+    let segments = (0..count.div_ceil(SEGMENT_LEN))
+        .into_par_iter()
+        .map(segment)
+        .collect::<Vec<_>>();
+    #[cfg(not(feature = "rayon"))]
+    let segments = (0..count.div_ceil(SEGMENT_LEN))
+        .map(segment)
+        .collect::<Vec<_>>();
+    for segment in segments {
+        primes.extend(segment);
+    }
+}
+
+/// Returns the primes among the `len` odd numbers starting from the odd
+/// number `first`, where `base` contains the primes upto the square root
+/// of the last one.
+fn sieve_segment(first: usize, len: usize, base: &[usize]) -> Vec<usize> {
+    // `composite[i]` is true if first + 2i is known to be composite.
+    let mut composite = vec![false; len];
+    let last = first + 2 * (len - 1);
+    for &p in base.iter().skip(1) {
+        if p > last / p {
+            break;
+        }
+        // The first odd multiple of p in the segment, but not p itself.
+        let mut multiple = (p * p).max(first.div_ceil(p) * p);
+        if multiple % 2 == 0 {
+            multiple += p;
+        }
+        for c in composite.iter_mut().skip((multiple - first) / 2).step_by(p) {
+            *c = true;
+        }
+    }
+    (0..len)
+        .filter(|&i| !composite[i])
+        .map(|i| first + 2 * i)
+        .collect()
+}
+
+/// Adds the primes upto and including `n` to `primes`.
+///
+/// `primes` should contain all the primes upto its last element,
+/// for example a table generated by [`gen_primes_upto_n`].
+/// If it is empty, the primes are generated from scratch. Numbers below 2
+/// at the start of `primes` are not primes, so they are removed.
+pub fn extend_primes_upto_n(primes: &mut Vec<usize>, n: usize) {
+    primes.drain(..primes.partition_point(|&p| p < 2));
+    if primes.is_empty() {
+        if n < 2 {
+            return;
+        }
+        primes.push(2);
+    }
+    let mut cap = primes[primes.len() - 1];
 
     // For the first `cutoff` numbers we just use 1 thread.
     // To properly parallelize we need to already have a decent
     // amount of primes generated.
     let cutoff = 5_000;
-    let mut cap = min(cutoff, n);
-
-    // Already skip all the even numbers.
-    for i in (3..=cap).step_by(2) {
-        if is_prime(i, &primes) {
-            primes.push(i);
+    if cap < cutoff && cap < n {
+        let end = min(cutoff, n);
+        // Already skip all the even numbers.
+        for i in ((cap + 1) | 1..=end).step_by(2) {
+            if is_prime(i, primes) {
+                primes.push(i);
+            }
         }
+        cap = end;
     }
 
-    // Now comes the parallel part.
+    // Now comes the parallel part. Without `rayon`,
+    // so the primes are checked one by one.
     while cap < n {
         let start = cap + 1;
 
@@ -391,20 +1584,93 @@ pub fn gen_primes_upto_n(n: usize) -> Vec<usize> {
         // we only need the primes less than N.
         cap = min(n, cap.saturating_mul(cap));
 
-        let new_primes = (start..=cap)
-            .into_par_iter()
-            .filter(|&i| is_prime(i, &primes))
+        #[cfg(feature = "rayon")]
+        let candidates = (start..=cap).into_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let candidates = start..=cap;
+        let new_primes = candidates
+            .filter(|&i| is_prime(i, primes))
             .collect::<Vec<usize>>();
         primes.extend_from_slice(&new_primes);
     }
+}
 
-    primes
+/// Generates the positive squares upto and including `n`.
+pub fn gen_squares_upto_n(n: usize) -> Vec<usize> {
+    (1..)
+        .map_while(|i: usize| i.checked_mul(i))
+        .take_while(|&s| s <= n)
+        .collect()
+}
+
+/// Generates the positive triangular numbers upto and including `n`.
+pub fn gen_triangular_upto_n(n: usize) -> Vec<usize> {
+    // Divide the even factor first, so that only the result can overflow.
+    (1..)
+        .map_while(|i: usize| match i % 2 {
+            0 => (i / 2).checked_mul(i + 1),
+            _ => i.checked_mul(i.div_ceil(2)),
+        })
+        .take_while(|&t| t <= n)
+        .collect()
+}
+
+#[cfg(feature = "std")]
+/// Writes `primes` as little-endian 64-bit integers, so that they
+/// can be loaded again with [`read_primes`].
+pub fn write_primes<W: Write>(mut output: W, primes: &[usize]) -> io::Result<()> {
+    for &prime in primes {
+        output.write_all(&(prime as u64).to_le_bytes())?;
+    }
+    output.flush()
+}
+
+#[cfg(feature = "std")]
+/// Reads a table of primes written by [`write_primes`].
+///
+/// Returns an error if the table is not a strictly increasing
+/// sequence starting at 2.
+pub fn read_primes<R: Read>(mut input: R) -> io::Result<Vec<usize>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut primes: Vec<usize> = Vec::new();
+    let mut bytes = [0; 8];
+    loop {
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match input.read(&mut bytes[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        if filled == 0 {
+            break;
+        }
+        if filled < bytes.len() {
+            return Err(invalid("the table of primes is truncated"));
+        }
+        let prime = usize::try_from(u64::from_le_bytes(bytes))
+            .map_err(|_| invalid("prime does not fit in a usize"))?;
+        match primes.last() {
+            None if prime != 2 => return Err(invalid("the primes should start at 2")),
+            Some(&last) if last >= prime => {
+                return Err(invalid("the primes should be strictly increasing"))
+            }
+            _ => primes.push(prime),
+        }
+    }
+    if primes.is_empty() {
+        return Err(invalid("the table of primes is empty"));
+    }
+    Ok(primes)
 }
 
 #[cfg(test)]
 #[test]
 fn correct_access() {
     let mat = Hankel::from_sequence(5, &[4, 6, 8]);
+    #[cfg(feature = "std")]
     let _ = mat.print();
     assert_eq!(mat.vertex_degrees(), vec![2, 2, 3, 2, 2]);
     assert!(!mat.valid_path(&[1, 3, 5, 2, 4]));
@@ -440,6 +1706,38 @@ fn prime_quadruplet() {
     assert_eq!(find_prime_quadruplet(10, None), Some((3, 17)));
 }
 
+#[test]
+fn quadruplets_range() {
+    let primes = gen_primes_upto_n(400);
+    let quadruplets = find_prime_quadruplets_range(2..100, Some(&primes));
+    assert_eq!(quadruplets.len(), 98);
+    for (half_size, quadruplet) in (2..).zip(quadruplets) {
+        assert_eq!(quadruplet, find_prime_quadruplet(half_size, Some(&primes)));
+    }
+    assert_eq!(
+        find_prime_quadruplets_range(10..11, None),
+        vec![Some((3, 17))]
+    );
+    assert!(find_prime_quadruplets_range(5..5, None).is_empty());
+}
+
+#[test]
+fn count_quadruplets() {
+    let primes = gen_primes_upto_n(200);
+    for half_size in 2..50 {
+        let count = (1..2 * half_size)
+            .step_by(2)
+            .flat_map(|p1| (p1 + 2..=2 * half_size).map(move |p2| (p1, p2)))
+            .filter(|&(p1, p2)| HamiltonianCycle::try_new(p1, p2, half_size).is_ok())
+            .count();
+        assert_eq!(count_prime_quadruplets(half_size, &primes), count);
+        assert_eq!(
+            count > 0,
+            find_prime_quadruplet(half_size, Some(&primes)).is_some()
+        );
+    }
+}
+
 #[test]
 fn first_100() {
     let primes = gen_primes_upto_n(200);
@@ -460,3 +1758,574 @@ fn primes() {
         vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
     );
 }
+
+#[test]
+fn other_sequences() {
+    assert_eq!(gen_squares_upto_n(50), vec![1, 4, 9, 16, 25, 36, 49]);
+    assert_eq!(gen_triangular_upto_n(21), vec![1, 3, 6, 10, 15, 21]);
+}
+
+#[test]
+fn extend_primes() {
+    let mut primes = gen_primes_upto_n(10);
+    extend_primes_upto_n(&mut primes, 30);
+    assert_eq!(primes, gen_primes_upto_n(30));
+    let mut primes = gen_primes_upto_n(6_000);
+    extend_primes_upto_n(&mut primes, 100_000);
+    assert_eq!(primes, gen_primes_upto_n(100_000));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn primes_round_trip() {
+    let primes = gen_primes_upto_n(1_000);
+    let mut bytes = Vec::new();
+    write_primes(&mut bytes, &primes).unwrap();
+    assert_eq!(read_primes(&bytes[..]).unwrap(), primes);
+    assert!(read_primes(&bytes[8..]).is_err());
+    assert!(read_primes(&bytes[..bytes.len() - 3]).is_err());
+}
+
+#[test]
+fn search_stats() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let mut path = vec![1, 0, 0, 0, 0, 0];
+    let mut stats = SearchStats::default();
+    assert!(mat.hamiltonian_cycle_with_stats(&mut path, 1, &mut stats));
+    assert!(stats.nodes >= 5);
+
+    // Vertex 3 only has 2 as a neighbour, so there is no cycle.
+    let mat = Hankel::from_sequence(4, &[3, 5]);
+    let mut path = vec![1, 0, 0, 0];
+    let mut stats = SearchStats::default();
+    assert!(!mat.hamiltonian_cycle_with_stats(&mut path, 1, &mut stats));
+    assert_eq!(stats.best_path, vec![1, 2, 3]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn search_deadline() {
+    let mat = Hankel::prime_sum_matrix(2_000, None);
+    let mut path = vec![0; 2_000];
+    path[0] = 1;
+    let limits = SearchLimits {
+        deadline: Some(Instant::now()),
+        ..Default::default()
+    };
+    let mut stats = SearchStats::default();
+    let result = mat.hamiltonian_cycle_with_limits(&mut path, 1, &limits, &mut stats);
+    assert_eq!(result, SearchResult::Aborted);
+    assert_eq!(stats.nodes, 1024);
+}
+
+#[test]
+fn search_node_budget() {
+    let mat = Hankel::prime_sum_matrix(2_000, None);
+    let limits = SearchLimits {
+        max_nodes: Some(100),
+        ..Default::default()
+    };
+    for iterative in [false, true] {
+        let mut path = vec![0; 2_000];
+        path[0] = 1;
+        let mut stats = SearchStats::default();
+        let result = if iterative {
+            mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats)
+        } else {
+            mat.hamiltonian_cycle_with_limits(&mut path, 1, &limits, &mut stats)
+        };
+        assert_eq!(result, SearchResult::Aborted);
+        assert_eq!(stats.nodes, 100);
+    }
+}
+
+#[test]
+fn search_progress() {
+    let mat = Hankel::prime_sum_matrix(2_000, None);
+    let mut path = vec![0; 2_000];
+    path[0] = 1;
+    let progress = SearchProgress::default();
+    let limits = SearchLimits {
+        max_nodes: Some(3_000),
+        progress: Some(&progress),
+        ..Default::default()
+    };
+    let mut stats = SearchStats::default();
+    mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats);
+    assert!(stats.nodes >= 1_024);
+    assert_eq!(
+        progress.nodes.load(Ordering::Relaxed),
+        stats.nodes / 1_024 * 1_024
+    );
+    assert!(progress.depth.load(Ordering::Relaxed) > 1);
+}
+
+#[test]
+fn symmetry_breaking() {
+    let limits = SearchLimits::default();
+    for seed in 0..20 {
+        let mat = Hankel::random(14, 0.5, seed);
+        let mut path = vec![0; 14];
+        path[0] = 1;
+        let mut other = path.clone();
+        let (mut stats, mut other_stats) = (SearchStats::default(), SearchStats::default());
+        let result = mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats);
+        assert_eq!(
+            mat.hamiltonian_cycle_with_limits(&mut other, 1, &limits, &mut other_stats),
+            result
+        );
+        assert_eq!((path.clone(), stats.nodes), (other, other_stats.nodes));
+        assert_eq!(
+            result == SearchResult::Found,
+            mat.count_hamiltonian_cycles() > 0
+        );
+        if result == SearchResult::Found {
+            // Only the direction where the second vertex is the smaller one.
+            assert!(mat.valid_cycle(&path) && path[1] < path[13]);
+        }
+    }
+    // With a longer start the direction is already fixed.
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let mut path = vec![1, 6, 5, 0, 0, 0];
+    assert!(mat.hamiltonian_cycle(&mut path, 3));
+    assert_eq!(path, [1, 6, 5, 2, 3, 4]);
+}
+
+#[test]
+fn count_cycles() {
+    // OEIS A051252
+    let counts = (1..=6)
+        .map(|n| Hankel::prime_sum_matrix(2 * n, None).count_hamiltonian_cycles())
+        .collect::<Vec<_>>();
+    assert_eq!(counts, [1, 1, 1, 2, 48, 512]);
+    assert_eq!(
+        Hankel::prime_sum_matrix(7, None).count_hamiltonian_cycles(),
+        0
+    );
+}
+
+#[test]
+fn prime_differences() {
+    let mat = Hankel::prime_difference_matrix(8, None);
+    assert_eq!(mat.kind(), SumOrDiff::Diff);
+    assert_eq!((mat.get(1, 3), mat.get(3, 1), mat.get(1, 2)), (1, 1, 0));
+    let degrees = (1..=8)
+        .map(|i| (1..=8).filter(|&j| mat.get(i, j) != 0).count())
+        .collect::<Vec<_>>();
+    assert_eq!(mat.vertex_degrees(), degrees);
+    assert_eq!(mat.edge_count(), mat.edges().count());
+    // The differences are 3, 3, 5, 3, 3, 5, 5 and 5.
+    assert!(mat.valid_cycle(&[1, 4, 7, 2, 5, 8, 3, 6]));
+    assert!(mat.valid_cycle(&mat.is_hamiltonian().unwrap()));
+    // With 0 in the sequence every vertex has a loop.
+    let looped = Hankel::from_differences(5, &[0, 4]);
+    assert_eq!(looped.vertex_degrees(), vec![2, 1, 1, 1, 2]);
+    assert_eq!(looped.edge_count(), 1);
+    assert!(Hankel::prime_difference_matrix(0, None)
+        .edges()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn raw_parts() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(mat.diagonals(), [0, 1, 0, 1, 0, 1, 0, 0, 0, 1, 0]);
+    let (diagonals, size, kind) = mat.into_raw_parts();
+    let mat = Hankel::from_raw_parts(diagonals.clone(), size, kind).unwrap();
+    assert_eq!(
+        mat.vertex_degrees(),
+        Hankel::prime_sum_matrix(6, None).degrees
+    );
+    assert_eq!(
+        Hankel::from_raw_parts(diagonals, 5, kind).unwrap_err(),
+        Error::InvalidDiagonals {
+            expected: 9,
+            len: 11
+        }
+    );
+    assert_eq!(
+        Hankel::from_raw_parts(vec![0, 2, 1], 2, SumOrDiff::Sum).unwrap_err(),
+        Error::InvalidEntry { index: 1, value: 2 }
+    );
+    let mat = Hankel::try_from(vec![0, 1, 0, 1, 0]).unwrap();
+    assert_eq!((mat.size, mat.edge_count()), (3, 2));
+    assert_eq!(
+        Hankel::try_from(vec![0, 1, 0, 1]).unwrap_err(),
+        Error::InvalidDiagonals {
+            expected: 3,
+            len: 4
+        }
+    );
+    let diff = Hankel::from_raw_parts(vec![0, 0, 1], 3, SumOrDiff::Diff).unwrap();
+    assert_eq!(diff.edges().collect::<Vec<_>>(), vec![(1, 3)]);
+    assert_eq!(
+        Hankel::from_raw_parts(Vec::new(), 0, SumOrDiff::Sum)
+            .unwrap()
+            .size,
+        0
+    );
+}
+
+#[test]
+fn union_and_intersection() {
+    let primes = gen_primes_upto_n(19);
+    let squares = gen_squares_upto_n(19);
+    let mut both = [&primes[..], &squares[..]].concat();
+    both.sort_unstable();
+    let prime_sums = Hankel::from_sequence(10, &primes);
+    let square_sums = Hankel::from_sequence(10, &squares);
+    let union = prime_sums.union(&square_sums);
+    assert_eq!(
+        union.diagonals(),
+        Hankel::from_sequence(10, &both).diagonals()
+    );
+    assert_eq!(
+        union.vertex_degrees(),
+        Hankel::from_sequence(10, &both).degrees
+    );
+    // No square is a prime, so the graphs have no edges in common.
+    let intersection = prime_sums.intersection(&square_sums);
+    assert_eq!(intersection.edge_count(), 0);
+    assert_eq!(
+        union.edge_count(),
+        prime_sums.edge_count() + square_sums.edge_count()
+    );
+    let diff = Hankel::prime_difference_matrix(10, None);
+    assert_eq!(diff.union(&diff).diagonals(), diff.diagonals());
+    assert_eq!(
+        prime_sums.try_union(&diff).unwrap_err(),
+        Error::KindMismatch {
+            left: SumOrDiff::Sum,
+            right: SumOrDiff::Diff
+        }
+    );
+    assert_eq!(
+        prime_sums
+            .try_intersection(&Hankel::from_sequence(9, &primes))
+            .unwrap_err(),
+        Error::SizeMismatch { left: 10, right: 9 }
+    );
+}
+
+#[test]
+fn sum_sets() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(mat.sum_set(), vec![3, 5, 7, 11]);
+    let random = Hankel::random(40, 0.2, 3);
+    let again = Hankel::from_sequence(40, &random.sum_set());
+    assert_eq!(again.diagonals(), random.diagonals());
+    let diff = Hankel::from_differences(8, &[0, 3, 5]);
+    assert_eq!(diff.sum_set(), vec![0, 3, 5]);
+    assert!(Hankel::from_sequence(0, &[]).sum_set().is_empty());
+}
+
+#[test]
+fn random_matrices() {
+    let mat = Hankel::random(50, 0.3, 7);
+    assert_eq!(mat.diagonals(), Hankel::random(50, 0.3, 7).diagonals());
+    assert_ne!(mat.diagonals(), Hankel::random(50, 0.3, 8).diagonals());
+    let ones = mat.diagonals().iter().filter(|&&d| d == 1).count();
+    assert!((10..50).contains(&ones), "{} of 99 diagonals", ones);
+    assert!(Hankel::random(20, 0.0, 1)
+        .diagonals()
+        .iter()
+        .all(|&d| d == 0));
+    assert!(Hankel::random(20, 1.0, 1)
+        .diagonals()
+        .iter()
+        .all(|&d| d == 1));
+    assert_eq!(Hankel::random(0, 0.5, 1).size, 0);
+}
+
+#[test]
+fn reused_buffers() {
+    let mut path = Vec::with_capacity(64);
+    for n in (2..=64).step_by(2) {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        assert!(mat.is_hamiltonian_into(&mut path));
+        assert_eq!(Some(&path[..]), mat.is_hamiltonian().as_deref());
+    }
+    assert_eq!(path.capacity(), 64);
+    assert!(!Hankel::prime_sum_matrix(7, None).is_hamiltonian_into(&mut path));
+    assert!(!Hankel::from_sequence(0, &[]).is_hamiltonian_into(&mut path));
+}
+
+#[test]
+fn cached_degrees() {
+    let primes = gen_primes_upto_n(40);
+    let mut mat = Hankel::prime_sum_matrix(6, None);
+    for n in [6, 7, 10, 20] {
+        mat.extend_to(n, &primes);
+        let fresh = Hankel::prime_sum_matrix(n, None);
+        assert_eq!(mat.diagonals, fresh.diagonals);
+        assert_eq!(mat.degrees(), fresh.compute_degrees());
+    }
+    mat.set(1, 1, 1);
+    mat.set(3, 4, 0);
+    mat.set(20, 20, 1);
+    assert_eq!(mat.get(2, 5), 0);
+    assert_eq!(mat.degrees(), mat.compute_degrees());
+    let mut diffs = Hankel::prime_difference_matrix(3, None);
+    diffs.extend_to(12, &primes);
+    assert_eq!(
+        diffs.diagonals,
+        Hankel::prime_difference_matrix(12, None).diagonals
+    );
+    diffs.set(5, 5, 1);
+    diffs.set(1, 6, 0);
+    diffs.set(12, 1, 1);
+    assert_eq!(diffs.degrees(), diffs.compute_degrees());
+    assert_eq!(
+        diffs.try_set(0, 1, 1),
+        Err(Error::InvalidVertex {
+            vertex: 0,
+            size: 12
+        })
+    );
+    assert_eq!(
+        mat.try_set(1, 21, 0),
+        Err(Error::InvalidVertex {
+            vertex: 21,
+            size: 20
+        })
+    );
+    assert_eq!(
+        mat.try_set(2, 3, 2),
+        Err(Error::InvalidEntry { index: 3, value: 2 })
+    );
+    assert_eq!(mat.get(2, 3), 1);
+    assert_eq!(mat.degrees(), mat.compute_degrees());
+}
+
+#[test]
+fn fallible_constructors() {
+    let primes = gen_primes_upto_n(20);
+    assert!(Hankel::try_prime_sum_matrix(10, Some(&primes)).is_ok());
+    assert_eq!(
+        Hankel::try_prime_sum_matrix(12, Some(&primes)).unwrap_err(),
+        Error::InsufficientPrimes { needed: 23 }
+    );
+    assert_eq!(
+        Hankel::prime_sum_matrix(12, Some(&primes)).diagonals,
+        Hankel::prime_sum_matrix(12, None).diagonals
+    );
+    assert!(try_find_prime_quadruplet(5, Some(&primes)).is_ok());
+    assert!(try_find_prime_quadruplet(6, Some(&primes)).is_err());
+    assert!(try_find_prime_quadruplet(1, None).is_err());
+    let (p1, p2) = find_prime_quadruplet(5, None).unwrap();
+    assert!(HamiltonianCycle::try_new(p1, p2, 5).is_ok());
+    assert!(HamiltonianCycle::try_new(p2, p1, 5).is_err());
+    assert!(HamiltonianCycle::try_new(1, 9, 5).is_err());
+}
+
+#[test]
+fn checked_accessors() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    assert_eq!(mat.try_get(1, 2), Some(1));
+    assert_eq!(mat.try_get(1, 3), Some(0));
+    assert_eq!(mat.try_get(0, 0), None);
+    assert_eq!(mat.try_get(7, 1), None);
+    assert_eq!(mat.try_get_0_based(0, 1), Some(1));
+    assert_eq!(mat.try_get_0_based(0, 6), None);
+    assert!(!mat.valid_cycle(&[]));
+    assert!(!mat.valid_cycle(&[1, 4, 3, 2, 5, 0]));
+    assert!(!mat.valid_path(&[1, 4, 3, 2, 5, 6, 7]));
+    assert!(mat.valid_cycle(&[1, 4, 3, 2, 5, 6]));
+}
+
+#[test]
+fn invalid_input_does_not_panic() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let limits = SearchLimits::default();
+    for (mut path, pos) in [
+        (vec![], 0),
+        (vec![1, 0, 0, 0, 0, 0], 0),
+        (vec![1, 0, 0], 1),
+        (vec![7, 0, 0, 0, 0, 0], 1),
+        (vec![1, 0, 0, 0, 0, 0], 7),
+    ] {
+        let mut stats = SearchStats::default();
+        assert_eq!(
+            mat.hamiltonian_cycle_with_limits(&mut path, pos, &limits, &mut stats),
+            SearchResult::NotFound
+        );
+        assert_eq!(
+            mat.hamiltonian_cycle_iterative(&mut path, pos, &limits, &mut stats),
+            SearchResult::NotFound
+        );
+    }
+    assert_eq!(Hankel::from_sequence(1, &[]).is_hamiltonian(), None);
+    assert_eq!(HamiltonianCycle::new(0, 0, 0).count(), 0);
+    assert!(HamiltonianCycle::new(1, 100, 5).count() <= 10);
+    assert!(gen_primes_upto_n(1).is_empty());
+    let mut primes = vec![0];
+    extend_primes_upto_n(&mut primes, 10);
+    assert_eq!(primes, [2, 3, 5, 7]);
+    let mut primes = vec![1, 2, 3];
+    extend_primes_upto_n(&mut primes, 1);
+    assert_eq!(primes, [2, 3]);
+}
+
+#[test]
+fn iterative_matches_recursive() {
+    let primes = gen_primes_upto_n(200);
+    for n in (4..100).step_by(2) {
+        let mat = Hankel::prime_sum_matrix(n, Some(&primes));
+        let limits = SearchLimits::default();
+        let mut recursive = vec![0; n];
+        recursive[0] = 1;
+        let mut iterative = recursive.clone();
+        let mut recursive_stats = SearchStats::default();
+        let mut iterative_stats = SearchStats::default();
+        assert_eq!(
+            mat.hamiltonian_cycle_with_limits(&mut recursive, 1, &limits, &mut recursive_stats),
+            mat.hamiltonian_cycle_iterative(&mut iterative, 1, &limits, &mut iterative_stats)
+        );
+        assert_eq!(recursive, iterative);
+        assert_eq!(recursive_stats.nodes, iterative_stats.nodes);
+        assert_eq!(recursive_stats.best_path, iterative_stats.best_path);
+    }
+    let mat = Hankel::from_sequence(4, &[3, 5]);
+    let mut path = vec![1, 0, 0, 0];
+    let mut stats = SearchStats::default();
+    let result =
+        mat.hamiltonian_cycle_iterative(&mut path, 1, &SearchLimits::default(), &mut stats);
+    assert_eq!(result, SearchResult::NotFound);
+    assert_eq!(stats.best_path, vec![1, 2, 3]);
+}
+
+#[test]
+fn odd_path_from_cycle() {
+    let cycle = [1, 4, 3, 2, 5, 6];
+    let path = cycle_to_path(&cycle, 6).unwrap();
+    assert_eq!(path, vec![1, 4, 3, 2, 5]);
+    assert!(Hankel::prime_sum_matrix(5, None).valid_path(&path));
+    assert_eq!(cycle_to_path(&cycle, 3).unwrap(), vec![2, 5, 6, 1, 4]);
+    assert!(cycle_to_path(&cycle, 7).is_none());
+}
+
+#[test]
+fn degenerate_sizes() {
+    assert_eq!(
+        Hankel::try_prime_sum_matrix(0, None).unwrap_err(),
+        Error::SizeTooSmall {
+            size: 0,
+            minimum: 1
+        }
+    );
+    assert_eq!(Hankel::prime_sum_matrix(0, None).size, 0);
+    assert!(Hankel::from_sequence(0, &[2]).vertex_degrees().is_empty());
+    assert!(matches!(
+        Hankel::try_prime_sum_matrix(usize::MAX, None),
+        Err(Error::SizeTooLarge { .. })
+    ));
+    assert!(matches!(
+        try_find_prime_quadruplet(usize::MAX / 2, Some(&[2, 3])),
+        Err(Error::SizeTooLarge { .. })
+    ));
+    assert!(matches!(
+        HamiltonianCycle::try_new(1, 3, usize::MAX / 2),
+        Err(Error::SizeTooLarge { .. })
+    ));
+}
+
+#[test]
+fn degrees_and_edges() {
+    let mat = Hankel::from_sequence(101, &gen_primes_upto_n(201));
+    let naive = (1..=101)
+        .map(|i| (1..=101).filter(|&j| mat.get(i, j) != 0).count())
+        .collect::<Vec<_>>();
+    assert_eq!(mat.vertex_degrees(), naive);
+    #[cfg(feature = "rayon")]
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(7)
+            .build()
+            .unwrap();
+        assert_eq!(pool.install(|| mat.vertex_degrees()), naive);
+    }
+    // `from_sequence` includes the loop at vertex 1, since 1 + 1 = 2 is prime.
+    assert_eq!(mat.get(1, 1), 1);
+    assert_eq!(mat.edge_count(), mat.edges().count());
+    assert_eq!(
+        try_find_prime_quadruplet(50, None),
+        Ok(find_prime_quadruplet(50, None))
+    );
+}
+
+#[test]
+fn repeated_vertices() {
+    let mat = Hankel::prime_sum_matrix(4, None);
+    assert!(mat.valid_cycle(&[1, 2, 3, 4]));
+    assert!(!mat.valid_cycle(&[1, 2, 1, 2]));
+    // Found with the fuzz targets: a start that doesn't alternate, and an odd size.
+    let limits = SearchLimits::default();
+    let mat = Hankel::from_sequence(3, &[3, 5, 7, 9]);
+    for mut path in [vec![3, 2, 0], vec![2, 1, 0], vec![1, 1, 0]] {
+        let mut stats = SearchStats::default();
+        assert_eq!(
+            mat.hamiltonian_cycle_iterative(&mut path, 2, &limits, &mut stats),
+            SearchResult::NotFound
+        );
+        assert_eq!(
+            mat.hamiltonian_cycle_with_limits(&mut path, 2, &limits, &mut stats),
+            SearchResult::NotFound
+        );
+    }
+}
+
+#[test]
+fn sieve() {
+    for n in [0, 1, 2, 3, 4, 9, 25, 100, 10_007] {
+        assert_eq!(sieve_primes_upto_n(n), gen_primes_upto_n(n));
+    }
+    let expected = gen_primes_upto_n(3 * SEGMENT_LEN + 12_345);
+    for start in [0, 1, 2, 3, 4, 100, 1_000] {
+        for n in [
+            0,
+            2,
+            3,
+            1_000,
+            2 * SEGMENT_LEN + 1,
+            3 * SEGMENT_LEN + 12_345,
+        ] {
+            let mut primes = gen_primes_upto_n(start);
+            sieve_primes_by_segments(&mut primes, n);
+            let end = expected.partition_point(|&p| p <= n.max(start));
+            assert_eq!(primes, expected[..end]);
+        }
+    }
+}
+
+#[test]
+fn goldbach() {
+    let primes = gen_primes_upto_n(1_000);
+    assert_eq!(goldbach_partitions(4, &primes), 1);
+    assert_eq!(goldbach_partitions(10, &primes), 2);
+    assert_eq!(goldbach_partitions(100, &primes), 6);
+    assert_eq!(goldbach_partitions(2, &primes), 0);
+    let bulk = goldbach_partitions_in(3, 1_000, &primes);
+    assert_eq!(bulk.len(), 499);
+    for (n, count) in bulk {
+        assert_eq!(count, goldbach_partitions(n, &primes), "{}", n);
+    }
+}
+
+#[test]
+fn cycle_similarity() {
+    let a = [1, 4, 3, 2, 5, 6];
+    assert_eq!(cycle_edge_overlap(&a, &a), 6);
+    assert_eq!(hamming_distance(&a, &a), 0);
+    // The same cycle in the other direction.
+    let b = [1, 6, 5, 2, 3, 4];
+    assert_eq!(cycle_edge_overlap(&a, &b), 6);
+    assert_eq!(hamming_distance(&a, &b), 4);
+    // A cycle of size 8 that keeps the edges 1 - 4, 2 - 3 and 5 - 6.
+    let c = [1, 2, 3, 8, 5, 6, 7, 4];
+    assert!(Hankel::prime_sum_matrix(8, None).valid_cycle(&c));
+    assert_eq!(cycle_edge_overlap(&a, &c), 3);
+    assert_eq!(hamming_distance(&a, &c), 4);
+    assert_eq!(cycle_edge_overlap(&[], &a), 0);
+    assert_eq!(cycle_edge_overlap(&[1, 2], &[2, 1]), 1);
+}
@@ -0,0 +1,162 @@
+//! Conversions to the matrix types of linear algebra crates, and the
+//! spectrum of the adjacency matrix, enabled with the features of the
+//! same name as the crates.
+
+#[cfg(any(feature = "nalgebra", feature = "sprs"))]
+use alloc::vec::Vec;
+#[cfg(feature = "nalgebra")]
+use nalgebra::{DMatrix, Scalar};
+#[cfg(feature = "sprs")]
+use sprs::CsMat;
+
+use crate::Hankel;
+#[cfg(feature = "sprs")]
+use crate::SumOrDiff;
+
+impl Hankel {
+    /// Returns the adjacency matrix as a dense `nalgebra` matrix, for example
+    /// `mat.to_dmatrix::<f64>()` to compute its eigenvalues.
+    #[cfg(feature = "nalgebra")]
+    pub fn to_dmatrix<T: Scalar + From<u8>>(&self) -> DMatrix<T> {
+        DMatrix::from_fn(self.size, self.size, |row, col| {
+            T::from(self.get_0_based(row, col))
+        })
+    }
+
+    /// Returns the eigenvalues of the adjacency matrix, from large to small.
+    ///
+    /// If only odd sums are adjacent, like in the prime sum graphs, the graph is
+    /// bipartite between the odd and the even vertices. Then the eigenvalues are
+    /// plus and minus the singular values of the half as large matrix between
+    /// the odd and the even vertices, which are computed instead.
+    #[cfg(feature = "nalgebra")]
+    pub fn spectrum(&self) -> Vec<f64> {
+        // The entries on an even diagonal have an even sum.
+        let bipartite = self.is_bipartite_by_parity();
+        let mut eigenvalues = if bipartite && self.size > 1 {
+            let odd = self.size.div_ceil(2);
+            let even = self.size / 2;
+            let between = DMatrix::from_fn(odd, even, |i, j| {
+                f64::from(self.get_0_based(2 * i, 2 * j + 1))
+            });
+            let singular_values = between.singular_values();
+            singular_values
+                .iter()
+                .flat_map(|&s| [s, -s])
+                .chain(core::iter::repeat_n(0.0, odd - even))
+                .collect::<Vec<_>>()
+        } else {
+            self.to_dmatrix::<f64>()
+                .symmetric_eigenvalues()
+                .iter()
+                .copied()
+                .collect()
+        };
+        eigenvalues.sort_by(|a, b| b.total_cmp(a));
+        eigenvalues
+    }
+
+    /// Returns the difference between the two largest eigenvalues of the
+    /// adjacency matrix, or `None` if there are less than two vertices.
+    #[cfg(feature = "nalgebra")]
+    pub fn spectral_gap(&self) -> Option<f64> {
+        match self.spectrum()[..] {
+            [first, second, ..] => Some(first - second),
+            _ => None,
+        }
+    }
+
+    /// Returns the adjacency matrix as a sparse `sprs` matrix in the
+    /// compressed sparse row format.
+    ///
+    /// Only the nonzero entries are visited, so this is also feasible
+    /// for graphs that are too large for a dense matrix.
+    #[cfg(feature = "sprs")]
+    pub fn to_csmat<T: From<u8>>(&self) -> CsMat<T> {
+        let ones = (0..self.diagonals.len())
+            .filter(|&d| self.diagonals[d] != 0)
+            .collect::<Vec<_>>();
+        let mut indptr = Vec::with_capacity(self.size + 1);
+        let mut indices = Vec::new();
+        indptr.push(0);
+        for row in 0..self.size {
+            match self.kind {
+                // The entry at (row, col) is the diagonal row + col.
+                SumOrDiff::Sum => {
+                    let first = ones.partition_point(|&d| d < row);
+                    let last = ones.partition_point(|&d| d < row + self.size);
+                    indices.extend(ones[first..last].iter().map(|&d| d - row));
+                }
+                // The entry at (row, col) is the diagonal |row - col|.
+                SumOrDiff::Diff => {
+                    let before = ones.partition_point(|&d| d <= row);
+                    let after = ones.partition_point(|&d| d < self.size - row);
+                    indices.extend(ones[..before].iter().rev().map(|&d| row - d));
+                    indices.extend(ones[..after].iter().filter(|&&d| d > 0).map(|&d| row + d));
+                }
+            }
+            indptr.push(indices.len());
+        }
+        let data = indices.iter().map(|_| T::from(1)).collect();
+        CsMat::new((self.size, self.size), indptr, indices, data)
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn dmatrix_spectrum() {
+    let mat = Hankel::prime_sum_matrix(10, None);
+    let dense = mat.to_dmatrix::<u8>();
+    assert_eq!(dense, dense.transpose());
+    assert_eq!(dense[(0, 1)], mat.get(1, 2));
+    // The graph is bipartite, so the spectrum is symmetric around 0.
+    let eigenvalues = mat.to_dmatrix::<f64>().symmetric_eigenvalues();
+    let max_degree = *mat.vertex_degrees().iter().max().unwrap() as f64;
+    assert!(eigenvalues.max() <= max_degree + 1e-9);
+    assert!((eigenvalues.max() + eigenvalues.min()).abs() < 1e-9);
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn bipartite_spectrum() {
+    for mat in [
+        Hankel::prime_sum_matrix(11, None),
+        Hankel::prime_sum_matrix(12, None),
+        Hankel::from_sequence(9, &[4, 5, 7, 11]),
+        Hankel::from_sequence(1, &[2]),
+    ] {
+        let mut dense = mat
+            .to_dmatrix::<f64>()
+            .symmetric_eigenvalues()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        dense.sort_by(|a, b| b.total_cmp(a));
+        let spectrum = mat.spectrum();
+        assert_eq!(spectrum.len(), dense.len());
+        for (a, b) in spectrum.iter().zip(dense) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+    assert_eq!(Hankel::prime_sum_matrix(1, None).spectral_gap(), None);
+    assert!(Hankel::prime_sum_matrix(10, None).spectral_gap().unwrap() > 0.0);
+}
+
+#[cfg(feature = "sprs")]
+#[test]
+fn csmat_entries() {
+    for mat in [
+        Hankel::prime_sum_matrix(10, None),
+        Hankel::from_sequence(7, &[2, 4, 9, 13]),
+        Hankel::from_sequence(0, &[]),
+        Hankel::prime_difference_matrix(9, None),
+        Hankel::from_differences(6, &[0, 1, 5]),
+    ] {
+        let sparse = mat.to_csmat::<u8>();
+        assert_eq!(sparse.shape(), (mat.size, mat.size));
+        assert_eq!(sparse.nnz(), mat.vertex_degrees().iter().sum::<usize>());
+        for (&value, (row, col)) in sparse.iter() {
+            assert_eq!(value, mat.get_0_based(row, col));
+        }
+    }
+}
@@ -1,144 +1,2102 @@
 use rayon::prelude::*;
 use std::cmp::max;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use primes::{self, find_prime_quadruplet, gen_primes_upto_n, Hankel};
+use primes::{
+    self, count_prime_quadruplets, find_prime_quadruplet, find_prime_quadruplets_range,
+    gen_primes_upto_n, gen_squares_upto_n, gen_triangular_upto_n, read_primes,
+    sieve_primes_by_segments, write_primes, HamiltonianCycle, Hankel, SearchLimits, SearchProgress,
+    SearchResult, SearchStats, SumGraph,
+};
 
-use clap::Parser;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+
+mod distributed;
+#[cfg(feature = "tui")]
+mod tui;
+
+use distributed::{ServeArgs, WorkerArgs};
+
+/// The options shared by all the threads searching for cycles.
+struct SearchConfig<'a> {
+    divisor: usize,
+    include_odd: bool,
+    primes: &'a [usize],
+    /// The sums that are allowed between consecutive numbers.
+    /// If `None`, the sums should be prime.
+    sequence: Option<&'a [usize]>,
+    /// If given, failures are logged here instead of aborting the search.
+    failures: Option<&'a FailureLog>,
+    /// Print exactly one line for every size, and nothing else.
+    quiet: bool,
+    /// Print every cycle and path that was found, with the sums of
+    /// consecutive numbers.
+    explain: bool,
+    /// The maximum time spent searching for a single size.
+    time_limit: Option<Duration>,
+    /// The maximum number of nodes visited when searching for a single size.
+    max_nodes: Option<u64>,
+    /// Set when a size failed without a failure log, to stop all the threads.
+    stop: AtomicBool,
+    /// If given, the result for every size is passed to it.
+    sink: Option<ResultSink<'a>>,
+    /// Also construct the cycles from prime quadruplets, and compare
+    /// them with the backtracking search.
+    cross_check: bool,
+    /// When cross-checking, also count the edges that the constructed and
+    /// the searched cycles have in common.
+    verify_construction: bool,
+    /// If given, the threads report their progress here.
+    monitor: Option<&'a Monitor>,
+    /// Print the minimum degree, the number of vertices of degree 2 and
+    /// the edge density of every size.
+    degree_stats: bool,
+    /// If given, the sizes with a cycle or path are recorded here.
+    checkpoint: Option<&'a Checkpoint>,
+    /// If given, the fast search writes the quadruplet of every size here.
+    quadruplets: Option<&'a QuadrupletTable>,
+    /// How a cycle is searched for every size.
+    strategy: SearchStrategy,
+    /// The seed of the first attempt of [`SearchStrategy::Rotation`].
+    seed: u64,
+}
+
+/// Receives the size, the outcome and the cycle or path that was found.
+type ResultSink<'a> = &'a (dyn Fn(usize, Outcome, &[usize]) -> io::Result<()> + Sync);
+
+impl SearchConfig<'_> {
+    /// The limits for the search of a single size, starting now.
+    fn limits<'b>(&self, progress: Option<&'b ThreadProgress>) -> SearchLimits<'b> {
+        SearchLimits {
+            deadline: self.time_limit.map(|limit| Instant::now() + limit),
+            max_nodes: self.max_nodes,
+            progress: progress.map(|p| &p.search),
+        }
+    }
+
+    /// Where `thread` should report its progress, if it is monitored.
+    fn progress(&self, thread: usize) -> Option<&ThreadProgress> {
+        self.monitor.map(|m| &m.threads[thread])
+    }
+
+    /// Checks if the search was stopped because of a failure.
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// The sums that are allowed between consecutive numbers.
+    fn sums(&self) -> &[usize] {
+        self.sequence.unwrap_or(self.primes)
+    }
+
+    /// Checks if `sum` is allowed as the sum of consecutive numbers.
+    fn allowed_sum(&self, sum: usize) -> bool {
+        self.sums().binary_search(&sum).is_ok()
+    }
+
+    /// Searches for a cycle in `mat` with rotations and extensions, trying
+    /// the seeds from `seed` on one after the other, so that the same cycle
+    /// is found every time. Every attempt takes at most `max_nodes` steps.
+    ///
+    /// Since this can't show that there is no cycle, the search is aborted
+    /// if none of the attempts found one, or the time limit was reached.
+    fn rotation_extension(
+        &self,
+        mat: &Hankel,
+        cycle: &mut [usize],
+        limits: &SearchLimits,
+    ) -> SearchResult {
+        let max_steps = self.max_nodes.unwrap_or(ROTATION_STEPS);
+        for attempt in 0..ROTATION_ATTEMPTS {
+            if limits
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                break;
+            }
+            if let Some(found) = mat.rotation_extension(self.seed.wrapping_add(attempt), max_steps)
+            {
+                cycle.copy_from_slice(&found);
+                return SearchResult::Found;
+            }
+        }
+        SearchResult::Aborted
+    }
+
+    /// The adjacency matrix of the graph of size `n`.
+    fn matrix(&self, n: usize) -> Hankel {
+        match self.sequence {
+            Some(sequence) => Hankel::from_sequence(n, sequence),
+            None => Hankel::prime_sum_matrix(n, Some(self.primes)),
+        }
+    }
+}
 
 /// Brute force search for Hamiltonian cycles
 ///
-/// Searches for cycles of length n, where n goes from
-/// `start + offset` upto and including `maximum` in steps of `increment`.
-/// Since cycles of odd length are impossible, `start` and `increment` should always be
-/// even. If run with `m` threads, use `increment = 2 * m`.
+/// Searches for cycles of length n, where n is taken from the counter `next`
+/// until it goes past `last`. The counter is shared by all the threads, and is
+/// incremented by 2 every time, so it should start at an even size. This way the
+/// threads that got easy sizes can move on, instead of waiting for the others.
+/// With [`Schedule::Blocks`] every thread has its own counter and `last` instead.
+///
+/// The previous cycle found by the same thread is re-used as a starting point,
+/// and so is the matrix, which is extended to every next size.
 ///
 /// `divisor` indicates where to start searching in the previous path. If the path is
 /// length `n` then we start a backtracking search from index `n/divisor`. If `divisor`
 /// is 0, then we start searching from index 1.
+///
+/// If `include_odd` is true, then for every cycle of length n a Hamiltonian path of
+/// length n - 1 is constructed from it, and checked as well.
 fn test_for_cycles(
-    maximum: usize,
-    start: usize,
-    increment: usize,
-    offset: usize,
-    divisor: usize,
-    primes: &[usize],
-) {
-    // When we try to create a new cycle
-    let decrement = max(6, increment);
-    // Create the first Hamiltonian cycle
-    let mat = primes::Hankel::prime_sum_matrix(start + offset, Some(primes));
-    let mut previous_path = mat
-        .is_hamiltonian()
-        .expect("No Hamiltonian cycle found for the starting index");
-    let mut i = start + offset;
-    while i <= maximum {
-        let mat = Hankel::prime_sum_matrix(i, Some(primes));
-        // We attempt to re-use the previous cycle by only changing the last
-        // vertices in the cycle
-        if !mat.hamiltonian_cycle(&mut previous_path, i - decrement) {
-            // It didn't work -> create a new cycle from scratch
-            let cycles_start = match divisor {
-                0 => 1,
-                _ => i / divisor,
-            };
-            if !mat.hamiltonian_cycle(&mut previous_path, cycles_start) {
+    config: &SearchConfig,
+    next: &AtomicUsize,
+    last: usize,
+    thread: usize,
+) -> ThreadStats {
+    let now = Instant::now();
+    let mut stats = ThreadStats::default();
+    let mut path = vec![1];
+    // Whether `path` contains the cycle of the previous size.
+    let mut have_previous = false;
+    let mut matrix: Option<Hankel> = None;
+    while !config.stopped() {
+        let i = next.fetch_add(2, Ordering::Relaxed);
+        if i > last {
+            break;
+        }
+        // When we try to create a new cycle
+        let decrement = max(6, i - path.len());
+        path.resize(i, 0);
+        let mat = match &mut matrix {
+            Some(mat) => {
+                mat.extend_to(i, config.sums());
+                mat
+            }
+            None => matrix.insert(config.matrix(i)),
+        };
+        let progress = config.progress(thread);
+        if let Some(progress) = progress {
+            progress.start(i);
+        }
+        let limits = config.limits(progress);
+        let mut search = SearchStats::default();
+        let result = if let SearchStrategy::Rotation = config.strategy {
+            config.rotation_extension(mat, &mut path, &limits)
+        } else if have_previous {
+            // We attempt to re-use the previous cycle by only changing the last
+            // vertices in the cycle
+            let reuse_start = max(1, i.saturating_sub(decrement));
+            match mat.hamiltonian_cycle_iterative(&mut path, reuse_start, &limits, &mut search) {
+                SearchResult::NotFound => {
+                    // It didn't work -> create a new cycle from scratch
+                    stats.fresh_searches += 1;
+                    let cycles_start = match config.divisor {
+                        0 => 1,
+                        _ => max(1, i / config.divisor),
+                    };
+                    mat.hamiltonian_cycle_iterative(&mut path, cycles_start, &limits, &mut search)
+                }
+                result => result,
+            }
+        } else {
+            // Create the first Hamiltonian cycle
+            mat.hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut search)
+        };
+        stats.nodes += search.nodes;
+        if let Some(progress) = progress {
+            progress.finish(search.nodes);
+        }
+        if config.cross_check {
+            cross_check(config, mat, &path, result, &mut stats);
+        }
+        let found = result == SearchResult::Found;
+        if found {
+            // Double check if it is actually a valid cycle
+            if !mat.valid_cycle(&path) {
+                panic!("Generated invalid path");
+            }
+            // If the even index has a cycle then we can always remove one vertex
+            // to create a valid path of length index - 1. Therefore we only check
+            // the even indices, unless explicitly asked to produce the paths.
+            if config.include_odd {
+                let odd_path = primes::cycle_to_path(&path, i).unwrap();
+                // The graph of size i - 1 is the one of size i without i.
+                if odd_path.contains(&i) || !mat.valid_path(&odd_path) {
+                    panic!("Generated invalid path");
+                }
+                report(config, Some(mat), i - 1, Outcome::Path, &odd_path);
+                stats.paths += 1;
+            }
+            report(config, Some(mat), i, Outcome::Cycle, &path);
+            stats.sizes += 1;
+        } else {
+            if result == SearchResult::Aborted {
+                report_skipped(config, Some(mat), i, &search);
+                stats.skipped += 1;
+            } else {
                 // Didn't find a cycle
-                panic!("Did not find Hamiltonian cycle for size {}.", i);
+                report_failure(config, Some(mat), i, &search);
+                stats.failures += 1;
+            }
+            // There is nothing to re-use for the next size.
+            path.fill(0);
+            path[0] = 1;
+        }
+        have_previous = found;
+    }
+    stats.time = now.elapsed();
+    stats
+}
+
+/// The number of half sizes for which the fast search finds the quadruplets
+/// at once.
+const QUADRUPLET_CHUNK: usize = 1 << 16;
+
+/// The largest size in bytes of the neighbor lists that are used for a search.
+const ADJACENCY_BYTES: usize = 1 << 20;
+
+/// The number of attempts of [`SearchStrategy::Rotation`] for every size.
+const ROTATION_ATTEMPTS: u64 = 8;
+
+/// The number of steps of every attempt of [`SearchStrategy::Rotation`],
+/// unless `--max-nodes` is given.
+const ROTATION_STEPS: u64 = 10_000_000;
+
+/// How the main search looks for a cycle of every size.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SearchStrategy {
+    /// Backtracking search, re-using the cycle of the previous size
+    Backtracking,
+    /// Rotations and extensions from random starts, which can't show
+    /// that there is no cycle
+    Rotation,
+}
+
+/// Searches for a Hamiltonian cycle from scratch for every size in `sizes`.
+/// The threads share the index `next` of the next size to search.
+///
+/// For odd sizes n a cycle of length n + 1 is searched, which is turned into
+/// a Hamiltonian path of length n.
+fn test_sizes(
+    config: &SearchConfig,
+    sizes: &[usize],
+    next: &AtomicUsize,
+    thread: usize,
+) -> ThreadStats {
+    let now = Instant::now();
+    let mut stats = ThreadStats::default();
+    let mut cycle = Vec::new();
+    while !config.stopped() {
+        let Some(&n) = sizes.get(next.fetch_add(1, Ordering::Relaxed)) else {
+            break;
+        };
+        let cycle_size = n + n % 2;
+        let mat = config.matrix(cycle_size);
+        cycle.clear();
+        cycle.resize(cycle_size, 0);
+        cycle[0] = 1;
+        let mut search = SearchStats::default();
+        stats.fresh_searches += 1;
+        let progress = config.progress(thread);
+        if let Some(progress) = progress {
+            progress.start(n);
+        }
+        let limits = config.limits(progress);
+        // The neighbor lists are faster to search, as long as they fit in the cache.
+        let result = if let SearchStrategy::Rotation = config.strategy {
+            config.rotation_extension(&mat, &mut cycle, &limits)
+        } else if mat.degrees().iter().sum::<usize>() * 4 <= ADJACENCY_BYTES {
+            mat.adjacency()
+                .hamiltonian_cycle_iterative(&mut cycle, 1, &limits, &mut search)
+        } else {
+            mat.hamiltonian_cycle_iterative(&mut cycle, 1, &limits, &mut search)
+        };
+        stats.nodes += search.nodes;
+        if let Some(progress) = progress {
+            progress.finish(search.nodes);
+        }
+        if config.cross_check {
+            cross_check(config, &mat, &cycle, result, &mut stats);
+        }
+        match result {
+            SearchResult::Found => {}
+            SearchResult::NotFound => {
+                report_failure(config, Some(&mat), n, &search);
+                stats.failures += 1;
+                continue;
+            }
+            SearchResult::Aborted => {
+                report_skipped(config, Some(&mat), n, &search);
+                stats.skipped += 1;
+                continue;
             }
         }
-        // Double check if it is actually a valid cycle
-        if !mat.valid_cycle(&previous_path) {
+        if !mat.valid_cycle(&cycle) {
             panic!("Generated invalid path");
         }
-        // If the even index has a cycle then we can always remove one vertex
-        // to create a valid path of length index - 1. Therefore we only check
-        // the even indices.
-        i += increment;
-        previous_path.resize(previous_path.len() + increment, 0);
+        if cycle_size == n {
+            report(config, Some(&mat), n, Outcome::Cycle, &cycle);
+            stats.sizes += 1;
+        } else {
+            let path = primes::cycle_to_path(&cycle, cycle_size).unwrap();
+            if !config.matrix(n).valid_path(&path) {
+                panic!("Generated invalid path");
+            }
+            report(config, Some(&mat), n, Outcome::Path, &path);
+            stats.paths += 1;
+        }
+    }
+    stats.time = now.elapsed();
+    stats
+}
+
+/// Constructs a cycle of length `n` from a prime quadruplet, where `mat` is
+/// the matrix of size `n` that was searched, and compares it with the
+/// `result` of the backtracking search, which found `cycle`.
+///
+/// Both cycles should be valid, and if the construction works, the backtracking
+/// search should find a cycle as well. The converse does not hold, since a prime
+/// quadruplet is not needed for a cycle to exist.
+///
+/// A disagreement is counted in `stats`. With `--verify-construction`, the
+/// fraction of the edges that both cycles have in common is recorded as well.
+fn cross_check(
+    config: &SearchConfig,
+    mat: &Hankel,
+    cycle: &[usize],
+    result: SearchResult,
+    stats: &mut ThreadStats,
+) {
+    let n = mat.size;
+    let is_cycle = |c: &[usize]| {
+        let mut vertices = c.to_vec();
+        vertices.sort_unstable();
+        vertices.into_iter().eq(1..=n) && mat.valid_cycle(c)
+    };
+    let constructed = match n {
+        // There is no quadruplet for these sizes.
+        0..=3 => None,
+        _ => find_prime_quadruplet(n / 2, Some(config.primes))
+            .map(|(p1, p2)| HamiltonianCycle::new(p1, p2, n / 2).collect::<Vec<_>>()),
+    };
+    let problem = match (&constructed, result) {
+        (_, SearchResult::Found) if !is_cycle(cycle) => "the backtracking cycle is invalid",
+        (Some(c), _) if !is_cycle(c) => "the constructed cycle is invalid",
+        (Some(_), SearchResult::NotFound) => "only the construction found a cycle",
+        (Some(c), SearchResult::Found) => {
+            if config.verify_construction {
+                stats
+                    .overlaps
+                    .push(primes::cycle_edge_overlap(c, cycle) as f64 / n as f64);
+            }
+            return;
+        }
+        _ => return,
+    };
+    eprintln!("Cross-check failed for size {}: {}", n, problem);
+    stats.disagreements += 1;
+}
+
+/// Checks that `quadruplet`, the one found for the size `2 * half_size`, exists.
+///
+/// If `include_odd` is true, the constructed cycle is turned into a Hamiltonian
+/// path of length `2 * half_size - 1`, which is checked as well.
+/// If `include_even` is false, the size `2 * half_size` itself is not reported.
+///
+/// Returns false if there is no quadruplet.
+fn test_quadruplet(
+    config: &SearchConfig,
+    half_size: usize,
+    quadruplet: Option<(usize, usize)>,
+    include_odd: bool,
+    include_even: bool,
+) -> bool {
+    if config.stopped() {
+        return true;
+    }
+    let (p1, p2) = match quadruplet {
+        Some(t) => t,
+        None => {
+            report_failure(config, None, half_size * 2, &SearchStats::default());
+            return false;
+        }
+    };
+    if let Some(table) = config.quadruplets {
+        table.record(half_size * 2, p1, p2);
+    }
+    // The cycle is only needed to construct the path, or to explain it.
+    let cycle = if include_odd || config.explain {
+        HamiltonianCycle::new(p1, p2, half_size).collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+    if include_odd {
+        let path = primes::cycle_to_path(&cycle, half_size * 2).unwrap();
+        let mat = config.matrix(half_size * 2 - 1);
+        if !mat.valid_path(&path) {
+            panic!("Generated invalid path");
+        }
+        report(config, Some(&mat), half_size * 2 - 1, Outcome::Path, &path);
+    }
+    if include_even {
+        report(config, None, half_size * 2, Outcome::Cycle, &cycle);
+    }
+    true
+}
+
+/// What was found for a single size.
+#[derive(Clone, Copy, Debug)]
+enum Outcome {
+    /// A Hamiltonian cycle
+    Cycle,
+    /// A Hamiltonian path
+    Path,
+    /// Nothing
+    Failed,
+    /// Nothing, because the time limit was reached
+    Timeout,
+    /// Nothing, because the maximum number of nodes was visited
+    OutOfNodes,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Cycle => "cycle",
+            Outcome::Path => "path",
+            Outcome::Failed => "failed",
+            Outcome::Timeout => "timeout",
+            Outcome::OutOfNodes => "out-of-nodes",
+        }
+    }
+}
+
+/// In quiet mode, prints one line with the size and what was found for it,
+/// followed by the [`DegreeStats`] if they are asked for. Those are computed
+/// from `mat` if given, which can have more than `n` vertices, see
+/// [`DegreeStats::new`].
+///
+/// The outcome and `witness`, the cycle or path if one was found, are also
+/// passed on to the sink. If that fails, all the threads are stopped.
+fn report(
+    config: &SearchConfig,
+    mat: Option<&Hankel>,
+    n: usize,
+    outcome: Outcome,
+    witness: &[usize],
+) {
+    let stats = config.degree_stats.then(|| match mat {
+        Some(mat) => DegreeStats::new(mat, n),
+        None => DegreeStats::new(&config.matrix(n), n),
+    });
+    match (config.quiet, stats) {
+        (true, Some(s)) => println!(
+            "{}\t{}\t{}\t{}\t{:.4}",
+            n,
+            outcome.as_str(),
+            s.min_degree,
+            s.degree_two,
+            s.density
+        ),
+        (true, None) => println!("{}\t{}", n, outcome.as_str()),
+        (false, Some(s)) => println!(
+            "Size {} ({}): minimum degree {}, {} vertices of degree 2, edge density {:.4}",
+            n,
+            outcome.as_str(),
+            s.min_degree,
+            s.degree_two,
+            s.density
+        ),
+        (false, None) => {}
+    }
+    if config.explain && !witness.is_empty() {
+        explain(config, n, outcome, witness);
+    }
+    if let Some(monitor) = config.monitor {
+        monitor.record(n, outcome);
+    }
+    if let (Some(checkpoint), Outcome::Cycle | Outcome::Path) = (config.checkpoint, outcome) {
+        checkpoint.record(n, outcome);
+    }
+    if let Some(sink) = config.sink {
+        if let Err(e) = sink(n, outcome, witness) {
+            eprintln!("Failed to report the result for size {}: {}", n, e);
+            config.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Statistics of the degrees of a graph, which explain most of the
+/// differences in the time the search takes.
+struct DegreeStats {
+    min_degree: usize,
+    /// The number of vertices of degree 2, whose edges are both in every cycle.
+    degree_two: usize,
+    /// The fraction of all the pairs of vertices that are adjacent.
+    density: f64,
+}
+
+impl DegreeStats {
+    /// The statistics of the graph of size `n`, which is the one on the first
+    /// `n` vertices of `mat`, so that the matrix of a larger size can be
+    /// reused. Loops are not counted in the degrees.
+    fn new(mat: &Hankel, n: usize) -> Self {
+        let degrees = (1..=n)
+            .map(|v| {
+                let beyond = (n + 1..=mat.size).filter(|&w| mat.get(v, w) != 0).count();
+                mat.neighbor_count(v) - beyond
+            })
+            .collect::<Vec<_>>();
+        let pairs = n * n.saturating_sub(1) / 2;
+        Self {
+            min_degree: degrees.iter().copied().min().unwrap_or(0),
+            degree_two: degrees.iter().filter(|&&d| d == 2).count(),
+            density: (degrees.iter().sum::<usize>() / 2) as f64 / pairs.max(1) as f64,
+        }
+    }
+}
+
+/// Prints the cycle or path `witness` of size `n`, with one line per pair of
+/// consecutive numbers showing their sum, and whether it is allowed:
+///
+/// ```text
+/// cycle of size 4:
+///   1 + 2 = 3 ✓
+///   2 + 3 = 5 ✓
+///   3 + 4 = 7 ✓
+///   4 + 1 = 5 ✓
+/// ```
+fn explain(config: &SearchConfig, n: usize, outcome: Outcome, witness: &[usize]) {
+    let mut explanation = format!("{} of size {}:\n", outcome.as_str(), n);
+    let mut pairs = witness.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>();
+    if let Outcome::Cycle = outcome {
+        pairs.push((witness[witness.len() - 1], witness[0]));
+    }
+    for (a, b) in pairs {
+        let mark = if config.allowed_sum(a + b) {
+            '✓'
+        } else {
+            '✗'
+        };
+        explanation += &format!("  {} + {} = {} {}\n", a, b, a + b, mark);
+    }
+    // Print it at once, so the explanations of different threads don't get mixed.
+    print!("{}", explanation);
+}
+
+/// Records the failure in the failure log. If there is none, and
+/// no sink to report it to, all the threads are stopped instead.
+/// The message is not printed when it would mess up the terminal UI.
+fn report_failure(config: &SearchConfig, mat: Option<&Hankel>, n: usize, search: &SearchStats) {
+    report(config, mat, n, Outcome::Failed, &[]);
+    match config.failures {
+        Some(log) => log.record(n, Outcome::Failed, search),
+        None if config.sink.is_some() => {}
+        None => {
+            if config.monitor.is_none() {
+                eprintln!("Did not find Hamiltonian cycle for size {}.", n);
+            }
+            config.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Records that the time limit or the node budget was reached for size `n`.
+fn report_skipped(config: &SearchConfig, mat: Option<&Hankel>, n: usize, search: &SearchStats) {
+    let outcome = match config.max_nodes {
+        Some(max_nodes) if search.nodes >= max_nodes => Outcome::OutOfNodes,
+        _ => Outcome::Timeout,
+    };
+    report(config, mat, n, outcome, &[]);
+    match (config.failures, outcome) {
+        (Some(log), _) => log.record(n, outcome, search),
+        (None, _) if config.sink.is_some() || config.monitor.is_some() => {}
+        (None, Outcome::OutOfNodes) => {
+            eprintln!("Visited {} nodes for size {}, skipping it", search.nodes, n)
+        }
+        (None, _) => eprintln!("Reached the time limit for size {}, skipping it", n),
+    }
+}
+
+/// The progress of all the search threads, shown in the terminal UI.
+struct Monitor {
+    threads: Vec<ThreadProgress>,
+    /// The last few sizes for which no cycle was found, most recent first.
+    recent_failures: Mutex<VecDeque<(usize, Outcome)>>,
+}
+
+impl Monitor {
+    /// The number of failures that are remembered.
+    const RECENT_FAILURES: usize = 20;
+
+    fn new(num_threads: usize) -> Self {
+        Self {
+            threads: (0..num_threads)
+                .map(|_| ThreadProgress::default())
+                .collect(),
+            recent_failures: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Remembers the outcome for size `n`, if no cycle or path was found.
+    fn record(&self, n: usize, outcome: Outcome) {
+        if matches!(outcome, Outcome::Cycle | Outcome::Path) {
+            return;
+        }
+        let mut recent = self.recent_failures.lock().unwrap();
+        recent.push_front((n, outcome));
+        recent.truncate(Self::RECENT_FAILURES);
+    }
+}
+
+/// What a single search thread is doing.
+#[derive(Default)]
+struct ThreadProgress {
+    /// The size being searched, or 0 before the first one.
+    size: AtomicUsize,
+    /// The number of nodes visited for the previous sizes.
+    finished_nodes: AtomicU64,
+    /// The progress of the search for the current size.
+    search: SearchProgress,
+}
+
+impl ThreadProgress {
+    fn start(&self, n: usize) {
+        self.size.store(n, Ordering::Relaxed);
+        self.search.nodes.store(0, Ordering::Relaxed);
+        self.search.depth.store(0, Ordering::Relaxed);
+    }
+
+    /// Called when the search for the current size visited `nodes` nodes in total.
+    fn finish(&self, nodes: u64) {
+        self.finished_nodes.fetch_add(nodes, Ordering::Relaxed);
+        self.search.nodes.store(0, Ordering::Relaxed);
+    }
+}
+
+/// A file in which the sizes without a cycle are recorded, so
+/// the search can continue with the other sizes. This includes the
+/// sizes that were skipped because of the time limit or the node budget.
+struct FailureLog {
+    file: Mutex<File>,
+}
+
+impl FailureLog {
+    fn create(path: &Path) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        writeln!(file, "# size\toutcome\tnodes\tdepth\tpartial path")?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Writes a line with the size, the outcome, the search statistics
+    /// and the longest partial path that was found.
+    fn record(&self, n: usize, outcome: Outcome, search: &SearchStats) {
+        let path = search
+            .best_path
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let line = format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            n,
+            outcome.as_str(),
+            search.nodes,
+            search.best_path.len(),
+            path
+        );
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            eprintln!("Failed to record the failure for size {}: {}", n, e);
+        }
+    }
+}
+
+/// Runs `work` on `num_threads` threads, passing each thread its index.
+/// Returns the statistics of every thread.
+///
+/// The search is iterative, so the default stack size is enough,
+/// but it can still be overridden with `stack_size`.
+fn run_threads<F>(num_threads: usize, stack_size: Option<usize>, work: F) -> Vec<ThreadStats>
+where
+    F: Fn(usize) -> ThreadStats + Sync,
+{
+    std::thread::scope(|s| {
+        let handles = (0..num_threads)
+            .map(|i| {
+                let work = &work;
+                let mut builder = thread::Builder::new();
+                if let Some(size) = stack_size {
+                    builder = builder.stack_size(size);
+                }
+                builder.spawn_scoped(s, move || work(i)).unwrap()
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| {
+                // A thread only panics if it generated an invalid cycle,
+                // which counts as a failure.
+                handle.join().unwrap_or_else(|_| ThreadStats {
+                    failures: 1,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    })
+}
+
+/// The sizes for which a cycle or path was found, so that a search which was
+/// interrupted can be resumed. Every line has the size and the outcome, like
+/// the output with `--quiet`.
+struct Checkpoint {
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    /// Returns the sizes recorded in the file at `path` in increasing order,
+    /// or none if it doesn't exist yet.
+    fn read(path: &Path) -> io::Result<Vec<usize>> {
+        let mut done = match fs::read_to_string(path) {
+            Ok(contents) => contents
+                .lines()
+                .filter_map(|line| {
+                    let (n, outcome) = line.split_once('\t')?;
+                    matches!(outcome, "cycle" | "path")
+                        .then(|| n.parse().ok())
+                        .flatten()
+                })
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        done.sort_unstable();
+        done.dedup();
+        Ok(done)
+    }
+
+    /// Opens the file at `path` to append the next sizes to.
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Writes a line with the size and the outcome.
+    fn record(&self, n: usize, outcome: Outcome) {
+        let line = format!("{}\t{}\n", n, outcome.as_str());
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            eprintln!("Failed to record size {} in the checkpoint: {}", n, e);
+        }
+    }
+}
+
+/// Prints what a search would do, for `--dry-run`: the sizes, the number of
+/// primes and the memory for them, and the memory used by every thread and
+/// the first size it searches.
+///
+/// The `sizes` are searched, or if there are none, the even sizes from
+/// `start` up to `maximum`.
+fn print_estimates(cli: &Cli, sizes: &[usize], start: usize, maximum: usize, num_threads: usize) {
+    let first_sizes = if sizes.is_empty() {
+        let count = (maximum + 2).saturating_sub(start) / 2;
+        println!(
+            "Sizes: the {} even sizes from {} up to {}",
+            count, start, maximum
+        );
+        (start..=maximum).step_by(2).take(num_threads).collect()
+    } else {
+        println!("Sizes: {} given sizes, up to {}", sizes.len(), maximum);
+        sizes.iter().copied().take(num_threads).collect::<Vec<_>>()
+    };
+    // The prime number theorem, with x / (ln(x) - 1) as a better estimate.
+    let limit = 2 * maximum - 1;
+    let prime_count = if limit < 3 {
+        limit.saturating_sub(1)
+    } else {
+        (limit as f64 / ((limit as f64).ln() - 1.0)) as usize
+    };
+    println!(
+        "Primes: about {} up to {}, which take {}",
+        prime_count,
+        limit,
+        format_bytes(prime_count * std::mem::size_of::<usize>())
+    );
+    if cli.fast {
+        println!("The sizes are shared between the threads by rayon, without matrices");
+        return;
+    }
+    // The diagonals, the degrees and the cycle of the largest size, and
+    // the neighbor lists for the given sizes, as long as they fit in the cache.
+    let mut per_thread = 2 * maximum - 1 + 2 * maximum * std::mem::size_of::<usize>();
+    if !sizes.is_empty() {
+        per_thread += ADJACENCY_BYTES;
+    }
+    println!(
+        "Memory: at most {} per thread, {} in total",
+        format_bytes(per_thread),
+        format_bytes(per_thread * num_threads)
+    );
+    println!("Every thread takes the next size when it is done, starting with:");
+    for (thread, n) in first_sizes.iter().enumerate() {
+        println!("  thread {}: {}", thread, n);
+    }
+}
+
+/// Formats a number of bytes with a binary prefix, like `1.5 MiB`.
+fn format_bytes(bytes: usize) -> String {
+    let mut value = bytes as f64;
+    for unit in ["B", "KiB", "MiB", "GiB"] {
+        if value < 1024.0 {
+            return format!("{:.1} {}", value, unit);
+        }
+        value /= 1024.0;
+    }
+    format!("{:.1} TiB", value)
+}
+
+/// How the sizes from the start up to the maximum are divided between the
+/// threads of a search.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Schedule {
+    /// Every thread takes the next size when it is done, so the threads
+    /// that got easy sizes don't wait for the others
+    Interleaved,
+    /// Every thread searches a consecutive block of sizes, so it can reuse
+    /// the previous cycle and extend the matrix more often
+    Blocks,
+}
+
+/// Appends a line with the schedule, the number of threads, the sizes, the
+/// number of nodes and the time of the search to the CSV file at `path`,
+/// and writes the header first if the file is new.
+fn record_timing(
+    path: &Path,
+    cli: &Cli,
+    start: usize,
+    maximum: usize,
+    stats: &[ThreadStats],
+    time: Duration,
+) -> io::Result<()> {
+    let new = !path.exists();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    if new {
+        writeln!(
+            file,
+            "schedule,threads,start,max,nodes,fresh_searches,seconds"
+        )?;
+    }
+    writeln!(
+        file,
+        "{},{},{},{},{},{},{:.6}",
+        format!("{:?}", cli.schedule).to_lowercase(),
+        stats.len(),
+        start,
+        maximum,
+        stats.iter().map(|s| s.nodes).sum::<u64>(),
+        stats.iter().map(|s| s.fresh_searches).sum::<usize>(),
+        time.as_secs_f64()
+    )
+}
+
+/// The quadruplets found by the fast search, with one line `n p1 p2` per
+/// size, separated by tabs. The sizes are searched in parallel, so the
+/// lines are not in order.
+struct QuadrupletTable {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl QuadrupletTable {
+    /// Writes the table to the file at `path`, or the standard output for `-`.
+    fn create(path: &Path) -> io::Result<Self> {
+        let output: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(BufWriter::new(File::create(path)?))
+        };
+        Ok(Self {
+            output: Mutex::new(output),
+        })
+    }
+
+    fn record(&self, n: usize, p1: usize, p2: usize) {
+        let mut output = self.output.lock().unwrap();
+        if let Err(e) = writeln!(output, "{}\t{}\t{}", n, p1, p2) {
+            eprintln!("Failed to write the quadruplet for size {}: {}", n, e);
+        }
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.output.lock().unwrap().flush()
+    }
+}
+
+/// One of `count` independent jobs, which each search a separate part of
+/// the sizes, for example in an array job on a cluster.
+#[derive(Clone, Copy, Debug)]
+struct Shard {
+    /// Starts at 0.
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    /// Parses `i/k`, where i is less than k.
+    fn parse(arg: &str) -> Result<Self, String> {
+        let parsed = arg
+            .split_once('/')
+            .and_then(|(i, k)| Some((i.parse().ok()?, k.parse().ok()?)));
+        match parsed {
+            Some((index, count)) if index < count => Ok(Shard { index, count }),
+            _ => Err("expected i/k, with i less than k".to_string()),
+        }
+    }
+
+    /// The indices of the part of `len` items that belongs to this shard.
+    /// The parts of all the shards are consecutive, and differ in length
+    /// by at most one.
+    fn part(self, len: usize) -> std::ops::Range<usize> {
+        len * self.index / self.count..len * (self.index + 1) / self.count
+    }
+
+    /// Adds the shard to the name of the file at `path`, before the
+    /// extension, so `checkpoint.tsv` becomes `checkpoint.2-of-8.tsv`.
+    fn file_path(self, path: &Path) -> PathBuf {
+        let mut name = path.file_stem().unwrap_or_default().to_os_string();
+        name.push(format!(".{}-of-{}", self.index, self.count));
+        if let Some(extension) = path.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        path.with_file_name(name)
+    }
+}
+
+/// Reads the sizes given with `--sizes` and `--sizes-file`.
+fn read_sizes(cli: &Cli) -> io::Result<Vec<usize>> {
+    let mut sizes = cli.sizes.clone();
+    if let Some(path) = &cli.sizes_file {
+        sizes.extend(read_numbers(path)?);
+    }
+    Ok(sizes)
+}
+
+/// Reads the numbers in a file, separated by whitespace or commas.
+fn read_numbers(path: &Path) -> io::Result<Vec<usize>> {
+    parse_numbers(&fs::read_to_string(path)?)
+}
+
+/// Parses the numbers in `contents`, separated by whitespace or commas.
+fn parse_numbers(contents: &str) -> io::Result<Vec<usize>> {
+    contents
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+        .map(|word| {
+            word.parse().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid number {:?}: {}", word, e),
+                )
+            })
+        })
+        .collect()
+}
+
+/// The sequences that can be used for the sums of consecutive numbers.
+#[derive(Clone, Debug)]
+enum Sequence {
+    Primes,
+    Squares,
+    Triangular,
+    /// A sequence read from a file
+    File(PathBuf),
+    /// A sequence read from the standard input
+    Stdin,
+}
+
+impl Sequence {
+    /// Parses `primes`, `squares`, `triangular`, `file:<path>` or `-`.
+    fn parse(arg: &str) -> Result<Self, String> {
+        match arg {
+            "primes" => Ok(Sequence::Primes),
+            "squares" => Ok(Sequence::Squares),
+            "triangular" => Ok(Sequence::Triangular),
+            "-" => Ok(Sequence::Stdin),
+            _ => match arg.strip_prefix("file:") {
+                Some(path) => Ok(Sequence::File(PathBuf::from(path))),
+                None => Err("expected primes, squares, triangular, file:<path> or -".to_string()),
+            },
+        }
+    }
+
+    /// Generates the terms of the sequence upto and including `n`,
+    /// or `None` for the primes, which are calculated separately.
+    fn generate(&self, n: usize) -> io::Result<Option<Vec<usize>>> {
+        let mut terms = match self {
+            Sequence::Primes => return Ok(None),
+            Sequence::Squares => gen_squares_upto_n(n),
+            Sequence::Triangular => gen_triangular_upto_n(n),
+            Sequence::File(path) => read_numbers(path)?,
+            Sequence::Stdin => parse_numbers(&io::read_to_string(io::stdin())?)?,
+        };
+        // The terms read from a file or the standard input can be in any order.
+        terms.sort_unstable();
+        terms.dedup();
+        Ok(Some(terms))
+    }
+}
+
+/// Totals collected by a single thread running [`test_for_cycles`].
+#[derive(Debug, Default)]
+struct ThreadStats {
+    /// The number of sizes for which a cycle was found.
+    sizes: usize,
+    /// The number of odd sizes for which a path was constructed.
+    paths: usize,
+    /// The number of times the previous cycle could not be
+    /// extended, and a new search had to be started.
+    fresh_searches: usize,
+    /// The number of sizes for which no cycle was found.
+    failures: usize,
+    /// The number of sizes that were skipped because of the time limit
+    /// or the node budget.
+    skipped: usize,
+    /// The number of sizes for which the cross-check failed.
+    disagreements: usize,
+    /// The fraction of the edges that the constructed and the searched
+    /// cycle have in common, for every size where both were found.
+    overlaps: Vec<f64>,
+    /// The total number of nodes visited by the backtracking searches.
+    nodes: u64,
+    time: Duration,
+}
+
+/// Prints a table with the statistics of every thread.
+fn print_thread_stats(stats: &[ThreadStats]) {
+    println!(
+        "{:>6} {:>10} {:>10} {:>15} {:>10} {:>10} {:>13} {:>15} {:>12}",
+        "Thread",
+        "Sizes",
+        "Paths",
+        "Fresh searches",
+        "Failures",
+        "Skipped",
+        "Disagreements",
+        "Nodes",
+        "Time"
+    );
+    for (i, s) in stats.iter().enumerate() {
+        println!(
+            "{:>6} {:>10} {:>10} {:>15} {:>10} {:>10} {:>13} {:>15} {:>12}",
+            i,
+            s.sizes,
+            s.paths,
+            s.fresh_searches,
+            s.failures,
+            s.skipped,
+            s.disagreements,
+            s.nodes,
+            format!("{:.2?}", s.time)
+        );
+    }
+}
+
+/// The sizes used by the `bench` subcommand.
+const BENCH_SIZES: [usize; 4] = [100, 500, 1_000, 2_000];
+
+/// The ways of finding a Hamiltonian cycle that can be benchmarked.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Strategy {
+    /// Backtracking search from scratch
+    Backtracking,
+    /// Extend the cycle of the previous size, like the default search
+    Greedy,
+    /// Construct the cycle from a prime quadruplet
+    Quadruplet,
+}
+
+/// Finds a Hamiltonian cycle of length `n` with the given strategy.
+///
+/// For the greedy strategy `previous` should be a cycle of length n - 2.
+fn run_strategy(strategy: Strategy, n: usize, primes: &[usize], previous: &[usize]) -> bool {
+    match strategy {
+        Strategy::Backtracking => Hankel::prime_sum_matrix(n, Some(primes))
+            .is_hamiltonian()
+            .is_some(),
+        Strategy::Greedy => {
+            let mat = Hankel::prime_sum_matrix(n, Some(primes));
+            let mut path = previous.to_vec();
+            path.resize(n, 0);
+            mat.hamiltonian_cycle(&mut path, n - 6) || mat.hamiltonian_cycle(&mut path, 1)
+        }
+        Strategy::Quadruplet => match find_prime_quadruplet(n / 2, Some(primes)) {
+            Some((p1, p2)) => HamiltonianCycle::new(p1, p2, n / 2).count() == n,
+            None => false,
+        },
+    }
+}
+
+/// Times every strategy on the sizes in [`BENCH_SIZES`] and prints
+/// the mean, median and standard deviation of the timings.
+fn bench(args: &BenchArgs) {
+    let strategies = if args.strategy.is_empty() {
+        vec![
+            Strategy::Backtracking,
+            Strategy::Greedy,
+            Strategy::Quadruplet,
+        ]
+    } else {
+        args.strategy.clone()
+    };
+    let maximum = BENCH_SIZES[BENCH_SIZES.len() - 1];
+    let primes = gen_primes_upto_n(2 * maximum);
+    println!(
+        "{:>12} {:>6} {:>12} {:>12} {:>12}",
+        "Strategy", "Size", "Mean", "Median", "Sigma"
+    );
+    for strategy in strategies {
+        for n in BENCH_SIZES {
+            let previous = Hankel::prime_sum_matrix(n - 2, Some(&primes))
+                .is_hamiltonian()
+                .expect("No Hamiltonian cycle found for the previous size");
+            for _ in 0..args.warmup {
+                run_strategy(strategy, n, &primes, &previous);
+            }
+            let mut timings = Vec::with_capacity(args.repetitions);
+            for _ in 0..args.repetitions {
+                let now = Instant::now();
+                if !run_strategy(strategy, n, &primes, &previous) {
+                    panic!("Did not find Hamiltonian cycle for size {}.", n);
+                }
+                timings.push(now.elapsed().as_secs_f64());
+            }
+            let (mean, median, sigma) = summarize(&mut timings);
+            println!(
+                "{:>12} {:>6} {:>12} {:>12} {:>12}",
+                format!("{:?}", strategy).to_lowercase(),
+                n,
+                format!("{:.2?}", Duration::from_secs_f64(mean)),
+                format!("{:.2?}", Duration::from_secs_f64(median)),
+                format!("{:.2?}", Duration::from_secs_f64(sigma)),
+            );
+        }
+    }
+}
+
+/// Returns the mean, median and (sample) standard deviation of `values`.
+fn summarize(values: &mut [f64]) -> (f64, f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    let len = values.len();
+    let mean = values.iter().sum::<f64>() / len as f64;
+    values.sort_by(|a, b| a.total_cmp(b));
+    let median = if len.is_multiple_of(2) {
+        (values[len / 2 - 1] + values[len / 2]) / 2.0
+    } else {
+        values[len / 2]
+    };
+    let sigma = if len > 1 {
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (len - 1) as f64;
+        variance.sqrt()
+    } else {
+        0.0
+    };
+    (mean, median, sigma)
+}
+
+/// Writes the prime sum graphs of the sizes `start` upto and including
+/// `max` to files in the output directory.
+fn export_graphs(args: &ExportArgs) -> io::Result<()> {
+    fs::create_dir_all(&args.output)?;
+    let primes = gen_primes_upto_n(2 * args.max - 1);
+    for n in args.start..=args.max {
+        let mat = Hankel::prime_sum_matrix(n, Some(&primes));
+        // Cycles of odd length are impossible, so don't bother searching.
+        let cycle = if args.cycle && n % 2 == 0 {
+            let cycle = mat.is_hamiltonian();
+            if cycle.is_none() {
+                eprintln!("No Hamiltonian cycle found for size {}", n);
+            }
+            cycle
+        } else {
+            None
+        };
+        let path = args
+            .output
+            .join(format!("prime_sum_{}.{}", n, args.format.extension()));
+        let mut output = BufWriter::new(File::create(path)?);
+        match args.format {
+            ExportFormat::Dot => mat.write_dot(output, cycle.as_deref())?,
+            ExportFormat::Graphml => mat.write_graphml(output, cycle.as_deref())?,
+            ExportFormat::Mtx => mat.write_matrix_market(output, cycle.as_deref())?,
+            ExportFormat::Svg => mat.write_svg(output, cycle.as_deref())?,
+            ExportFormat::Graph6 => {
+                writeln!(output, "{}", mat.to_graph6())?;
+                output.flush()?;
+            }
+            ExportFormat::Sparse6 => {
+                writeln!(output, "{}", mat.to_sparse6())?;
+                output.flush()?;
+            }
+            ExportFormat::Pbm => {
+                write!(output, "P4\n{} {}\n", n, n)?;
+                mat.write_bit_rows(output, 1 << 16)?;
+            }
+        }
     }
+    Ok(())
+}
+
+/// Writes the terms `start` upto and including `max` of a sequence in the
+/// OEIS b-file format, with one line `n a(n)` per term.
+///
+/// Stops at the first term that is not defined, and returns its index.
+fn write_bfile<W: Write>(args: &BfileArgs, mut output: W) -> io::Result<Option<usize>> {
+    let primes = gen_primes_upto_n(4 * args.max);
+    let terms = (args.start..=args.max)
+        .into_par_iter()
+        .map(|n| match args.sequence {
+            BfileSequence::Cycles => {
+                Some(Hankel::prime_sum_matrix(2 * n, Some(&primes)).count_hamiltonian_cycles())
+            }
+            BfileSequence::QuadrupletP1 => {
+                find_prime_quadruplet(n, Some(&primes)).map(|(p1, _)| p1 as u64)
+            }
+        })
+        .collect::<Vec<_>>();
+    for (n, term) in (args.start..).zip(terms) {
+        match term {
+            Some(term) => writeln!(output, "{} {}", n, term)?,
+            None => {
+                output.flush()?;
+                return Ok(Some(n));
+            }
+        }
+    }
+    output.flush()?;
+    Ok(None)
+}
+
+/// Prints the `top` even sizes from `start` upto and including `max` with
+/// the fewest Hamiltonian cycles or prime quadruplets, with one line
+/// `n count` per size, from the fewest to the most.
+///
+/// Counterexamples to stronger conjectures, like one that asks for two
+/// disjoint cycles, would show up first at these sizes.
+fn rarest(args: &RarestArgs) {
+    let primes = gen_primes_upto_n(2 * args.max);
+    let mut counts = (args.start..=args.max)
+        .into_par_iter()
+        .filter(|n| n % 2 == 0)
+        .map(|n| {
+            let count = match args.count {
+                RarestCount::Cycles => {
+                    Hankel::prime_sum_matrix(n, Some(&primes)).count_hamiltonian_cycles()
+                }
+                RarestCount::Quadruplets => count_prime_quadruplets(n / 2, &primes) as u64,
+            };
+            (count, n)
+        })
+        .collect::<Vec<_>>();
+    counts.sort_unstable();
+    for (count, n) in counts.into_iter().take(args.top) {
+        println!("{} {}", n, count);
+    }
+}
+
+/// What the `rarest` subcommand counts.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RarestCount {
+    /// The number of Hamiltonian cycles, which is only feasible for small sizes
+    Cycles,
+    /// The number of prime quadruplets, which each give a cycle
+    Quadruplets,
+}
+
+/// The sequences that can be written by the `bfile` subcommand.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BfileSequence {
+    /// The number of Hamiltonian cycles in the prime sum graph of size 2n,
+    /// not counting the direction (OEIS A051252)
+    Cycles,
+    /// The smallest p1 of a prime quadruplet for the size 2n, where p1 = 1 is allowed
+    QuadrupletP1,
+}
+
+/// The file formats supported by the `export` subcommand.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    /// Graphviz DOT
+    Dot,
+    /// GraphML
+    Graphml,
+    /// Matrix Market coordinate format
+    Mtx,
+    /// SVG drawing with the vertices on a circle
+    Svg,
+    /// The graph6 format of nauty, without the cycle
+    Graph6,
+    /// The sparse6 format of nauty, without the cycle
+    Sparse6,
+    /// The adjacency matrix as a black and white PBM image, without the cycle
+    Pbm,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Graphml => "graphml",
+            ExportFormat::Mtx => "mtx",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Graph6 => "g6",
+            ExportFormat::Sparse6 => "s6",
+            ExportFormat::Pbm => "pbm",
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the prime sum graphs for a range of sizes to files
+    Export(ExportArgs),
+    /// Write the terms of a sequence in the OEIS b-file format
+    Bfile(BfileArgs),
+    /// Time the different strategies on a fixed set of sizes
+    Bench(BenchArgs),
+    /// Find the sizes with the fewest Hamiltonian cycles or prime quadruplets
+    Rarest(RarestArgs),
+    /// Print a script for tab completion of the subcommands and options
+    Completions(CompletionsArgs),
+    /// Hand out ranges of sizes to workers and verify their cycles
+    Serve(ServeArgs),
+    /// Search ranges of sizes handed out by a coordinator
+    Worker(WorkerArgs),
+}
+
+#[derive(Args, Debug)]
+struct BenchArgs {
+    /// Number of timed runs per strategy and size
+    #[arg(short, long, default_value_t = 10)]
+    repetitions: usize,
+    /// Number of untimed runs before the timed ones
+    #[arg(short, long, default_value_t = 2)]
+    warmup: usize,
+    /// Strategies to benchmark, all of them if not given
+    #[arg(short, long, value_enum)]
+    strategy: Vec<Strategy>,
+}
+
+#[derive(Args, Debug)]
+struct ExportArgs {
+    /// Size of the first graph to export
+    #[arg(short, long, default_value_t = 1)]
+    start: usize,
+    /// Size of the last graph to export
+    #[arg(short, long)]
+    max: usize,
+    /// Format of the written files
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Dot)]
+    format: ExportFormat,
+    /// Directory to write the files to
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+    /// Search for a Hamiltonian cycle and highlight it
+    #[arg(short, long)]
+    cycle: bool,
+}
+
+#[derive(Args, Debug)]
+struct BfileArgs {
+    /// The sequence to compute
+    #[arg(value_enum)]
+    sequence: BfileSequence,
+    /// Index of the first term
+    #[arg(short, long, default_value_t = 1)]
+    start: usize,
+    /// Index of the last term
+    #[arg(short, long)]
+    max: usize,
+    /// File to write to, instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct RarestArgs {
+    /// What to count for every size
+    #[arg(value_enum)]
+    count: RarestCount,
+    /// The first size, odd sizes are skipped
+    #[arg(short, long, default_value_t = 4)]
+    start: usize,
+    /// The last size
+    #[arg(short, long)]
+    max: usize,
+    /// The number of sizes to print
+    #[arg(short, long, default_value_t = 10)]
+    top: usize,
 }
 
+#[derive(Args, Debug)]
+struct CompletionsArgs {
+    /// The shell to generate the completions for
+    #[arg(value_enum)]
+    shell: clap_complete::Shell,
+}
+
+/// Exit code when there was at least one size without a cycle.
+const EXIT_FAILED: u8 = 1;
+/// Exit code when the search for at least one size was aborted.
+const EXIT_ABORTED: u8 = 2;
+/// Exit code for invalid arguments.
+const EXIT_USAGE: u8 = 64;
+/// Exit code when reading or writing a file failed.
+const EXIT_IO: u8 = 74;
+
 /// Search for prime sum sequences.
 #[derive(Parser, Debug)]
-#[command(name= "Prime sum sequences", version, author, long_about=None)]
+#[command(name= "Prime sum sequences", version, author, long_about=None, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Sequence length to start at
     #[arg(short, long)]
     start: Option<usize>,
     /// Maximum sequence length to search for
-    #[arg(short, long)]
-    max: usize,
-    /// Number of threads
-    #[arg(short, long = "threads", default_value_t = 1)]
-    num_threads: usize,
-    /// Stack size in bytes
-    #[arg(long, default_value_t = 1048576)]
-    stack_size: usize,
+    #[arg(short, long, required_unless_present_any = ["sizes", "sizes_file"])]
+    max: Option<usize>,
+    /// Only search for these sequence lengths, separated by commas
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["start", "max"])]
+    sizes: Vec<usize>,
+    /// Only search for the sequence lengths listed in this file
+    #[arg(long, conflicts_with_all = ["start", "max"])]
+    sizes_file: Option<PathBuf>,
+    /// Number of threads, by default the available parallelism
+    #[arg(short, long = "threads")]
+    num_threads: Option<usize>,
+    /// Stack size in bytes of the search threads. The search does
+    /// not use recursion, so this is normally not needed.
+    #[arg(long)]
+    stack_size: Option<usize>,
     /// Greedily start at n/divisor if non-zero
     #[arg(short, long, default_value_t = 0)]
     divisor: usize,
     /// Use greedy fast search
     #[arg(short, long)]
     fast: bool,
+    /// How a cycle is searched for every size. With rotation, a size is
+    /// skipped if no cycle was found, and --max-nodes is the number of
+    /// steps of every attempt
+    #[arg(long, value_enum, default_value_t = SearchStrategy::Backtracking, conflicts_with = "fast")]
+    strategy: SearchStrategy,
+    /// The seed of the first attempt of the rotation strategy, so that a
+    /// run can be repeated exactly
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Also construct every cycle from a prime quadruplet, and check
+    /// that it agrees with the backtracking search
+    #[arg(long, conflicts_with = "fast")]
+    cross_check: bool,
+    /// Like --cross-check, and also print how many edges the constructed
+    /// and the searched cycles have in common
+    #[arg(long, conflicts_with = "fast")]
+    verify_construction: bool,
+    /// The sequence the sums should belong to: primes, squares,
+    /// triangular, file:<path> or - for the standard input, with the
+    /// numbers separated by whitespace. The search only looks for cycles
+    /// that alternate between odd and even numbers.
+    #[arg(long, default_value = "primes", value_parser = Sequence::parse)]
+    sequence: Sequence,
+    /// Also construct and check Hamiltonian paths for the odd sizes
+    #[arg(long)]
+    include_odd: bool,
+    /// Load the primes from a file written with `--save-primes`
+    #[arg(long)]
+    primes_file: Option<PathBuf>,
+    /// Save the calculated primes to a file
+    #[arg(long)]
+    save_primes: Option<PathBuf>,
+    /// Only print one line per size, with the size and what was found
+    #[arg(short, long)]
+    quiet: bool,
+    /// Skip a size if no cycle is found within this many seconds
+    #[arg(long, value_name = "SECS")]
+    time_limit_per_n: Option<f64>,
+    /// Skip a size if no cycle is found after visiting this many nodes
+    #[arg(long, value_name = "NODES")]
+    max_nodes: Option<u64>,
+    /// Show the progress of every thread in a terminal UI. Only
+    /// available when built with the `tui` feature.
+    #[arg(long, conflicts_with_all = ["quiet", "fast"])]
+    tui: bool,
+    /// Print every cycle or path that was found, with the sum of every
+    /// pair of consecutive numbers
+    #[arg(long, conflicts_with_all = ["quiet", "tui"])]
+    explain: bool,
+    /// Record the sizes without a cycle in this file and continue,
+    /// instead of stopping at the first one
+    #[arg(long)]
+    failures: Option<PathBuf>,
+    /// Also print the minimum degree, the number of vertices of degree 2
+    /// and the edge density of every size, after what was found
+    #[arg(long, conflicts_with = "tui")]
+    degree_stats: bool,
+    /// Only search the i-th of k consecutive parts of the sizes, where i
+    /// starts at 0, so that k independent jobs together search all of them
+    #[arg(long, value_name = "i/k", value_parser = Shard::parse)]
+    shard: Option<Shard>,
+    /// Record the sizes with a cycle or path in this file, and skip the
+    /// ones that are already in it. With --shard, the shard is added to
+    /// the file name
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Only print the sizes that would be searched, how many primes would
+    /// be calculated and the memory that is needed, without searching
+    #[arg(long, conflicts_with = "tui")]
+    dry_run: bool,
+    /// How the sizes are divided between the threads
+    #[arg(long, value_enum, default_value_t = Schedule::Interleaved, conflicts_with_all = ["sizes", "sizes_file", "fast"])]
+    schedule: Schedule,
+    /// Write the size, p1 and p2 of the quadruplet of every size found by
+    /// the fast search to this file, or to the standard output for -
+    #[arg(long, value_name = "FILE", requires = "fast")]
+    quadruplets: Option<PathBuf>,
+    /// Append the schedule, the number of threads, the nodes and the time
+    /// of the search to this CSV file, to compare the schedules
+    #[arg(long, value_name = "FILE")]
+    timings: Option<PathBuf>,
+    /// Read the options from this TOML file, where the keys are the names
+    /// of the options. The options on the command line override them
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
 }
 
-fn main() {
-    let cli = Cli::parse();
-    let start = match cli.start {
-        Some(arg) => {
-            if arg < cli.num_threads * 2 {
-                eprintln!("The number of threads must be less than the start/2");
-                return;
+/// Returns the command line arguments, with the options in the file given
+/// with `--config` inserted in front of them, so that the command line
+/// overrides them.
+///
+/// Every key `name = value` of the file becomes `--name value`, where
+/// underscores are replaced by dashes, `true` becomes `--name` and a list
+/// becomes its values separated by commas. The options of a subcommand are
+/// in a table named after it, and are only used with that subcommand.
+fn args_with_config() -> Result<Vec<OsString>, String> {
+    let mut args = std::env::args_os().collect::<Vec<_>>();
+    let path = match args.iter().position(|arg| arg == "--config") {
+        Some(i) => args.get(i + 1).map(PathBuf::from),
+        None => args
+            .iter()
+            .find_map(|arg| arg.to_str()?.strip_prefix("--config=").map(PathBuf::from)),
+    };
+    let Some(path) = path else {
+        return Ok(args);
+    };
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let table = contents
+        .parse::<toml::Table>()
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    let subcommands = Cli::command()
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect::<Vec<_>>();
+    // The subcommand, if there is one, is the first argument with its name.
+    let subcommand = args
+        .iter()
+        .position(|arg| subcommands.iter().any(|name| arg == name.as_str()));
+    let mut options = Vec::new();
+    let mut subcommand_options = Vec::new();
+    for (key, value) in &table {
+        match value {
+            toml::Value::Table(table) if subcommands.contains(key) => {
+                if subcommand.is_some_and(|i| args[i] == key.as_str()) {
+                    for (key, value) in table {
+                        push_option(&mut subcommand_options, &args, key, value)?;
+                    }
+                }
+            }
+            _ => push_option(&mut options, &args, key, value)?,
+        }
+    }
+    if let Some(i) = subcommand {
+        args.splice(i + 1..i + 1, subcommand_options);
+    }
+    args.splice(1..1, options);
+    Ok(args)
+}
+
+/// Parses the arguments, where an option that is given again overrides
+/// the earlier value, like the command line does with the configuration.
+fn parse_cli(args: Vec<OsString>) -> Result<Cli, clap::Error> {
+    let mut command = Cli::command().args_override_self(true);
+    let subcommands = command
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect::<Vec<_>>();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |c| c.args_override_self(true));
+    }
+    Cli::from_arg_matches(&command.try_get_matches_from(args)?)
+}
+
+/// Adds the option `--key` with `value` from a configuration file to
+/// `options`, unless it is a list that is also given in `args`, since
+/// lists are appended to instead of overridden.
+fn push_option(
+    options: &mut Vec<OsString>,
+    args: &[OsString],
+    key: &str,
+    value: &toml::Value,
+) -> Result<(), String> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    let to_string = |value: &toml::Value| match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        _ => Err(format!(
+            "Unsupported value for {} in the configuration",
+            key
+        )),
+    };
+    match value {
+        toml::Value::Boolean(true) => options.push(flag.into()),
+        toml::Value::Boolean(false) => {}
+        toml::Value::Array(values) => {
+            let given = args.iter().any(|arg| {
+                arg.to_str()
+                    .is_some_and(|arg| arg == flag || arg.starts_with(&format!("{}=", flag)))
+            });
+            if !given {
+                let values = values
+                    .iter()
+                    .map(to_string)
+                    .collect::<Result<Vec<_>, _>>()?;
+                options.push(flag.into());
+                options.push(values.join(",").into());
             }
-            if arg % 2 != 0 {
-                eprintln!("The start should be even");
-                return;
+        }
+        value => {
+            options.push(flag.into());
+            options.push(to_string(value)?.into());
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match args_with_config() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let cli = match parse_cli(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            // Printing the help or the version is not an error.
+            return if e.use_stderr() {
+                ExitCode::from(EXIT_USAGE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+    match &cli.command {
+        Some(Command::Export(args)) => {
+            if args.start == 0 || args.start > args.max {
+                eprintln!("The start should be at least 1 and at most the max");
+                return ExitCode::from(EXIT_USAGE);
+            }
+            if let Err(e) = export_graphs(args) {
+                eprintln!("Failed to export the graphs: {}", e);
+                return ExitCode::from(EXIT_IO);
+            }
+            return ExitCode::SUCCESS;
+        }
+        Some(Command::Bfile(args)) => {
+            let min_start = match args.sequence {
+                BfileSequence::Cycles => 1,
+                BfileSequence::QuadrupletP1 => 2,
+            };
+            if args.start < min_start || args.start > args.max {
+                eprintln!(
+                    "The start should be at least {} and at most the max",
+                    min_start
+                );
+                return ExitCode::from(EXIT_USAGE);
+            }
+            let result = match &args.output {
+                Some(path) => File::create(path).and_then(|f| write_bfile(args, BufWriter::new(f))),
+                None => write_bfile(args, io::stdout().lock()),
+            };
+            return match result {
+                Ok(None) => ExitCode::SUCCESS,
+                Ok(Some(n)) => {
+                    eprintln!("The term for n = {} is not defined, stopping there", n);
+                    ExitCode::from(EXIT_FAILED)
+                }
+                Err(e) => {
+                    eprintln!("Failed to write the b-file: {}", e);
+                    ExitCode::from(EXIT_IO)
+                }
+            };
+        }
+        Some(Command::Bench(args)) => {
+            bench(args);
+            return ExitCode::SUCCESS;
+        }
+        Some(Command::Rarest(args)) => {
+            // The quadruplets need the size to be at least 4.
+            if args.start < 4 || args.start > args.max {
+                eprintln!("The start should be at least 4 and at most the max");
+                return ExitCode::from(EXIT_USAGE);
+            }
+            rarest(args);
+            return ExitCode::SUCCESS;
+        }
+        Some(Command::Completions(args)) => {
+            let mut command = Cli::command();
+            clap_complete::generate(
+                args.shell,
+                &mut command,
+                env!("CARGO_BIN_NAME"),
+                &mut io::stdout(),
+            );
+            return ExitCode::SUCCESS;
+        }
+        Some(Command::Serve(args)) => return distributed::serve(args),
+        Some(Command::Worker(args)) => return distributed::work(args),
+        None => {}
+    }
+    if let Some(limit) = cli.time_limit_per_n {
+        if !limit.is_finite() || limit <= 0.0 {
+            eprintln!("The time limit should be a positive number of seconds");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+    let mut sizes = match read_sizes(&cli) {
+        Ok(sizes) => sizes,
+        Err(e) => {
+            eprintln!("Failed to read the sizes: {}", e);
+            return ExitCode::from(EXIT_IO);
+        }
+    };
+    if sizes.iter().any(|&n| n < 2) {
+        eprintln!("The sizes should be at least 2");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    if !cli.include_odd && sizes.iter().any(|&n| n % 2 != 0) {
+        eprintln!("The sizes should be even, unless --include-odd is given");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let mut maximum = match sizes.iter().max() {
+        // Odd sizes are handled using the cycle of the next size.
+        Some(&n) => n.saturating_add(n % 2),
+        // Only optional when a subcommand or sizes are given.
+        None => cli.max.unwrap(),
+    };
+    // The primes and the sequence are computed up to 2 * maximum - 1.
+    if !(2..=usize::MAX / 2).contains(&maximum) {
+        eprintln!(
+            "The maximum should be at least 2 and at most {}",
+            usize::MAX / 2
+        );
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let num_threads = match cli.num_threads {
+        Some(0) => {
+            eprintln!("The number of threads should be at least 1");
+            return ExitCode::from(EXIT_USAGE);
+        }
+        Some(n) => n,
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let mut start = match cli.start {
+        Some(arg) => {
+            if arg % 2 != 0 || arg < 2 {
+                eprintln!("The start should be even and at least 2");
+                return ExitCode::from(EXIT_USAGE);
             }
             arg
         }
-        None => max(cli.num_threads * 2, 12),
+        None => 12,
+    };
+    let checkpoint_path = cli.checkpoint.as_ref().map(|path| match cli.shard {
+        Some(shard) => shard.file_path(path),
+        None => path.clone(),
+    });
+    let done = match &checkpoint_path {
+        Some(path) => match Checkpoint::read(path) {
+            Ok(done) => done,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path.display(), e);
+                return ExitCode::from(EXIT_IO);
+            }
+        },
+        None => Vec::new(),
+    };
+    if cli.shard.is_some() || checkpoint_path.is_some() {
+        let is_done = |n: usize| done.binary_search(&n).is_ok();
+        let left = if !sizes.is_empty() {
+            if let Some(shard) = cli.shard {
+                sizes = sizes[shard.part(sizes.len())].to_vec();
+            }
+            sizes.retain(|&n| !is_done(n));
+            sizes.iter().max().map(|&n| maximum = n + n % 2)
+        } else {
+            // The even sizes from the start up to the maximum, where the search
+            // continues after the sizes at the start of the part that are done.
+            let count = (maximum + 2).saturating_sub(start) / 2;
+            let part = cli.shard.map_or(0..count, |shard| shard.part(count));
+            part.clone().find(|i| !is_done(start + 2 * i)).map(|i| {
+                maximum = start + 2 * (part.end - 1);
+                start += 2 * i;
+            })
+        };
+        if left.is_none() {
+            if !cli.quiet {
+                println!("There are no sizes left to search");
+            }
+            return ExitCode::SUCCESS;
+        }
+    }
+    if cli.dry_run {
+        print_estimates(&cli, &sizes, start, maximum, num_threads);
+        return ExitCode::SUCCESS;
+    }
+    let checkpoint = match &checkpoint_path {
+        Some(path) => match Checkpoint::open(path) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                eprintln!("Failed to open {}: {}", path.display(), e);
+                return ExitCode::from(EXIT_IO);
+            }
+        },
+        None => None,
     };
-    let increment = 2 * cli.num_threads;
 
     rayon::ThreadPoolBuilder::new()
-        .num_threads(cli.num_threads)
+        .num_threads(num_threads)
         .build_global()
         .unwrap();
 
     let now = Instant::now();
 
     // Calculate primes ahead of time.
-    println!("Calculating primes");
-    let primes = gen_primes_upto_n(2 * cli.max - 1);
-    let primes = std::sync::Arc::new(primes);
-    println!("Finished calculating primes in {:?}", now.elapsed());
-    if cli.fast {
-        // We divide by 2, because `find_prime_quadruplet`
-        // takes in half the size, to ensure that it is even.
-        ((start / 2)..(cli.max / 2)).into_par_iter().for_each(|i| {
-            if find_prime_quadruplet(i, Some(&primes)).is_none() {
-                panic!("Did not find Hamiltonian cycle for size {}.", i * 2);
+    if !cli.quiet {
+        println!("Calculating primes");
+    }
+    let mut primes = match &cli.primes_file {
+        Some(path) => match File::open(path).and_then(|f| read_primes(BufReader::new(f))) {
+            Ok(primes) => primes,
+            Err(e) => {
+                eprintln!("Failed to load primes from {}: {}", path.display(), e);
+                return ExitCode::from(EXIT_IO);
             }
-        });
-    } else {
-        std::thread::scope(|s| {
-            for i in 0..cli.num_threads {
-                let builder = thread::Builder::new();
-                builder
-                    // Spawn threads with explicit stack size
-                    // Needed because of the heavy recursion
-                    .stack_size(cli.stack_size)
-                    .spawn_scoped(s, {
-                        let primes = primes.clone();
-                        move || {
-                            test_for_cycles(cli.max, start, increment, i * 2, cli.divisor, &primes);
-                        }
+        },
+        None => Vec::new(),
+    };
+    // The segments are sieved by the threads of the pool configured above.
+    sieve_primes_by_segments(&mut primes, 2 * maximum - 1);
+    if let Some(path) = &cli.save_primes {
+        if let Err(e) = File::create(path).and_then(|f| write_primes(BufWriter::new(f), &primes)) {
+            eprintln!("Failed to save primes to {}: {}", path.display(), e);
+            return ExitCode::from(EXIT_IO);
+        }
+    }
+    if !cli.quiet {
+        println!("Finished calculating primes in {:?}", now.elapsed());
+    }
+    let sequence = match cli.sequence.generate(2 * maximum - 1) {
+        Ok(sequence) => sequence,
+        Err(e) => {
+            eprintln!("Failed to read the sequence: {}", e);
+            return ExitCode::from(EXIT_IO);
+        }
+    };
+    if (cli.fast || cli.cross_check || cli.verify_construction) && sequence.is_some() {
+        eprintln!("The fast search and the cross-check only work for primes");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    if cli.tui && cfg!(not(feature = "tui")) {
+        eprintln!("The terminal UI is not available, build with `--features tui` to enable it");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let monitor = cli.tui.then(|| Monitor::new(num_threads));
+    let failures = match &cli.failures {
+        Some(path) => match FailureLog::create(path) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("Failed to create {}: {}", path.display(), e);
+                return ExitCode::from(EXIT_IO);
+            }
+        },
+        None => None,
+    };
+    let quadruplets = match &cli.quadruplets {
+        Some(path) => match QuadrupletTable::create(path) {
+            Ok(table) => Some(table),
+            Err(e) => {
+                eprintln!("Failed to create {}: {}", path.display(), e);
+                return ExitCode::from(EXIT_IO);
+            }
+        },
+        None => None,
+    };
+    let config = SearchConfig {
+        divisor: cli.divisor,
+        include_odd: cli.include_odd,
+        primes: &primes,
+        sequence: sequence.as_deref(),
+        failures: failures.as_ref(),
+        quiet: cli.quiet,
+        explain: cli.explain,
+        time_limit: cli.time_limit_per_n.map(Duration::from_secs_f64),
+        max_nodes: cli.max_nodes,
+        stop: AtomicBool::new(false),
+        sink: None,
+        cross_check: cli.cross_check || cli.verify_construction,
+        verify_construction: cli.verify_construction,
+        monitor: monitor.as_ref(),
+        degree_stats: cli.degree_stats,
+        checkpoint: checkpoint.as_ref(),
+        quadruplets: quadruplets.as_ref(),
+        strategy: cli.strategy,
+        seed: cli.seed,
+    };
+    let search = || {
+        if !sizes.is_empty() {
+            if cli.fast {
+                let failures = sizes
+                    .par_iter()
+                    .filter(|&&n| {
+                        let half_size = n.div_ceil(2);
+                        let quadruplet = find_prime_quadruplet(half_size, Some(&primes));
+                        !test_quadruplet(&config, half_size, quadruplet, n % 2 != 0, n % 2 == 0)
+                    })
+                    .count();
+                vec![ThreadStats {
+                    failures,
+                    ..Default::default()
+                }]
+            } else {
+                let next = AtomicUsize::new(0);
+                run_threads(num_threads, cli.stack_size, |i| {
+                    test_sizes(&config, &sizes, &next, i)
+                })
+            }
+        } else if cli.fast {
+            // We divide by 2, because `find_prime_quadruplet`
+            // takes in half the size, to ensure that it is even.
+            // The quadruplets are found a chunk at a time, so that the table of
+            // a huge range doesn't have to fit in memory.
+            let mut failures = 0;
+            let mut first = start / 2;
+            while first <= maximum / 2 && !config.stopped() {
+                let end = (first + QUADRUPLET_CHUNK).min(maximum / 2 + 1);
+                let quadruplets = find_prime_quadruplets_range(first..end, Some(&primes));
+                failures += (first..end)
+                    .into_par_iter()
+                    .zip(quadruplets)
+                    .filter(|&(i, q)| !test_quadruplet(&config, i, q, cli.include_odd, true))
+                    .count();
+                first = end;
+            }
+            vec![ThreadStats {
+                failures,
+                ..Default::default()
+            }]
+        } else {
+            match cli.schedule {
+                Schedule::Interleaved => {
+                    let next = AtomicUsize::new(start);
+                    run_threads(num_threads, cli.stack_size, |i| {
+                        test_for_cycles(&config, &next, maximum, i)
                     })
-                    .unwrap();
+                }
+                Schedule::Blocks => {
+                    let count = (maximum + 2).saturating_sub(start) / 2;
+                    run_threads(num_threads, cli.stack_size, |i| {
+                        let shard = Shard {
+                            index: i,
+                            count: num_threads,
+                        };
+                        let part = shard.part(count);
+                        let next = AtomicUsize::new(start + 2 * part.start);
+                        // An empty block ends before it starts.
+                        let last = (start + 2 * part.end).saturating_sub(2);
+                        test_for_cycles(&config, &next, last, i)
+                    })
+                }
             }
-        });
+        }
+    };
+    let stats = match &monitor {
+        #[cfg(feature = "tui")]
+        Some(monitor) => tui::run(monitor, &config.stop, search),
+        _ => search(),
     };
-    println!("All threads done, total time: {:?}", now.elapsed());
+    if let Some(table) = &quadruplets {
+        if let Err(e) = table.flush() {
+            eprintln!("Failed to write the quadruplets: {}", e);
+            return ExitCode::from(EXIT_IO);
+        }
+    }
+    if let Some(path) = &cli.timings {
+        if let Err(e) = record_timing(path, &cli, start, maximum, &stats, now.elapsed()) {
+            eprintln!("Failed to record the timing in {}: {}", path.display(), e);
+        }
+    }
+    if !cli.quiet {
+        if !cli.fast {
+            print_thread_stats(&stats);
+        }
+        if cli.verify_construction {
+            let mut overlaps = stats
+                .iter()
+                .flat_map(|s| s.overlaps.clone())
+                .collect::<Vec<_>>();
+            let (mean, median, sigma) = summarize(&mut overlaps);
+            println!(
+                "The constructed and searched cycles of {} sizes share {:.1}% of their edges \
+                 on average (median {:.1}%, σ {:.1}%)",
+                overlaps.len(),
+                100.0 * mean,
+                100.0 * median,
+                100.0 * sigma
+            );
+        }
+        println!("All threads done, total time: {:?}", now.elapsed());
+    }
+    if stats.iter().any(|s| s.failures > 0 || s.disagreements > 0) {
+        ExitCode::from(EXIT_FAILED)
+    } else if stats.iter().any(|s| s.skipped > 0) || config.stopped() {
+        ExitCode::from(EXIT_ABORTED)
+    } else {
+        ExitCode::SUCCESS
+    }
 }
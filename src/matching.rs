@@ -0,0 +1,464 @@
+//! Maximum matchings, minimum vertex covers and 2-factors of the graphs that
+//! are bipartite between the odd and the even numbers, like the prime sum graphs.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::connectivity::max_flow;
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns a maximum matching as pairs of an odd and an even vertex, or
+    /// `None` if two vertices of the same parity are adjacent.
+    ///
+    /// Every odd vertex is matched in turn by searching for an augmenting path.
+    pub fn maximum_matching(&self) -> Option<Vec<(usize, usize)>> {
+        let mate = self.even_mates()?;
+        Some(
+            (2..=self.size)
+                .step_by(2)
+                .filter_map(|even| mate[even].map(|odd| (odd, even)))
+                .collect(),
+        )
+    }
+
+    /// Returns a minimum vertex cover, the smallest set of vertices touching every
+    /// edge, in increasing order. Returns `None` like [`Hankel::maximum_matching`].
+    ///
+    /// By König's theorem the cover has one vertex of every edge in a maximum
+    /// matching: the even one if it can be reached from an unmatched odd vertex
+    /// by a path alternating between other edges and edges of the matching, and
+    /// the odd one otherwise.
+    pub fn minimum_vertex_cover(&self) -> Option<Vec<usize>> {
+        let mate = self.even_mates()?;
+        let mut odd_mate = vec![None; self.size + 1];
+        for even in (2..=self.size).step_by(2) {
+            if let Some(odd) = mate[even] {
+                odd_mate[odd] = Some(even);
+            }
+        }
+        let mut reached = vec![false; self.size + 1];
+        let mut stack = (1..=self.size)
+            .step_by(2)
+            .filter(|&odd| odd_mate[odd].is_none())
+            .collect::<Vec<_>>();
+        for &odd in &stack {
+            reached[odd] = true;
+        }
+        while let Some(odd) = stack.pop() {
+            for even in (2..=self.size).step_by(2) {
+                if reached[even] || self.get(odd, even) == 0 {
+                    continue;
+                }
+                reached[even] = true;
+                // Every even neighbor is matched, or the matching isn't maximum.
+                if let Some(next) = mate[even].filter(|&next| !reached[next]) {
+                    reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        Some(
+            (1..=self.size)
+                .filter(|&v| reached[v] == (v % 2 == 0))
+                .collect(),
+        )
+    }
+
+    /// Returns a set of odd vertices with fewer neighbors than vertices, in
+    /// increasing order, or `None` if there is none or the graph isn't
+    /// bipartite by parity.
+    ///
+    /// By Hall's theorem there is such a set if and only if some odd vertex
+    /// is unmatched in a maximum matching, and then there is no Hamiltonian
+    /// cycle. The set is the odd vertices reached from an unmatched one by
+    /// alternating paths, whose neighbors are all matched to the others.
+    pub fn hall_violator(&self) -> Option<Vec<usize>> {
+        let mate = self.even_mates()?;
+        let mut matched = vec![false; self.size + 1];
+        for odd in mate.iter().flatten() {
+            matched[*odd] = true;
+        }
+        let start = (1..=self.size).step_by(2).find(|&odd| !matched[odd])?;
+        let mut reached = vec![false; self.size + 1];
+        reached[start] = true;
+        let mut stack = vec![start];
+        while let Some(odd) = stack.pop() {
+            for even in (2..=self.size).step_by(2) {
+                if reached[even] || self.get(odd, even) == 0 {
+                    continue;
+                }
+                reached[even] = true;
+                if let Some(next) = mate[even].filter(|&next| !reached[next]) {
+                    reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        Some(
+            (1..=self.size)
+                .step_by(2)
+                .filter(|&odd| reached[odd])
+                .collect(),
+        )
+    }
+
+    /// Returns a 2-factor, disjoint cycles which together visit every vertex,
+    /// or `None` if there is none or the graph isn't bipartite by parity.
+    /// Every cycle starts at its smallest vertex.
+    ///
+    /// A Hamiltonian cycle is a 2-factor with one cycle, so if there is no
+    /// 2-factor, there is no Hamiltonian cycle. The edges are found with a
+    /// flow, where every odd vertex sends 2 units to its even neighbors.
+    pub fn two_factor(&self) -> Option<Vec<Vec<usize>>> {
+        let n = self.size;
+        if !self.is_bipartite_by_parity() || n % 2 == 1 {
+            return None;
+        }
+        // The source is 0 and the sink is n + 1.
+        let mut capacity = vec![vec![0; n + 2]; n + 2];
+        for odd in (1..=n).step_by(2) {
+            capacity[0][odd] = 2;
+            for even in (2..=n).step_by(2) {
+                capacity[odd][even] = usize::from(self.get(odd, even) != 0);
+            }
+        }
+        for even in (2..=n).step_by(2) {
+            capacity[even][n + 1] = 2;
+        }
+        if max_flow(&mut capacity, 0, n + 1) < n {
+            return None;
+        }
+        // The edges with flow have a residual capacity back to the odd vertex.
+        let mut neighbors = vec![Vec::with_capacity(2); n + 1];
+        for odd in (1..=n).step_by(2) {
+            for even in (2..=n).step_by(2) {
+                if capacity[even][odd] > 0 {
+                    neighbors[odd].push(even);
+                    neighbors[even].push(odd);
+                }
+            }
+        }
+        let mut visited = vec![false; n + 1];
+        let mut cycles = Vec::new();
+        for start in 1..=n {
+            if visited[start] {
+                continue;
+            }
+            let mut cycle = vec![start];
+            visited[start] = true;
+            let (mut previous, mut v) = (start, neighbors[start][0]);
+            while v != start {
+                cycle.push(v);
+                visited[v] = true;
+                let next = neighbors[v][usize::from(neighbors[v][0] == previous)];
+                (previous, v) = (v, next);
+            }
+            cycles.push(cycle);
+        }
+        Some(cycles)
+    }
+
+    /// Returns the number of perfect matchings, or `None` if it doesn't fit
+    /// in a `u128`. This is only feasible for small or sparse graphs.
+    ///
+    /// If the graph is bipartite by parity, this is the permanent of the
+    /// matrix between the odd and the even vertices. Up to 24 odd vertices it
+    /// is computed with Ryser's formula in about 2^(n / 2) steps. For larger
+    /// sizes, or if Ryser's sums overflow, the odd vertices are matched one
+    /// by one, keeping track of the even vertices that are used and still
+    /// have unmatched neighbors, which is fast for sparse graphs but can take
+    /// much longer for dense ones. Otherwise the smallest
+    /// vertex that isn't matched yet is matched with each of its neighbors in
+    /// turn. Then `None` is also returned if the number of matchings of a
+    /// part of the graph doesn't fit.
+    ///
+    /// Every Hamiltonian cycle of even length is the union of two perfect
+    /// matchings, so the number of cycles is at most the square of this.
+    pub fn count_perfect_matchings(&self) -> Option<u128> {
+        let n = self.size;
+        if n % 2 == 1 {
+            return Some(0);
+        }
+        if self.is_bipartite_by_parity() {
+            if n / 2 <= RYSER_MAX_HALF_SIZE {
+                let between = (1..=n)
+                    .step_by(2)
+                    .map(|odd| {
+                        (2..=n)
+                            .step_by(2)
+                            .map(|even| i128::from(self.get(odd, even)))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                if let Some(count) = permanent(&between) {
+                    return Some(count);
+                }
+            }
+            return self.count_bipartite_matchings();
+        }
+        let mut matched = vec![false; n + 1];
+        self.count_matchings_from(&mut matched)
+    }
+
+    /// Counts the perfect matchings of a graph that is bipartite by parity,
+    /// by matching the odd vertices in increasing order.
+    ///
+    /// The partial matchings are grouped by the even vertices they use, but
+    /// only the even vertices that are adjacent to an odd vertex that comes
+    /// later are kept. An even vertex whose last odd neighbor has been
+    /// matched without it can't be matched anymore, so those partial
+    /// matchings are dropped. When the graph is sparse, like for the
+    /// sequences with few terms, there are few such groups, even if Ryser's
+    /// formula would take too long.
+    fn count_bipartite_matchings(&self) -> Option<u128> {
+        let n = self.size;
+        // The last odd vertex that every even vertex is adjacent to.
+        let mut last_odd = vec![0; n + 1];
+        for odd in (1..=n).step_by(2) {
+            for even in (2..=n).step_by(2) {
+                if self.get(odd, even) != 0 {
+                    last_odd[even] = odd;
+                }
+            }
+        }
+        if (2..=n).step_by(2).any(|even| last_odd[even] == 0) {
+            return Some(0);
+        }
+        // The even vertices that every odd vertex is the last neighbor of.
+        let mut retiring = vec![Vec::new(); n + 1];
+        for even in (2..=n).step_by(2) {
+            retiring[last_odd[even]].push(even);
+        }
+        // The used even vertices that still have unmatched neighbors, sorted.
+        let mut partial = BTreeMap::new();
+        partial.insert(Vec::new(), 1u128);
+        for odd in (1..=n).step_by(2) {
+            let mut next: BTreeMap<Vec<usize>, u128> = BTreeMap::new();
+            for (used, &count) in &partial {
+                for even in (2..=n).step_by(2) {
+                    if self.get(odd, even) == 0 || used.binary_search(&even).is_ok() {
+                        continue;
+                    }
+                    let mut used = used.clone();
+                    used.insert(used.partition_point(|&e| e < even), even);
+                    // The even vertices that are last adjacent to this odd
+                    // one must be used by now.
+                    if !retiring[odd].iter().all(|e| used.binary_search(e).is_ok()) {
+                        continue;
+                    }
+                    used.retain(|&e| last_odd[e] != odd);
+                    let entry = next.entry(used).or_insert(0);
+                    *entry = entry.checked_add(count)?;
+                }
+            }
+            partial = next;
+        }
+        Some(partial.get(&Vec::new()).copied().unwrap_or(0))
+    }
+
+    /// Counts the perfect matchings of the vertices which aren't `matched`,
+    /// or returns `None` if the count doesn't fit in a `u128`.
+    fn count_matchings_from(&self, matched: &mut [bool]) -> Option<u128> {
+        let Some(v) = (1..=self.size).find(|&v| !matched[v]) else {
+            return Some(1);
+        };
+        matched[v] = true;
+        let mut count = Some(0u128);
+        for w in v + 1..=self.size {
+            if !matched[w] && self.get(v, w) != 0 {
+                matched[w] = true;
+                count = count.and_then(|c| c.checked_add(self.count_matchings_from(matched)?));
+                matched[w] = false;
+            }
+        }
+        matched[v] = false;
+        count
+    }
+
+    /// Returns the odd vertex matched to every even vertex in a maximum
+    /// matching, or `None` if the graph isn't bipartite by parity.
+    fn even_mates(&self) -> Option<Vec<Option<usize>>> {
+        if !self.is_bipartite_by_parity() {
+            return None;
+        }
+        let mut mate = vec![None; self.size + 1];
+        let mut visited = vec![false; self.size + 1];
+        for odd in (1..=self.size).step_by(2) {
+            visited.fill(false);
+            self.augment(odd, &mut mate, &mut visited);
+        }
+        Some(mate)
+    }
+
+    /// Searches for an augmenting path from the unmatched `odd` vertex, and
+    /// swaps the edges along it. Returns false if there is none.
+    fn augment(&self, odd: usize, mate: &mut [Option<usize>], visited: &mut [bool]) -> bool {
+        for even in (2..=self.size).step_by(2) {
+            if visited[even] || self.get(odd, even) == 0 {
+                continue;
+            }
+            visited[even] = true;
+            if mate[even].is_none_or(|next| self.augment(next, mate, visited)) {
+                mate[even] = Some(odd);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// The largest number of odd vertices for which
+/// [`Hankel::count_perfect_matchings`] uses Ryser's formula, which takes
+/// about 2^m * m steps for m odd vertices.
+const RYSER_MAX_HALF_SIZE: usize = 24;
+
+/// Returns the permanent of the square `matrix` with Ryser's formula, where
+/// the subsets of the columns are visited in the order of a Gray code, so
+/// that the row sums change by one column at a time.
+///
+/// Returns `None` if one of the products or sums overflows, even if the
+/// permanent itself would fit.
+///
+/// # Panics
+///
+/// If the matrix has 64 rows or more, since the subsets are `u64`s.
+fn permanent(matrix: &[Vec<i128>]) -> Option<u128> {
+    let m = matrix.len();
+    assert!(
+        m < 64,
+        "Ryser's formula is only used for fewer than 64 rows"
+    );
+    if m == 0 {
+        return Some(1);
+    }
+    let mut row_sums = vec![0; m];
+    let mut total: i128 = 0;
+    for k in 1..1u64 << m {
+        // The column that is added or removed from the subset.
+        let col = k.trailing_zeros() as usize;
+        let sign = if (k ^ (k >> 1)) >> col & 1 == 1 {
+            1
+        } else {
+            -1
+        };
+        for (sum, row) in row_sums.iter_mut().zip(matrix) {
+            *sum += sign * row[col];
+        }
+        let product = row_sums
+            .iter()
+            .try_fold(1i128, |product, &sum| product.checked_mul(sum))?;
+        // The subsets with as many columns as m, modulo 2, are added.
+        if (k ^ (k >> 1)).count_ones() % 2 == m as u32 % 2 {
+            total = total.checked_add(product)?;
+        } else {
+            total = total.checked_sub(product)?;
+        }
+    }
+    u128::try_from(total).ok()
+}
+
+#[test]
+fn matching_and_cover() {
+    // The prime sum graphs with a Hamiltonian cycle have a perfect matching.
+    let mat = Hankel::prime_sum_matrix(10, None);
+    let matching = mat.maximum_matching().unwrap();
+    assert_eq!(matching.len(), 5);
+    assert!(matching.iter().all(|&(odd, even)| mat.get(odd, even) == 1));
+    let cover = mat.minimum_vertex_cover().unwrap();
+    assert_eq!(cover.len(), 5);
+    assert!(mat
+        .edges()
+        .all(|(i, j)| cover.contains(&i) || cover.contains(&j)));
+    // 2 is the only neighbor of 1 and 5, and 3 - 4 is another edge.
+    let forked = Hankel::from_sequence(5, &[3, 7]);
+    assert_eq!(forked.maximum_matching().unwrap().len(), 2);
+    assert_eq!(forked.minimum_vertex_cover(), Some(vec![2, 3]));
+    // 1 + 3 = 4 is even, so the triangle isn't bipartite by parity.
+    let triangle = Hankel::from_sequence(3, &[3, 4, 5]);
+    assert_eq!(triangle.maximum_matching(), None);
+    assert_eq!(triangle.minimum_vertex_cover(), None);
+}
+
+#[test]
+fn two_factors() {
+    for n in [4, 10, 30, 60] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        let cycles = mat.two_factor().unwrap();
+        let mut vertices = cycles.concat();
+        vertices.sort_unstable();
+        assert_eq!(vertices, (1..=n).collect::<Vec<_>>());
+        for cycle in &cycles {
+            assert!(cycle.len() >= 4 && cycle.len() % 2 == 0);
+            assert!(cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .all(|(&v, &w)| mat.get(v, w) != 0));
+        }
+    }
+    // 2 is the only neighbor of 1 and 5.
+    assert_eq!(Hankel::from_sequence(6, &[3, 7]).two_factor(), None);
+    assert_eq!(Hankel::prime_sum_matrix(7, None).two_factor(), None);
+    assert_eq!(Hankel::from_sequence(3, &[3, 4, 5]).two_factor(), None);
+    assert_eq!(Hankel::from_sequence(0, &[]).two_factor(), Some(Vec::new()));
+}
+
+#[test]
+fn perfect_matchings() {
+    // 1 - 2 - 3 - 4 - 1 is a square, with two perfect matchings.
+    assert_eq!(
+        Hankel::prime_sum_matrix(4, None).count_perfect_matchings(),
+        Some(2)
+    );
+    assert_eq!(
+        Hankel::prime_sum_matrix(5, None).count_perfect_matchings(),
+        Some(0)
+    );
+    assert_eq!(
+        Hankel::from_sequence(0, &[]).count_perfect_matchings(),
+        Some(1)
+    );
+    // The complete graph on 4 vertices has 3 perfect matchings.
+    let complete = Hankel::from_sequence(4, &[3, 4, 5, 6, 7]);
+    assert_eq!(complete.count_perfect_matchings(), Some(3));
+    for n in [6, 8, 10, 12, 14] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        let mut matched = vec![false; n + 1];
+        let matchings = mat.count_perfect_matchings().unwrap();
+        assert_eq!(Some(matchings), mat.count_matchings_from(&mut matched));
+        assert_eq!(Some(matchings), mat.count_bipartite_matchings());
+        let cycles = u128::from(mat.count_hamiltonian_cycles());
+        assert!(cycles <= matchings * matchings);
+    }
+    // These are too large for Ryser's formula, but sparse enough to be
+    // counted quickly. A path of 200 vertices has one perfect matching.
+    let path = Hankel::from_differences(200, &[1]);
+    assert_eq!(path.count_perfect_matchings(), Some(1));
+    let sparse = Hankel::from_differences(30, &[1, 3]);
+    assert_eq!(
+        sparse.count_perfect_matchings(),
+        sparse.count_matchings_from(&mut [false; 31])
+    );
+    // The products of Ryser's formula overflow, even for 2 by 2 matrices.
+    let large = i128::MAX / 2;
+    assert_eq!(permanent(&[vec![large, large], vec![large, large]]), None);
+}
+
+#[test]
+fn hall_violators() {
+    // 2 is the only neighbor of 1 and 5.
+    assert_eq!(
+        Hankel::from_sequence(5, &[3, 7]).hall_violator(),
+        Some(vec![1, 5])
+    );
+    for n in [5, 7, 13] {
+        let mat = Hankel::prime_sum_matrix(n, None);
+        let odd = mat.hall_violator().unwrap();
+        let neighbors = (1..=n).filter(|&v| odd.iter().any(|&w| mat.get(v, w) != 0));
+        assert!(odd.iter().all(|&v| v % 2 == 1));
+        assert!(neighbors.count() < odd.len());
+    }
+    assert_eq!(Hankel::prime_sum_matrix(10, None).hall_violator(), None);
+    assert_eq!(Hankel::from_sequence(3, &[3, 4, 5]).hall_violator(), None);
+}
@@ -0,0 +1,179 @@
+//! Hamiltonian cycles that are optimal for the sums (or differences) of
+//! the adjacent vertices, which are the primes in the prime sum graphs.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Cycle, Hankel, SumOrDiff};
+
+/// What [`Hankel::optimal_cycle`] optimizes. The weight of an edge is the sum
+/// of its vertices, or their difference, see [`SumOrDiff`].
+///
+/// In a Hamiltonian cycle every vertex is in two edges, so the total of the
+/// sums is always n(n + 1). The totals only differ for the differences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// The smallest total weight of the edges.
+    MinTotal,
+    /// The largest total weight of the edges.
+    MaxTotal,
+    /// The smallest largest weight of an edge.
+    MinLargest,
+    /// The largest smallest weight of an edge.
+    MaxSmallest,
+}
+
+/// A partial cycle being searched, with the best complete cycle so far.
+struct Search {
+    objective: Objective,
+    /// The largest weight of an edge, to bound the total of the edges left.
+    max_weight: u64,
+    path: Vec<usize>,
+    used: Vec<bool>,
+    best: Option<(u64, Vec<usize>)>,
+}
+
+impl Objective {
+    /// Combines the value of the edges so far with the weight of another edge.
+    fn add(self, value: u64, weight: u64) -> u64 {
+        match self {
+            Self::MinTotal | Self::MaxTotal => value + weight,
+            Self::MinLargest => value.max(weight),
+            Self::MaxSmallest => value.min(weight),
+        }
+    }
+
+    /// The value of a path without edges.
+    fn empty(self) -> u64 {
+        match self {
+            Self::MaxSmallest => u64::MAX,
+            _ => 0,
+        }
+    }
+
+    /// Checks if the cycle with value `a` is better than the one with `b`.
+    fn better(self, a: u64, b: u64) -> bool {
+        match self {
+            Self::MinTotal | Self::MinLargest => a < b,
+            Self::MaxTotal | Self::MaxSmallest => a > b,
+        }
+    }
+}
+
+impl Hankel {
+    /// Returns a Hamiltonian cycle starting at 1 which is optimal for the
+    /// `objective`, or `None` if there is no Hamiltonian cycle.
+    ///
+    /// This is a branch and bound search, where a path is cut if it can't
+    /// become better than the best cycle so far. Like
+    /// [`Hankel::count_hamiltonian_cycles`] this is only feasible for small sizes,
+    /// unless the total of the sums is optimized, which is the same for every cycle.
+    pub fn optimal_cycle(&self, objective: Objective) -> Option<Cycle> {
+        if self.size == 0 {
+            return None;
+        }
+        if self.kind == SumOrDiff::Sum
+            && matches!(objective, Objective::MinTotal | Objective::MaxTotal)
+        {
+            return self.is_hamiltonian();
+        }
+        let mut search = Search {
+            objective,
+            max_weight: match self.kind {
+                SumOrDiff::Sum => 2 * self.size as u64 - 1,
+                SumOrDiff::Diff => self.size as u64 - 1,
+            },
+            path: vec![1],
+            used: vec![false; self.size + 1],
+            best: None,
+        };
+        search.used[1] = true;
+        self.extend_optimally(&mut search, objective.empty());
+        search.best.map(|(_, cycle)| Cycle::found(cycle, self))
+    }
+
+    /// Returns the sum or the difference of the 1-based vertices `i` and `j`.
+    fn weight(&self, i: usize, j: usize) -> u64 {
+        match self.kind {
+            SumOrDiff::Sum => (i + j) as u64,
+            SumOrDiff::Diff => i.abs_diff(j) as u64,
+        }
+    }
+
+    /// Extends the path of `search`, where `value` is the value of its edges,
+    /// and keeps the best cycle found.
+    fn extend_optimally(&self, search: &mut Search, value: u64) {
+        let objective = search.objective;
+        // The best value the cycle can still get.
+        let bound = match objective {
+            Objective::MaxTotal => {
+                let left = (self.size + 1 - search.path.len()) as u64;
+                value + left * search.max_weight
+            }
+            _ => value,
+        };
+        if let Some((best, _)) = search.best {
+            if !objective.better(bound, best) {
+                return;
+            }
+        }
+        let last = search.path[search.path.len() - 1];
+        if search.path.len() == self.size {
+            if self.get(last, 1) != 0 {
+                let value = objective.add(value, self.weight(last, 1));
+                if search
+                    .best
+                    .as_ref()
+                    .is_none_or(|&(best, _)| objective.better(value, best))
+                {
+                    search.best = Some((value, search.path.clone()));
+                }
+            }
+            return;
+        }
+        for next in 2..=self.size {
+            if search.used[next] || self.get(last, next) == 0 {
+                continue;
+            }
+            search.path.push(next);
+            search.used[next] = true;
+            self.extend_optimally(search, objective.add(value, self.weight(last, next)));
+            search.used[next] = false;
+            search.path.pop();
+        }
+    }
+}
+
+#[test]
+fn optimal_cycles() {
+    let mat = Hankel::prime_sum_matrix(10, None);
+    let sums = |cycle: &[usize]| {
+        (0..cycle.len())
+            .map(|i| cycle[i] + cycle[(i + 1) % cycle.len()])
+            .collect::<Vec<_>>()
+    };
+    let cycle = mat.optimal_cycle(Objective::MinTotal).unwrap();
+    assert_eq!(sums(&cycle).iter().sum::<usize>(), 10 * 11);
+    // 10 is next to two of 1, 3, 7 and 9, so one of its sums is at least 13.
+    let cycle = mat.optimal_cycle(Objective::MinLargest).unwrap();
+    assert!(mat.valid_cycle(&cycle));
+    assert_eq!(sums(&cycle).into_iter().max(), Some(13));
+    let cycle = mat.optimal_cycle(Objective::MaxSmallest).unwrap();
+    assert!(mat.valid_cycle(&cycle));
+    // 1 is next to two of 2, 4, 6 and 10, so one of its sums is at most 7.
+    assert_eq!(sums(&cycle).into_iter().min(), Some(7));
+    let diffs = Hankel::prime_difference_matrix(8, None);
+    let differences = |cycle: &[usize]| {
+        (0..cycle.len())
+            .map(|i| cycle[i].abs_diff(cycle[(i + 1) % cycle.len()]))
+            .sum::<usize>()
+    };
+    let min = diffs.optimal_cycle(Objective::MinTotal).unwrap();
+    let max = diffs.optimal_cycle(Objective::MaxTotal).unwrap();
+    assert!(diffs.valid_cycle(&min) && diffs.valid_cycle(&max));
+    assert!(differences(&min) < differences(&max));
+    assert_eq!(
+        Hankel::prime_sum_matrix(7, None).optimal_cycle(Objective::MinLargest),
+        None
+    );
+}
@@ -0,0 +1,380 @@
+//! Hamiltonian paths and cycles that are known to be valid for the graph
+//! they were found in, so that a path can't be passed where a cycle is
+//! expected, and the [`PartialPath`]s that the searches start from.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use crate::{Error, Hankel, SearchLimits, SearchResult, SearchStats};
+
+/// A Hamiltonian path of a [`Hankel`] matrix, with 1-indexed vertices.
+///
+/// It visits every vertex once, and consecutive vertices are adjacent.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Path(Vec<usize>);
+
+/// A Hamiltonian cycle of a [`Hankel`] matrix, with 1-indexed vertices.
+///
+/// It is a [`Path`] where the last vertex is also adjacent to the first one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cycle(Vec<usize>);
+
+impl Path {
+    /// Returns the path, or [`Error::InvalidPath`] if it isn't a Hamiltonian
+    /// path of `mat`.
+    pub fn new(path: Vec<usize>, mat: &Hankel) -> Result<Self, Error> {
+        if path.len() != mat.size || !mat.valid_path(&path) {
+            return Err(Error::InvalidPath);
+        }
+        Ok(Self(path))
+    }
+
+    /// Wraps a path found by a search, which is only checked in debug builds.
+    pub(crate) fn found(path: Vec<usize>, mat: &Hankel) -> Self {
+        debug_assert!(path.len() == mat.size && mat.valid_path(&path));
+        Self(path)
+    }
+
+    /// Returns the vertices of the path.
+    pub fn into_vec(self) -> Vec<usize> {
+        self.0
+    }
+}
+
+impl Cycle {
+    /// Returns the cycle, or [`Error::InvalidCycle`] if it isn't a Hamiltonian
+    /// cycle of `mat`.
+    pub fn new(cycle: Vec<usize>, mat: &Hankel) -> Result<Self, Error> {
+        if cycle.len() != mat.size || !mat.valid_cycle(&cycle) {
+            return Err(Error::InvalidCycle);
+        }
+        Ok(Self(cycle))
+    }
+
+    /// Wraps a cycle found by a search, which is only checked in debug builds.
+    pub(crate) fn found(cycle: Vec<usize>, mat: &Hankel) -> Self {
+        debug_assert!(cycle.len() == mat.size && mat.valid_cycle(&cycle));
+        Self(cycle)
+    }
+
+    /// Returns the vertices of the cycle.
+    pub fn into_vec(self) -> Vec<usize> {
+        self.0
+    }
+
+    /// Returns the Hamiltonian path that starts at `vertex` and goes around
+    /// the cycle, or `None` if `vertex` is not in the cycle. Unlike
+    /// [`crate::cycle_to_path`], `vertex` is kept.
+    pub fn to_path(&self, vertex: usize) -> Option<Path> {
+        let index = self.0.iter().position(|&v| v == vertex)?;
+        let mut path = self.0.clone();
+        path.rotate_left(index);
+        Some(Path(path))
+    }
+}
+
+/// The start of a Hamiltonian cycle, which a search can complete, see
+/// [`Hankel::complete_cycle`].
+///
+/// Every vertex is visited at most once, and the vertices alternate between
+/// odd and even, starting with an odd one, like in the searches. Instead of
+/// checking a slice and a position at every search, the path is checked
+/// when a vertex is added.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PartialPath {
+    /// An entry for every vertex: the first `len` are the path, and the
+    /// others are 0.
+    path: Vec<usize>,
+    /// Whether every vertex, from 1 to n, is on the path. Entry 0 is unused.
+    visited: Vec<bool>,
+    len: usize,
+}
+
+impl PartialPath {
+    /// Returns the path that only contains 1, where every search starts.
+    ///
+    /// # Panics
+    ///
+    /// If `mat` is empty.
+    pub fn new(mat: &Hankel) -> Self {
+        assert!(mat.size > 0, "the matrix is empty");
+        let mut partial = Self {
+            path: vec![0; mat.size],
+            visited: vec![false; mat.size + 1],
+            len: 0,
+        };
+        partial.add(1);
+        partial
+    }
+
+    /// Returns the path with the vertices of `prefix`, or
+    /// [`Error::InvalidPrefix`] with the index of the first vertex that
+    /// can't be added, see [`PartialPath::push`]. An empty prefix can't be
+    /// completed, so it is an error at index 0.
+    pub fn from_prefix(prefix: &[usize], mat: &Hankel) -> Result<Self, Error> {
+        let mut partial = Self {
+            path: vec![0; mat.size],
+            visited: vec![false; mat.size + 1],
+            len: 0,
+        };
+        if prefix.is_empty() {
+            return Err(Error::InvalidPrefix { index: 0 });
+        }
+        for &v in prefix {
+            partial.push(v, mat)?;
+        }
+        Ok(partial)
+    }
+
+    /// Adds `v` at the end of the path. Returns [`Error::InvalidPrefix`] with
+    /// the index it would have if it isn't a vertex of `mat`, if it is already
+    /// on the path, if it isn't adjacent to the last vertex, or if it doesn't
+    /// alternate between odd and even.
+    pub fn push(&mut self, v: usize, mat: &Hankel) -> Result<(), Error> {
+        let index = self.len;
+        let valid = mat.size == self.path.len()
+            && index < mat.size
+            && (1..=mat.size).contains(&v)
+            && !self.visited[v]
+            && v % 2 != index % 2
+            && self.last().is_none_or(|last| mat.get(last, v) != 0);
+        if !valid {
+            return Err(Error::InvalidPrefix { index });
+        }
+        self.add(v);
+        Ok(())
+    }
+
+    /// Removes the last vertex and returns it, unless it is the only one.
+    pub fn pop(&mut self) -> Option<usize> {
+        if self.len <= 1 {
+            return None;
+        }
+        self.len -= 1;
+        let v = core::mem::take(&mut self.path[self.len]);
+        self.visited[v] = false;
+        Some(v)
+    }
+
+    /// Returns the vertices of the path.
+    pub fn vertices(&self) -> &[usize] {
+        &self.path[..self.len]
+    }
+
+    /// Returns the last vertex, if any.
+    pub fn last(&self) -> Option<usize> {
+        self.vertices().last().copied()
+    }
+
+    /// Returns the number of vertices on the path, which is where the search
+    /// continues.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if there are no vertices on the path, which only happens for
+    /// an empty matrix.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks if `v` is on the path.
+    pub fn contains(&self, v: usize) -> bool {
+        self.visited.get(v).copied().unwrap_or(false)
+    }
+
+    /// Checks if every vertex is on the path.
+    pub fn is_complete(&self) -> bool {
+        self.len == self.path.len()
+    }
+
+    /// Checks that the visited vertices are the ones on the path, that no
+    /// vertex is on it twice, and that the entries after it are 0. This is
+    /// always true, unless there is a bug in the searches.
+    pub fn is_consistent(&self) -> bool {
+        let mut seen = vec![false; self.visited.len()];
+        self.len <= self.path.len()
+            && self.vertices().iter().all(|&v| {
+                (1..self.visited.len()).contains(&v) && !core::mem::replace(&mut seen[v], true)
+            })
+            && seen == self.visited
+            && self.path[self.len..].iter().all(|&v| v == 0)
+    }
+
+    /// Returns the vertices of the path, followed by a 0 for every vertex
+    /// that isn't on it.
+    pub fn into_vec(self) -> Vec<usize> {
+        self.path
+    }
+
+    /// Adds `v`, which is known to be valid.
+    fn add(&mut self, v: usize) {
+        self.path[self.len] = v;
+        self.visited[v] = true;
+        self.len += 1;
+    }
+}
+
+impl Hankel {
+    /// Tries to complete `partial` to a Hamiltonian cycle, with the same
+    /// search as [`Hankel::hamiltonian_cycle_iterative`].
+    ///
+    /// If a cycle is found, `partial` contains all of it. Otherwise it is left
+    /// as it was, also when the search was aborted. A path of a matrix of
+    /// another size is never completed.
+    pub fn complete_cycle(
+        &self,
+        partial: &mut PartialPath,
+        limits: &SearchLimits,
+        stats: &mut SearchStats,
+    ) -> SearchResult {
+        if partial.path.len() != self.size || partial.is_empty() {
+            return SearchResult::NotFound;
+        }
+        let start = partial.len;
+        let result = self.hamiltonian_cycle_iterative(&mut partial.path, start, limits, stats);
+        if result == SearchResult::Found {
+            partial.len = self.size;
+            partial.visited[1..].fill(true);
+        } else {
+            partial.path[start..].fill(0);
+        }
+        debug_assert!(partial.is_consistent());
+        result
+    }
+}
+
+impl Deref for Path {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl Deref for Cycle {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl From<Path> for Vec<usize> {
+    fn from(path: Path) -> Self {
+        path.0
+    }
+}
+
+impl From<Cycle> for Vec<usize> {
+    fn from(cycle: Cycle) -> Self {
+        cycle.0
+    }
+}
+
+impl IntoIterator for Path {
+    type Item = usize;
+    type IntoIter = alloc::vec::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl IntoIterator for Cycle {
+    type Item = usize;
+    type IntoIter = alloc::vec::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[test]
+fn checked_paths_and_cycles() {
+    let mat = Hankel::prime_sum_matrix(6, None);
+    let cycle = mat.is_hamiltonian().unwrap();
+    assert_eq!(Cycle::new(cycle.to_vec(), &mat), Ok(cycle.clone()));
+    assert_eq!(
+        Path::new(cycle.to_vec(), &mat).map(Vec::from),
+        Ok(cycle.to_vec())
+    );
+    let path = cycle.to_path(2).unwrap();
+    assert_eq!(path[0], 2);
+    assert_eq!(Path::new(path.to_vec(), &mat), Ok(path));
+    // 1 - 2 - 3 - 4 is a path, but doesn't visit 5 and 6.
+    assert_eq!(Path::new(vec![1, 2, 3, 4], &mat), Err(Error::InvalidPath));
+    assert_eq!(Cycle::new(vec![1, 2, 3, 4], &mat), Err(Error::InvalidCycle));
+    // 1 + 6 = 7, so a path from 1 to 6 is also a cycle.
+    let path = mat.hamiltonian_path_between(1, 6).unwrap();
+    assert!(Cycle::new(path.into_vec(), &mat).is_ok());
+    let open = Hankel::from_sequence(3, &[3, 5]);
+    assert!(Path::new(vec![1, 2, 3], &open).is_ok());
+    assert_eq!(Cycle::new(vec![1, 2, 3], &open), Err(Error::InvalidCycle));
+}
+
+#[test]
+fn partial_paths() {
+    let mat = Hankel::prime_sum_matrix(10, None);
+    let mut partial = PartialPath::new(&mat);
+    assert_eq!(partial.vertices(), [1]);
+    // 1 + 3 isn't prime, 1 is already on the path and 11 isn't a vertex.
+    assert_eq!(
+        partial.push(3, &mat),
+        Err(Error::InvalidPrefix { index: 1 })
+    );
+    assert_eq!(partial.push(4, &mat), Ok(()));
+    assert_eq!(
+        partial.push(1, &mat),
+        Err(Error::InvalidPrefix { index: 2 })
+    );
+    assert_eq!(
+        partial.push(11, &mat),
+        Err(Error::InvalidPrefix { index: 2 })
+    );
+    assert_eq!(partial.push(7, &mat), Ok(()));
+    assert_eq!(partial.push(6, &mat), Ok(()));
+    assert!(partial.is_consistent() && partial.contains(6) && !partial.contains(11));
+    assert_eq!(partial.pop(), Some(6));
+    assert_eq!(
+        PartialPath::from_prefix(&[1, 4, 7], &mat),
+        Ok(partial.clone())
+    );
+    // A prefix with a vertex twice is rejected, instead of being searched.
+    assert_eq!(
+        PartialPath::from_prefix(&[1, 2, 1], &mat),
+        Err(Error::InvalidPrefix { index: 2 })
+    );
+    assert_eq!(
+        PartialPath::from_prefix(&[], &mat),
+        Err(Error::InvalidPrefix { index: 0 })
+    );
+    let mut stats = SearchStats::default();
+    let limits = SearchLimits::default();
+    assert_eq!(
+        mat.complete_cycle(&mut partial, &limits, &mut stats),
+        SearchResult::Found
+    );
+    assert!(partial.is_complete() && partial.is_consistent());
+    assert_eq!(partial.vertices()[..3], [1, 4, 7]);
+    let cycle = partial.into_vec();
+    assert!(mat.valid_cycle(&cycle));
+    // An aborted search leaves the path as it was.
+    let mut partial = PartialPath::from_prefix(&[1, 10, 9], &mat).unwrap();
+    let limits = SearchLimits {
+        max_nodes: Some(3),
+        ..SearchLimits::default()
+    };
+    assert_eq!(
+        mat.complete_cycle(&mut partial, &limits, &mut stats),
+        SearchResult::Aborted
+    );
+    assert_eq!(partial.vertices(), [1, 10, 9]);
+    assert!(partial.is_consistent());
+    let other = Hankel::prime_sum_matrix(12, None);
+    assert_eq!(
+        other.complete_cycle(&mut partial, &limits, &mut stats),
+        SearchResult::NotFound
+    );
+}
@@ -0,0 +1,160 @@
+//! Bindings for Python, enabled with the `pyo3` feature.
+//!
+//! Build and install the module with `maturin develop`, which
+//! uses the settings in `pyproject.toml`. Then:
+//!
+//! ```text
+//! >>> import primes
+//! >>> primes.Hankel(10).find_cycle()
+//! [1, 10, 9, 8, 5, 6, 7, 4, 3, 2]
+//! ```
+
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::{PyIndexError, PyTimeoutError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::{Error, HamiltonianCycle, Hankel, SearchLimits, SearchResult, SearchStats};
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        PyValueError::new_err(e.to_string())
+    }
+}
+
+/// The adjacency matrix of a prime sum graph, see [`Hankel`].
+#[pyclass(name = "Hankel", frozen)]
+struct PyHankel(Hankel);
+
+#[pymethods]
+impl PyHankel {
+    /// Generates the prime sum graph of size `n`. If given, `primes`
+    /// should contain all the primes up to 2n - 1.
+    #[new]
+    #[pyo3(signature = (n, primes=None))]
+    fn new(n: usize, primes: Option<Vec<usize>>) -> PyResult<Self> {
+        Ok(Self(Hankel::try_prime_sum_matrix(n, primes.as_deref())?))
+    }
+
+    /// Generates the graph of size `n` where two vertices are adjacent
+    /// if their sum is in the sorted `sequence`.
+    #[staticmethod]
+    fn from_sequence(n: usize, sequence: Vec<usize>) -> Self {
+        Self(Hankel::from_sequence(n, &sequence))
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.0.size
+    }
+
+    /// Returns the entry at `row` and `col`, which are 1-indexed.
+    fn get(&self, row: usize, col: usize) -> PyResult<u8> {
+        self.0
+            .try_get(row, col)
+            .ok_or_else(|| PyIndexError::new_err((row, col)))
+    }
+
+    fn vertex_degrees(&self) -> Vec<usize> {
+        self.0.vertex_degrees()
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        self.0.edges().collect()
+    }
+
+    fn valid_path(&self, path: Vec<usize>) -> bool {
+        self.0.valid_path(&path)
+    }
+
+    fn valid_cycle(&self, cycle: Vec<usize>) -> bool {
+        self.0.valid_cycle(&cycle)
+    }
+
+    /// Searches for a Hamiltonian cycle starting at 1, and returns `None` if
+    /// there is none. Raises `TimeoutError` if `max_nodes` or `time_limit`
+    /// (in seconds) is reached first.
+    ///
+    /// Other Python threads can run during the search.
+    #[pyo3(signature = (max_nodes=None, time_limit=None))]
+    fn find_cycle(
+        &self,
+        py: Python<'_>,
+        max_nodes: Option<u64>,
+        time_limit: Option<f64>,
+    ) -> PyResult<Option<Vec<usize>>> {
+        if self.0.size == 0 {
+            return Ok(None);
+        }
+        let deadline = time_limit
+            .map(|t| {
+                Duration::try_from_secs_f64(t).map_err(|e| PyValueError::new_err(e.to_string()))
+            })
+            .transpose()?
+            .map(|t| Instant::now() + t);
+        let limits = SearchLimits {
+            deadline,
+            max_nodes,
+            progress: None,
+        };
+        py.detach(|| {
+            let mut path = vec![0; self.0.size];
+            path[0] = 1;
+            let mut stats = SearchStats::default();
+            match self
+                .0
+                .hamiltonian_cycle_iterative(&mut path, 1, &limits, &mut stats)
+            {
+                SearchResult::Found => Ok(Some(path)),
+                SearchResult::NotFound => Ok(None),
+                SearchResult::Aborted => Err(PyTimeoutError::new_err(stats.nodes)),
+            }
+        })
+    }
+
+    /// Counts the Hamiltonian cycles, which is only feasible for small sizes.
+    fn count_hamiltonian_cycles(&self, py: Python<'_>) -> u64 {
+        py.detach(|| self.0.count_hamiltonian_cycles())
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.size
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Hankel({})", self.0.size)
+    }
+}
+
+/// Finds primes p1 < p2 <= 2n, see [`crate::find_prime_quadruplet`].
+#[pyfunction]
+#[pyo3(signature = (half_size, primes=None))]
+fn find_prime_quadruplet(
+    py: Python<'_>,
+    half_size: usize,
+    primes: Option<Vec<usize>>,
+) -> PyResult<Option<(usize, usize)>> {
+    Ok(py.detach(|| crate::try_find_prime_quadruplet(half_size, primes.as_deref()))?)
+}
+
+/// Constructs the Hamiltonian cycle of size 2n from the primes
+/// p1 and p2, see [`HamiltonianCycle`].
+#[pyfunction]
+fn hamiltonian_cycle(prime1: usize, prime2: usize, half_size: usize) -> PyResult<Vec<usize>> {
+    Ok(HamiltonianCycle::try_new(prime1, prime2, half_size)?.collect())
+}
+
+/// Generates the primes up to and including `n`.
+#[pyfunction]
+fn gen_primes_upto_n(py: Python<'_>, n: usize) -> Vec<usize> {
+    py.detach(|| crate::gen_primes_upto_n(n))
+}
+
+#[pymodule(name = "primes")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyHankel>()?;
+    m.add_function(wrap_pyfunction!(find_prime_quadruplet, m)?)?;
+    m.add_function(wrap_pyfunction!(hamiltonian_cycle, m)?)?;
+    m.add_function(wrap_pyfunction!(gen_primes_upto_n, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Prime quadruplets as values, which can be turned into the whole
+//! Hamiltonian cycle they construct.
+
+use alloc::vec::Vec;
+
+use crate::{is_prime_by_trial_division, Cycle, Error, HamiltonianCycle, Hankel};
+
+/// A prime quadruplet for the size 2n: the primes p1 < p2, where p1 can be 1,
+/// such that p1 + 2n and p2 + 2n are prime and gcd((p2 - p1) / 2, n) = 1,
+/// like the ones returned by [`crate::find_prime_quadruplet`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Quadruplet {
+    /// The smaller prime, or 1.
+    pub p1: usize,
+    /// The larger prime, at most 2n.
+    pub p2: usize,
+}
+
+impl Quadruplet {
+    /// The primes are not checked, see [`Quadruplet::try_into_cycle`].
+    pub fn new(p1: usize, p2: usize) -> Self {
+        Self { p1, p2 }
+    }
+
+    /// Returns the Hamiltonian cycle of the size 2 * `half_size` that is
+    /// constructed from the quadruplet, checked against the graph.
+    ///
+    /// # Panics
+    ///
+    /// If the primes don't satisfy the conditions for `half_size`. See
+    /// [`Quadruplet::try_into_cycle`] for a version that doesn't panic.
+    pub fn into_cycle(self, half_size: usize) -> Cycle {
+        match self.try_into_cycle(half_size) {
+            Ok(cycle) => cycle,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Same as [`Quadruplet::into_cycle`], but returns an error if the primes
+    /// don't satisfy the conditions, or [`Error::InvalidCycle`] if the
+    /// construction doesn't give a Hamiltonian cycle.
+    ///
+    /// Besides the edges along the way, the sum of the last and the first
+    /// vertex is checked to be prime, since that edge closes the cycle.
+    pub fn try_into_cycle(self, half_size: usize) -> Result<Cycle, Error> {
+        let cycle = HamiltonianCycle::try_new(self.p1, self.p2, half_size)?.collect::<Vec<_>>();
+        let closing = match (cycle.first(), cycle.last()) {
+            (Some(first), Some(last)) => first + last,
+            _ => return Err(Error::InvalidCycle),
+        };
+        if !is_prime_by_trial_division(closing) {
+            return Err(Error::InvalidCycle);
+        }
+        Cycle::new(cycle, &Hankel::prime_sum_matrix(2 * half_size, None))
+    }
+}
+
+impl From<(usize, usize)> for Quadruplet {
+    fn from((p1, p2): (usize, usize)) -> Self {
+        Self::new(p1, p2)
+    }
+}
+
+#[test]
+fn cycles_from_quadruplets() {
+    let cycle = Quadruplet::new(3, 17).into_cycle(10);
+    assert_eq!(cycle.len(), 20);
+    assert!(Hankel::prime_sum_matrix(20, None).valid_cycle(&cycle));
+    for half_size in [2, 7, 50, 501] {
+        let quadruplet = crate::find_prime_quadruplet(half_size, None).unwrap();
+        let cycle = Quadruplet::from(quadruplet).into_cycle(half_size);
+        assert_eq!(cycle.len(), 2 * half_size);
+        assert_eq!(
+            cycle.to_vec(),
+            HamiltonianCycle::new(quadruplet.0, quadruplet.1, half_size).collect::<Vec<_>>()
+        );
+    }
+    // 5 + 20 = 25 isn't prime.
+    assert_eq!(
+        Quadruplet::new(5, 17).try_into_cycle(10),
+        Err(Error::InvalidQuadruplet {
+            prime1: 5,
+            prime2: 17,
+            half_size: 10
+        })
+    );
+}
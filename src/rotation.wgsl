@@ -0,0 +1,139 @@
+// The rotation-extension search of `Hankel::rotation_extension`, where every
+// invocation runs one attempt. See `src/gpu.rs`.
+
+struct Params {
+    size: u32,
+    // 0 if the entries depend on the sum, 1 for the difference.
+    kind: u32,
+    attempts: u32,
+    first_seed: u32,
+    max_steps: u32,
+}
+
+struct State {
+    // The length of the path, where 0 means that the attempt hasn't started.
+    len: u32,
+    rng: u32,
+    steps: u32,
+}
+
+struct Progress {
+    // The smallest attempt that found a cycle.
+    found: atomic<u32>,
+    // The number of attempts that took all their steps.
+    finished: atomic<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> diagonals: array<u32>;
+// The path of every attempt, with `size` entries each.
+@group(0) @binding(2) var<storage, read_write> paths: array<u32>;
+// One more than the position of every vertex in the path of every attempt,
+// or 0 if it isn't in the path, with `size + 1` entries each, since the
+// vertices start at 1.
+@group(0) @binding(3) var<storage, read_write> positions: array<u32>;
+@group(0) @binding(4) var<storage, read_write> states: array<State>;
+@group(0) @binding(5) var<storage, read_write> progress: Progress;
+
+// The number of loop iterations after which an invocation stops taking
+// steps. Some drivers, like llvmpipe, stop the loops of an invocation after
+// 65535 iterations in total, so the search continues in the next dispatch.
+const WORK: u32 = 16384u;
+
+fn adjacent(v: u32, w: u32) -> bool {
+    var d: u32;
+    if params.kind == 0u {
+        d = v + w - 2u;
+    } else {
+        d = max(v, w) - min(v, w);
+    }
+    return diagonals[d] != 0u;
+}
+
+// A xorshift generator, whose state is never 0.
+fn next_random(state: ptr<function, u32>) -> u32 {
+    var x = *state;
+    x ^= x << 13u;
+    x ^= x >> 17u;
+    x ^= x << 5u;
+    *state = x;
+    return x;
+}
+
+// Mixes the seed, so that close seeds give different numbers.
+fn mix_seed(seed: u32) -> u32 {
+    var x = seed;
+    x ^= x >> 16u;
+    x *= 0x7feb352du;
+    x ^= x >> 15u;
+    x *= 0x846ca68bu;
+    x ^= x >> 16u;
+    return max(x, 1u);
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let attempt = id.x;
+    if attempt >= params.attempts || atomicLoad(&progress.found) <= attempt {
+        return;
+    }
+    var state = states[attempt];
+    if state.len != 0u && state.steps == params.max_steps {
+        return;
+    }
+    let n = params.size;
+    let path = attempt * n;
+    let position = attempt * (n + 1u);
+    if state.len == 0u {
+        state.rng = mix_seed(params.first_seed + attempt);
+        let start = 1u + next_random(&state.rng) % n;
+        paths[path] = start;
+        positions[position + start] = 1u;
+        state.len = 1u;
+    }
+    var work = 0u;
+    while work < WORK {
+        if state.steps == params.max_steps {
+            atomicAdd(&progress.finished, 1u);
+            break;
+        }
+        let end = paths[path + state.len - 1u];
+        if state.len == n && end != paths[path] && adjacent(end, paths[path]) {
+            atomicMin(&progress.found, attempt);
+            break;
+        }
+        state.steps += 1u;
+        // Every vertex has a neighbor, which can be itself.
+        var next = 1u + next_random(&state.rng) % n;
+        while !adjacent(end, next) {
+            next = 1u + next_random(&state.rng) % n;
+            work += 1u;
+        }
+        work += 1u;
+        if next == end {
+            continue;
+        }
+        let i = positions[position + next];
+        if i == 0u {
+            paths[path + state.len] = next;
+            state.len += 1u;
+            positions[position + next] = state.len;
+        } else {
+            // Reverse the part of the path after `next`, which is at i - 1.
+            var low = i;
+            var high = state.len - 1u;
+            while low < high {
+                let a = paths[path + low];
+                let b = paths[path + high];
+                paths[path + low] = b;
+                paths[path + high] = a;
+                positions[position + b] = low + 1u;
+                positions[position + a] = high + 1u;
+                low += 1u;
+                high -= 1u;
+                work += 1u;
+            }
+        }
+    }
+    states[attempt] = state;
+}
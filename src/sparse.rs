@@ -0,0 +1,313 @@
+//! Matrices over sequences that are much sparser than the primes.
+//!
+//! A [`Hankel`] matrix stores a byte for every diagonal, and a degree for
+//! every vertex. For a sequence with k terms up to 2n, a [`SparseHankel`]
+//! only stores the k diagonals that are 1, or the runs of them if they come
+//! in long runs, so a graph with millions of vertices takes a few bytes per
+//! term instead of 10 bytes per vertex. The storage is chosen automatically,
+//! whichever is smallest, and an entry is found in O(log k).
+//!
+//! The validators and the searches of [`crate::SumGraph`] work on it as well.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Hankel, SumOrDiff};
+
+/// A matrix like [`Hankel`], which only stores the diagonals that are 1.
+/// The vertices are 1-indexed, like in [`Hankel::get`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseHankel {
+    diagonals: Diagonals,
+    size: usize,
+    kind: SumOrDiff,
+}
+
+/// How the diagonals of a [`SparseHankel`] are stored, see
+/// [`SparseHankel::storage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagonalStorage {
+    /// A byte for every diagonal, like [`Hankel`].
+    Dense,
+    /// The sorted indices of the diagonals that are 1.
+    Sorted,
+    /// The first and last index of every run of diagonals that are 1.
+    RunLength,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Diagonals {
+    Dense(Vec<u8>),
+    Sorted(Vec<usize>),
+    /// The runs are disjoint, in increasing order, and not next to each other.
+    Runs(Vec<(usize, usize)>),
+}
+
+impl SparseHankel {
+    /// Same as [`Hankel::from_sequence`], but only the terms of the sorted
+    /// `sequence` up to 2n are looked at, so it takes O(k) time for k terms.
+    ///
+    /// # Panics
+    ///
+    /// If 2n overflows.
+    pub fn from_sequence(n: usize, sequence: &[usize]) -> Self {
+        let len = n.checked_mul(2).expect("2n overflows").saturating_sub(1);
+        let end = sequence.partition_point(|&s| s < len + 2);
+        let start = sequence.partition_point(|&s| s < 2);
+        let positions = sequence[start..end].iter().map(|&s| s - 2);
+        Self::from_positions(positions, len, n, SumOrDiff::Sum)
+    }
+
+    /// Same as [`Hankel::from_differences`], but only the terms of the sorted
+    /// `sequence` below n are looked at.
+    pub fn from_differences(n: usize, sequence: &[usize]) -> Self {
+        let end = sequence.partition_point(|&s| s < n);
+        Self::from_positions(sequence[..end].iter().copied(), n, n, SumOrDiff::Diff)
+    }
+
+    /// Stores the diagonals at the increasing `positions` in the smallest of
+    /// the three ways. Repeated positions are skipped.
+    fn from_positions(
+        positions: impl Iterator<Item = usize>,
+        len: usize,
+        size: usize,
+        kind: SumOrDiff,
+    ) -> Self {
+        let mut sorted: Vec<usize> = Vec::new();
+        for d in positions {
+            if sorted.last().is_none_or(|&last| last < d) {
+                sorted.push(d);
+            }
+        }
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for &d in &sorted {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == d => *last = d,
+                _ => runs.push((d, d)),
+            }
+        }
+        let word = core::mem::size_of::<usize>();
+        let dense_bytes = len;
+        let sorted_bytes = sorted.len() * word;
+        let run_bytes = runs.len() * 2 * word;
+        let diagonals = if dense_bytes < sorted_bytes.min(run_bytes) {
+            let mut dense = vec![0; len];
+            for d in sorted {
+                dense[d] = 1;
+            }
+            Diagonals::Dense(dense)
+        } else if run_bytes < sorted_bytes {
+            runs.shrink_to_fit();
+            Diagonals::Runs(runs)
+        } else {
+            Diagonals::Sorted(sorted)
+        };
+        Self {
+            diagonals,
+            size,
+            kind,
+        }
+    }
+
+    /// Returns the number of vertices.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the entries depend on the sum or the difference, see
+    /// [`SumOrDiff`].
+    pub fn kind(&self) -> SumOrDiff {
+        self.kind
+    }
+
+    /// Returns how the diagonals are stored.
+    pub fn storage(&self) -> DiagonalStorage {
+        match self.diagonals {
+            Diagonals::Dense(_) => DiagonalStorage::Dense,
+            Diagonals::Sorted(_) => DiagonalStorage::Sorted,
+            Diagonals::Runs(_) => DiagonalStorage::RunLength,
+        }
+    }
+
+    /// Returns the number of bytes of the diagonals on the heap.
+    pub fn heap_size(&self) -> usize {
+        match &self.diagonals {
+            Diagonals::Dense(dense) => dense.capacity(),
+            Diagonals::Sorted(sorted) => core::mem::size_of_val(sorted.as_slice()),
+            Diagonals::Runs(runs) => core::mem::size_of_val(runs.as_slice()),
+        }
+    }
+
+    /// Get the entry at `row` and `col`, which are 1-indexed, in O(log k)
+    /// for k stored diagonals or runs.
+    ///
+    /// # Panics
+    ///
+    /// If `row` or `col` is not between 1 and the size.
+    pub fn get(&self, row: usize, col: usize) -> u8 {
+        match self.try_get(row, col) {
+            Some(entry) => entry,
+            None => panic!("the vertices aren't between 1 and {}", self.size),
+        }
+    }
+
+    /// Same as [`SparseHankel::get`], but returns `None` if `row` or `col`
+    /// is not between 1 and the size.
+    pub fn try_get(&self, row: usize, col: usize) -> Option<u8> {
+        if !(1..=self.size).contains(&row) || !(1..=self.size).contains(&col) {
+            return None;
+        }
+        let d = match self.kind {
+            SumOrDiff::Sum => row + col - 2,
+            SumOrDiff::Diff => row.abs_diff(col),
+        };
+        let entry = match &self.diagonals {
+            Diagonals::Dense(dense) => dense[d] != 0,
+            Diagonals::Sorted(sorted) => sorted.binary_search(&d).is_ok(),
+            Diagonals::Runs(runs) => {
+                let i = runs.partition_point(|&(first, _)| first <= d);
+                i > 0 && runs[i - 1].1 >= d
+            }
+        };
+        Some(u8::from(entry))
+    }
+
+    /// Returns the neighbors of `v` other than itself, in increasing order.
+    ///
+    /// # Panics
+    ///
+    /// If `v` is not between 1 and the size.
+    pub fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        assert!(
+            (1..=self.size).contains(&v),
+            "the vertex isn't in the graph"
+        );
+        let n = self.size;
+        let kind = self.kind;
+        let (below, above) = match kind {
+            SumOrDiff::Sum => (0..0, v - 1..v - 1 + n),
+            SumOrDiff::Diff => (1..v, 1..n - v + 1),
+        };
+        self.positions(below)
+            .rev()
+            .map(move |d| v - d)
+            .chain(self.positions(above).map(move |d| match kind {
+                SumOrDiff::Sum => d + 2 - v,
+                SumOrDiff::Diff => v + d,
+            }))
+            .filter(move |&w| w != v)
+    }
+
+    /// Returns the degree of `v`, where a loop counts once, like in
+    /// [`Hankel::degrees`].
+    ///
+    /// # Panics
+    ///
+    /// If `v` is not between 1 and the size.
+    pub fn degree(&self, v: usize) -> usize {
+        self.neighbors(v).count() + usize::from(self.get(v, v))
+    }
+
+    /// Returns the same matrix with a byte for every diagonal, to run the
+    /// algorithms of [`Hankel`] on it.
+    pub fn to_hankel(&self) -> Hankel {
+        let len = match self.kind {
+            SumOrDiff::Sum => (2 * self.size).saturating_sub(1),
+            SumOrDiff::Diff => self.size,
+        };
+        let mut dense = vec![0; len];
+        for d in self.positions(0..len) {
+            dense[d] = 1;
+        }
+        Hankel::with_diagonals(dense, self.size, self.kind)
+    }
+
+    /// Returns the indices of the diagonals in `range` that are 1, in
+    /// increasing order.
+    fn positions(&self, range: Range<usize>) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        let Range { start, end } = range;
+        let (dense, sorted, runs): (&[u8], &[usize], &[(usize, usize)]) = match &self.diagonals {
+            Diagonals::Dense(dense) => (&dense[start..end], &[], &[]),
+            Diagonals::Sorted(sorted) => {
+                let first = sorted.partition_point(|&d| d < start);
+                let last = sorted.partition_point(|&d| d < end);
+                (&[], &sorted[first..last], &[])
+            }
+            Diagonals::Runs(runs) => {
+                let first = runs.partition_point(|&(_, last)| last < start);
+                let last = runs.partition_point(|&(first, _)| first < end);
+                (&[], &[], &runs[first..last.max(first)])
+            }
+        };
+        dense
+            .iter()
+            .enumerate()
+            .filter(|(_, &e)| e != 0)
+            .map(move |(i, _)| start + i)
+            .chain(sorted.iter().copied())
+            .chain(
+                runs.iter()
+                    .flat_map(move |&(first, last)| first.max(start)..=last.min(end - 1)),
+            )
+    }
+}
+
+impl From<&Hankel> for SparseHankel {
+    fn from(mat: &Hankel) -> Self {
+        let diagonals = mat.diagonals();
+        let positions = (0..diagonals.len()).filter(|&d| diagonals[d] != 0);
+        Self::from_positions(positions, diagonals.len(), mat.size, mat.kind())
+    }
+}
+
+#[test]
+fn sparse_matrices() {
+    use crate::SumGraph;
+
+    // The powers of 2, the squares and a few long runs.
+    let powers = (1..40).map(|k| 1 << k).collect::<Vec<_>>();
+    let squares = (1..30).map(|k| k * k).collect::<Vec<_>>();
+    let runs = (10..20).chain(40..60).collect::<Vec<_>>();
+    let primes = crate::sieve_primes_upto_n(100);
+    for (sequence, storage) in [
+        (&powers, DiagonalStorage::Sorted),
+        (&squares, DiagonalStorage::Sorted),
+        (&runs, DiagonalStorage::RunLength),
+        (&primes, DiagonalStorage::Dense),
+    ] {
+        for n in [0, 1, 7, 40] {
+            let sparse = SparseHankel::from_sequence(n, sequence);
+            let dense = Hankel::from_sequence(n, sequence);
+            if n == 40 {
+                assert_eq!(sparse.storage(), storage);
+            }
+            assert_eq!(SparseHankel::from(&dense), sparse);
+            assert_eq!(sparse.to_hankel().diagonals(), dense.diagonals());
+            for v in 1..=n {
+                for w in 1..=n {
+                    assert_eq!(sparse.get(v, w), dense.get(v, w));
+                }
+                let neighbors = (1..=n).filter(|&w| w != v && dense.get(v, w) != 0);
+                assert!(sparse.neighbors(v).eq(neighbors));
+                assert_eq!(sparse.degree(v), dense.degrees()[v - 1]);
+            }
+            let sparse = SparseHankel::from_differences(n, sequence);
+            let dense = Hankel::from_differences(n, sequence);
+            for v in 1..=n {
+                let neighbors = (1..=n).filter(|&w| w != v && dense.get(v, w) != 0);
+                assert!(sparse.neighbors(v).eq(neighbors));
+                assert_eq!(sparse.degree(v), dense.degrees()[v - 1]);
+            }
+        }
+    }
+    // A million vertices over the powers of 2 take a few hundred bytes.
+    let sparse = SparseHankel::from_sequence(1_000_000, &powers);
+    assert!(sparse.heap_size() < 200);
+    assert!(sparse.neighbors(1).eq((2..20).map(|k| (1 << k) - 1)));
+    assert_eq!(sparse.try_get(0, 1), None);
+    let sparse = SparseHankel::from_sequence(6, &primes);
+    assert!(sparse.valid_cycle(&[1, 4, 3, 2, 5, 6]));
+    assert!(!sparse.valid_cycle(&[1, 4, 3, 2, 6, 5]));
+    assert!(sparse.valid_path(&[1, 4, 3, 2, 5, 6]));
+}
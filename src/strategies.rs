@@ -0,0 +1,54 @@
+//! [`proptest`] strategies, enabled with the `proptest` feature.
+
+use alloc::vec::Vec;
+
+use proptest::collection::{btree_set, vec};
+use proptest::prelude::*;
+
+use crate::{Hankel, SumOrDiff};
+
+/// Generates Hankel matrices of size at most `max_size`, with random diagonals.
+pub fn arb_hankel(max_size: usize) -> impl Strategy<Value = Hankel> {
+    (0..=max_size).prop_flat_map(|size| {
+        vec(0..=1u8, (2 * size).saturating_sub(1))
+            .prop_map(move |diagonals| Hankel::with_diagonals(diagonals, size, SumOrDiff::Sum))
+    })
+}
+
+/// Generates sorted sequences of distinct sums from 2 up to `max_value`,
+/// like the ones used by [`Hankel::from_sequence`].
+pub fn arb_sequence(max_value: usize) -> impl Strategy<Value = Vec<usize>> {
+    btree_set(2..=max_value.max(2), 0..=max_value / 2).prop_map(|sums| sums.into_iter().collect())
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn valid_cycle_is_valid_path(
+        mat in arb_hankel(12),
+        path in vec(0..14usize, 0..14),
+    ) {
+        if mat.valid_cycle(&path) {
+            prop_assert!(mat.valid_path(&path));
+        }
+    }
+
+    #[test]
+    fn degrees_sum_to_twice_the_edges(
+        sequence in arb_sequence(60),
+        size in 0..30usize,
+    ) {
+        let mat = Hankel::from_sequence(size, &sequence);
+        // A loop at vertex i, when 2i is in the sequence, adds 1 to the degree of i.
+        let loops = (1..=size).filter(|&i| mat.get(i, i) != 0).count();
+        let degrees = mat.vertex_degrees().iter().sum::<usize>();
+        prop_assert_eq!(degrees, 2 * mat.edge_count() + loops);
+    }
+
+    #[test]
+    fn found_cycles_are_valid(mat in arb_hankel(10)) {
+        if let Some(cycle) = mat.is_hamiltonian() {
+            prop_assert!(mat.valid_cycle(&cycle));
+        }
+    }
+}
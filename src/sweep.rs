@@ -0,0 +1,142 @@
+//! Checks of the Hamiltonian paths for a range of odd sizes.
+//!
+//! Removing n + 1 from a Hamiltonian cycle of size n + 1 leaves a path of
+//! size n, so the odd sizes follow from the even ones. Here every path is
+//! also checked against the graph of its own size.
+
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    cycle_to_path, find_prime_quadruplet, sieve_primes_upto_n, HamiltonianCycle, Hankel,
+    SearchLimits, SearchResult, SearchStats,
+};
+
+/// How [`verify_path_range`] finds the paths.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PathOptions<'a> {
+    /// The primes up to 2n + 2 for the largest size n, or `None` to sieve them.
+    pub primes: Option<&'a [usize]>,
+    /// Only search for the paths, instead of constructing them from prime
+    /// quadruplets first.
+    pub search_only: bool,
+    /// The limits of every search.
+    pub limits: SearchLimits<'a>,
+}
+
+/// The odd sizes for which [`verify_path_range`] found a Hamiltonian path,
+/// and those for which it didn't.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PathReport {
+    /// The sizes whose path was constructed from a prime quadruplet.
+    pub constructed: Vec<usize>,
+    /// The sizes whose path was found by a search.
+    pub searched: Vec<usize>,
+    /// The sizes where the search finished without finding a cycle of the
+    /// next size, so the path isn't verified.
+    pub failed: Vec<usize>,
+    /// The sizes where the search reached one of the limits.
+    pub aborted: Vec<usize>,
+}
+
+impl PathReport {
+    /// Checks if a path was verified for every size.
+    pub fn all_verified(&self) -> bool {
+        self.failed.is_empty() && self.aborted.is_empty()
+    }
+}
+
+/// Checks that the prime sum graph has a Hamiltonian path for every odd
+/// size n in `sizes`. The even sizes are skipped.
+///
+/// The path comes from a cycle of size n + 1, which is constructed from a
+/// prime quadruplet, or found by a search if there is none or
+/// [`PathOptions::search_only`] is set.
+///
+/// # Panics
+///
+/// If [`PathOptions::primes`] doesn't contain all the primes it should.
+pub fn verify_path_range(sizes: Range<usize>, options: &PathOptions) -> PathReport {
+    let primes = match options.primes {
+        Some(primes) => Cow::Borrowed(primes),
+        None => Cow::Owned(sieve_primes_upto_n(sizes.end.saturating_mul(2))),
+    };
+    let mut report = PathReport::default();
+    for n in sizes.filter(|n| n % 2 == 1) {
+        let constructed = (!options.search_only && n >= 3)
+            .then(|| find_prime_quadruplet(n.div_ceil(2), Some(&primes)))
+            .flatten()
+            .map(|(p1, p2)| HamiltonianCycle::new(p1, p2, n.div_ceil(2)).collect::<Vec<_>>());
+        let list = if let Some(cycle) = constructed {
+            let path = cycle_to_path(&cycle, n + 1);
+            if path.is_some_and(|path| is_path(n, &path, &primes)) {
+                &mut report.constructed
+            } else {
+                &mut report.failed
+            }
+        } else {
+            let mut cycle = vec![0; n + 1];
+            cycle[0] = 1;
+            let result = Hankel::prime_sum_matrix(n + 1, Some(&primes))
+                .hamiltonian_cycle_with_limits(
+                    &mut cycle,
+                    1,
+                    &options.limits,
+                    &mut SearchStats::default(),
+                );
+            match result {
+                SearchResult::Found => {
+                    let path = cycle_to_path(&cycle, n + 1);
+                    if path.is_some_and(|path| is_path(n, &path, &primes)) {
+                        &mut report.searched
+                    } else {
+                        &mut report.failed
+                    }
+                }
+                SearchResult::NotFound => &mut report.failed,
+                SearchResult::Aborted => &mut report.aborted,
+            }
+        };
+        list.push(n);
+    }
+    report
+}
+
+/// Checks if `path` is a Hamiltonian path of the prime sum graph of size `n`.
+fn is_path(n: usize, path: &[usize], primes: &[usize]) -> bool {
+    path.len() == n && Hankel::prime_sum_matrix(n, Some(primes)).valid_path(path)
+}
+
+#[test]
+fn odd_paths() {
+    let report = verify_path_range(0..80, &PathOptions::default());
+    assert!(report.all_verified());
+    assert_eq!(report.constructed.len() + report.searched.len(), 40);
+    // There is no quadruplet for 1, so its path is searched.
+    assert_eq!(report.searched[0], 1);
+    let primes = sieve_primes_upto_n(100);
+    let options = PathOptions {
+        primes: Some(&primes),
+        search_only: true,
+        ..PathOptions::default()
+    };
+    let report = verify_path_range(20..41, &options);
+    assert_eq!(report.searched, (21..41).step_by(2).collect::<Vec<_>>());
+    let options = PathOptions {
+        search_only: true,
+        limits: SearchLimits {
+            max_nodes: Some(5),
+            ..SearchLimits::default()
+        },
+        ..PathOptions::default()
+    };
+    let report = verify_path_range(31..34, &options);
+    assert_eq!(report.aborted, vec![31, 33]);
+    assert!(!report.all_verified());
+    assert_eq!(
+        verify_path_range(4..5, &PathOptions::default()),
+        PathReport::default()
+    );
+}
@@ -0,0 +1,363 @@
+//! The automorphisms of the graphs, found by individualization and
+//! refinement of the colorings of the vertices, and the Hamiltonian
+//! cycles that are symmetric under the reflection i ↦ n + 1 - i.
+//!
+//! Every search for automorphisms compares two copies of the graph, so
+//! the colors are stored in one slice: the first `size` for the first
+//! copy, and the next `size` for the second copy. The vertices are
+//! 0-based in those searches.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Cycle, Hankel, SumOrDiff};
+
+impl Hankel {
+    /// Returns automorphisms which generate the automorphism group of the graph.
+    ///
+    /// An automorphism is given by the images of the vertices 1 to n, so the
+    /// first entry is the image of 1. If the only automorphism is the identity,
+    /// the result is empty.
+    ///
+    /// All the vertices are compared, so this is only feasible for small sizes.
+    pub fn automorphisms(&self) -> Vec<Vec<usize>> {
+        let n = self.size;
+        let mut generators = Vec::new();
+        let mut fixed = Vec::new();
+        loop {
+            // Find the first vertex that isn't fixed by the pointwise
+            // stabilizer of `fixed`, which is a vertex in a non-singleton cell.
+            let mut colors = individualized(n, &fixed, None);
+            if !self.refine(&mut colors) {
+                break;
+            }
+            let mut counts = vec![0; 2 * n];
+            for &c in &colors[..n] {
+                counts[c] += 1;
+            }
+            let Some(base) = (0..n).find(|&v| counts[colors[v]] > 1) else {
+                break;
+            };
+            // Find an automorphism mapping `base` to every vertex in its cell,
+            // unless the ones found so far already do.
+            let mut orbits = Orbits::new(n);
+            for target in (0..n).filter(|&v| v != base && colors[v] == colors[base]) {
+                if orbits.find(target) == orbits.find(base) {
+                    continue;
+                }
+                let start = individualized(n, &fixed, Some((base, target)));
+                if let Some(mapping) = self.extend_isomorphism(start) {
+                    orbits.add(&mapping);
+                    generators.push(mapping.iter().map(|v| v + 1).collect());
+                }
+            }
+            fixed.push(base);
+        }
+        generators
+    }
+
+    /// Returns the orbits of the automorphism group, as the smallest vertex
+    /// in the orbit of every vertex from 1 to n.
+    ///
+    /// See [`Hankel::automorphisms`].
+    pub fn automorphism_orbits(&self) -> Vec<usize> {
+        let mut orbits = Orbits::new(self.size);
+        for mapping in self.automorphisms() {
+            let mapping = mapping.iter().map(|v| v - 1).collect::<Vec<_>>();
+            orbits.add(&mapping);
+        }
+        (0..self.size).map(|v| orbits.find(v) + 1).collect()
+    }
+
+    /// Checks if the reflection i ↦ n + 1 - i is an automorphism, which is
+    /// always the case for differences, and for sums if the sequence is
+    /// symmetric around n + 1.
+    pub fn reflection_is_automorphism(&self) -> bool {
+        match self.kind {
+            SumOrDiff::Sum => self.diagonals.iter().eq(self.diagonals.iter().rev()),
+            SumOrDiff::Diff => true,
+        }
+    }
+
+    /// Returns a Hamiltonian cycle which the reflection i ↦ n + 1 - i maps to
+    /// itself, or `None` if there is none or the reflection isn't an automorphism.
+    ///
+    /// The reflection either turns such a cycle halfway around, or reflects it
+    /// in an axis through two edges (or a vertex and an edge, if n is odd).
+    /// Either way the cycle is determined by a path with one vertex of every
+    /// pair i and n + 1 - i, so only those paths are searched.
+    pub fn symmetric_cycle(&self) -> Option<Cycle> {
+        let n = self.size;
+        if n == 0 || !self.reflection_is_automorphism() {
+            return None;
+        }
+        let mirror = |v: usize| n + 1 - v;
+        let even = n.is_multiple_of(2);
+        // The vertex in the middle is the only one on the axis.
+        let starts = if !even {
+            n / 2 + 1..=n / 2 + 1
+        } else {
+            1..=n / 2
+        };
+        let mut used = vec![false; n + 1];
+        for start in starts {
+            // Only a cycle through 1 that is turned halfway around can
+            // start anywhere, so it is only tried from 1.
+            let turned = even && start == 1;
+            if !turned && even && self.get(start, mirror(start)) == 0 {
+                continue;
+            }
+            let mut path = vec![start];
+            (used[start], used[mirror(start)]) = (true, true);
+            if let Some(turned) = self.extend_symmetric(&mut path, &mut used, turned) {
+                let mut image = path.iter().map(|&v| mirror(v)).collect::<Vec<_>>();
+                if !turned {
+                    image.reverse();
+                    // The vertex in the middle is its own image.
+                    if !even {
+                        image.pop();
+                    }
+                }
+                path.extend(image);
+                return Some(Cycle::found(path, self));
+            }
+            (used[start], used[mirror(start)]) = (false, false);
+        }
+        None
+    }
+
+    /// Extends `path` until it has a vertex of every pair i and n + 1 - i, like
+    /// in [`Hankel::symmetric_cycle`]. Returns whether the cycle is turned
+    /// halfway around, which is only tried if `turned`, or `None` if it can't
+    /// be completed.
+    fn extend_symmetric(
+        &self,
+        path: &mut Vec<usize>,
+        used: &mut [bool],
+        turned: bool,
+    ) -> Option<bool> {
+        let n = self.size;
+        let last = path[path.len() - 1];
+        if path.len() == n.div_ceil(2) {
+            if turned && self.get(last, n + 1 - path[0]) != 0 {
+                return Some(true);
+            }
+            // A reflected cycle also contains the edge between the last vertex
+            // and its image, and the edge between the first one and its image.
+            let reflected = self.get(last, n + 1 - last) != 0
+                && (n % 2 == 1 || self.get(path[0], n + 1 - path[0]) != 0);
+            return reflected.then_some(false);
+        }
+        for next in 1..=n {
+            if used[next] || self.get(last, next) == 0 {
+                continue;
+            }
+            path.push(next);
+            (used[next], used[n + 1 - next]) = (true, true);
+            if let Some(turned) = self.extend_symmetric(path, used, turned) {
+                return Some(turned);
+            }
+            (used[next], used[n + 1 - next]) = (false, false);
+            path.pop();
+        }
+        None
+    }
+
+    /// Refines `colors` of the two copies of the graph, until every two vertices
+    /// of the same color have the same number of neighbors of every color.
+    ///
+    /// Returns false if the copies have a different number of vertices of some
+    /// color, in which case no isomorphism between the copies respects the colors.
+    fn refine(&self, colors: &mut [usize]) -> bool {
+        let n = self.size;
+        let mut num_colors = count_colors(colors);
+        loop {
+            let mut signatures = (0..2 * n)
+                .map(|x| {
+                    let (copy, v) = (x / n * n, x % n);
+                    let mut neighbors = (0..n)
+                        .filter(|&w| self.get_0_based(v, w) != 0)
+                        .map(|w| colors[copy + w])
+                        .collect::<Vec<_>>();
+                    neighbors.sort_unstable();
+                    (colors[x], neighbors, x)
+                })
+                .collect::<Vec<_>>();
+            signatures.sort_unstable();
+            let mut color = 0;
+            for i in 0..signatures.len() {
+                if i > 0
+                    && (signatures[i].0, &signatures[i].1)
+                        != (signatures[i - 1].0, &signatures[i - 1].1)
+                {
+                    color += 1;
+                }
+                colors[signatures[i].2] = color;
+            }
+            let mut balance = vec![0isize; 2 * n];
+            for (x, &c) in colors.iter().enumerate() {
+                balance[c] += if x < n { 1 } else { -1 };
+            }
+            if balance.iter().any(|&b| b != 0) {
+                return false;
+            }
+            if color + 1 == num_colors {
+                return true;
+            }
+            num_colors = color + 1;
+        }
+    }
+
+    /// Searches for an isomorphism from the first to the second copy that
+    /// respects `colors`, by individualizing the vertices of a non-singleton
+    /// color one at a time. The result maps every vertex to its image.
+    fn extend_isomorphism(&self, mut colors: Vec<usize>) -> Option<Vec<usize>> {
+        let n = self.size;
+        if !self.refine(&mut colors) {
+            return None;
+        }
+        let mut counts = vec![0; 2 * n];
+        for &c in &colors[..n] {
+            counts[c] += 1;
+        }
+        let Some(x) = (0..n).find(|&v| counts[colors[v]] > 1) else {
+            // Every color is a single vertex, so there is only one candidate.
+            let mut image = vec![0; 2 * n];
+            for y in 0..n {
+                image[colors[n + y]] = y;
+            }
+            let mapping = colors[..n].iter().map(|&c| image[c]).collect::<Vec<_>>();
+            let preserved = (0..n).all(|v| {
+                (0..n).all(|w| self.get_0_based(v, w) == self.get_0_based(mapping[v], mapping[w]))
+            });
+            return preserved.then_some(mapping);
+        };
+        let unused = 2 * n;
+        for y in (0..n).filter(|&y| colors[n + y] == colors[x]) {
+            let mut next = colors.clone();
+            next[x] = unused;
+            next[n + y] = unused;
+            if let Some(mapping) = self.extend_isomorphism(next) {
+                return Some(mapping);
+            }
+        }
+        None
+    }
+}
+
+/// Returns the colors of two copies of a graph of size `n`, where the
+/// vertices in `fixed` have their own color, and `pair` (a vertex in the
+/// first and one in the second copy) has another color.
+fn individualized(n: usize, fixed: &[usize], pair: Option<(usize, usize)>) -> Vec<usize> {
+    let mut colors = vec![0; 2 * n];
+    for (i, &v) in fixed.iter().enumerate() {
+        colors[v] = i + 1;
+        colors[n + v] = i + 1;
+    }
+    if let Some((v, w)) = pair {
+        colors[v] = fixed.len() + 1;
+        colors[n + w] = fixed.len() + 1;
+    }
+    colors
+}
+
+/// Returns the number of different colors.
+fn count_colors(colors: &[usize]) -> usize {
+    let mut sorted = colors.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted.len()
+}
+
+/// The orbits of the group generated by some permutations, as a union-find
+/// structure where the smallest vertex of an orbit is its root.
+struct Orbits {
+    parent: Vec<usize>,
+}
+
+impl Orbits {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut v: usize) -> usize {
+        while self.parent[v] != v {
+            self.parent[v] = self.parent[self.parent[v]];
+            v = self.parent[v];
+        }
+        v
+    }
+
+    /// Joins the orbits of every vertex and its image under `mapping`.
+    fn add(&mut self, mapping: &[usize]) {
+        for (v, &w) in mapping.iter().enumerate() {
+            let (a, b) = (self.find(v), self.find(w));
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[test]
+fn orbits_of_small_graphs() {
+    // 4 - 1 - 2 - 3 is a path, with the reflection as only symmetry.
+    let path = Hankel::from_sequence(4, &[3, 5]);
+    assert_eq!(path.automorphisms(), vec![vec![2, 1, 4, 3]]);
+    assert_eq!(path.automorphism_orbits(), vec![1, 1, 3, 3]);
+    // Without edges every permutation is an automorphism.
+    let empty = Hankel::from_sequence(5, &[]);
+    assert_eq!(empty.automorphism_orbits(), vec![1; 5]);
+    assert!(empty.automorphisms().len() >= 2);
+    // Swapping 2k - 1 and 2k doesn't change the sum of an odd and an even number.
+    let swap = (1..=20)
+        .map(|v| v + 1 - 2 * (v % 2 == 0) as usize)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Hankel::prime_sum_matrix(20, None).automorphisms(),
+        vec![swap]
+    );
+    for mat in [Hankel::prime_sum_matrix(12, None), empty, path] {
+        for mapping in mat.automorphisms() {
+            for (i, j) in mat.edges() {
+                assert_eq!(mat.get(mapping[i - 1], mapping[j - 1]), 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn symmetric_cycles() {
+    let invariant = |mat: &Hankel, cycle: &[usize]| {
+        let n = mat.size;
+        let edge = |i: usize| {
+            let (v, w) = (cycle[i], cycle[(i + 1) % n]);
+            (v.min(w), v.max(w))
+        };
+        let mut edges = (0..n).map(edge).collect::<Vec<_>>();
+        let mut images = edges
+            .iter()
+            .map(|&(v, w)| (n + 1 - w, n + 1 - v))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        images.sort_unstable();
+        mat.valid_cycle(cycle) && edges == images
+    };
+    // 1 - 2 - 3 - 4 and the sums 3, 5 and 7 are symmetric around 5.
+    let mat = Hankel::prime_sum_matrix(4, None);
+    assert!(mat.reflection_is_automorphism());
+    assert!(invariant(&mat, &mat.symmetric_cycle().unwrap()));
+    // 3 + 11 isn't prime, while 3 + 5 is.
+    assert!(!Hankel::prime_sum_matrix(6, None).reflection_is_automorphism());
+    assert_eq!(Hankel::prime_sum_matrix(6, None).symmetric_cycle(), None);
+    let mut found = 0;
+    for n in 1..=14 {
+        let mat = Hankel::prime_difference_matrix(n, None);
+        assert!(mat.reflection_is_automorphism());
+        if let Some(cycle) = mat.symmetric_cycle() {
+            assert!(invariant(&mat, &cycle));
+            found += 1;
+        }
+    }
+    assert!(found > 0);
+}
@@ -0,0 +1,137 @@
+//! A terminal UI showing the progress of the search threads, enabled with `--tui`.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, List, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::Monitor;
+
+/// How often the screen is redrawn.
+const REFRESH: Duration = Duration::from_millis(500);
+
+/// Runs `search` on another thread, while showing its progress.
+///
+/// Pressing `q` sets `stop`, after which the threads stop once they are done
+/// with their current size. If the terminal UI fails, the search continues
+/// without it.
+pub(crate) fn run<T: Send>(
+    monitor: &Monitor,
+    stop: &AtomicBool,
+    search: impl FnOnce() -> T + Send,
+) -> T {
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        let handle = s.spawn(|| {
+            let result = search();
+            done.store(true, Ordering::Relaxed);
+            result
+        });
+        let result = ratatui::try_init().and_then(|mut terminal| {
+            let result = show(&mut terminal, monitor, stop, &done);
+            ratatui::try_restore()?;
+            result
+        });
+        if let Err(e) = result {
+            eprintln!("The terminal UI failed: {}", e);
+        }
+        handle.join().unwrap()
+    })
+}
+
+/// Redraws the screen until the search is done.
+fn show(
+    terminal: &mut DefaultTerminal,
+    monitor: &Monitor,
+    stop: &AtomicBool,
+    done: &AtomicBool,
+) -> io::Result<()> {
+    let started = Instant::now();
+    let mut previous = (Instant::now(), vec![0; monitor.threads.len()]);
+    while !done.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let nodes = monitor
+            .threads
+            .iter()
+            .map(|t| {
+                t.finished_nodes.load(Ordering::Relaxed) + t.search.nodes.load(Ordering::Relaxed)
+            })
+            .collect::<Vec<_>>();
+        let elapsed = now.duration_since(previous.0).as_secs_f64();
+        let rates = nodes
+            .iter()
+            .zip(&previous.1)
+            .map(|(&n, &p)| match elapsed {
+                0.0 => 0.0,
+                _ => n.saturating_sub(p) as f64 / elapsed,
+            })
+            .collect::<Vec<_>>();
+        terminal.draw(|frame| draw(frame, monitor, &nodes, &rates, started, stop))?;
+        previous = (now, nodes);
+        if event::poll(REFRESH)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draws a table with the size, depth, total number of `nodes` and nodes
+/// per second of every thread, followed by the recent failures.
+fn draw(
+    frame: &mut Frame,
+    monitor: &Monitor,
+    nodes: &[u64],
+    rates: &[f64],
+    started: Instant,
+    stop: &AtomicBool,
+) {
+    let [header, threads, failures] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(monitor.threads.len() as u16 + 3),
+        Constraint::Min(3),
+    ])
+    .areas(frame.area());
+    let status = if stop.load(Ordering::Relaxed) {
+        "stopping after the current sizes"
+    } else {
+        "press q to stop"
+    };
+    frame.render_widget(
+        Paragraph::new(format!("Running for {:.0?}, {}", started.elapsed(), status)),
+        header,
+    );
+    let rows = monitor.threads.iter().enumerate().map(|(i, t)| {
+        Row::new([
+            i.to_string(),
+            t.size.load(Ordering::Relaxed).to_string(),
+            t.search.depth.load(Ordering::Relaxed).to_string(),
+            nodes[i].to_string(),
+            format!("{:.0}", rates[i]),
+        ])
+    });
+    let table = Table::new(rows, [Constraint::Length(12); 5])
+        .header(
+            Row::new(["Thread", "Size", "Depth", "Nodes", "Nodes/s"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title("Threads"));
+    frame.render_widget(table, threads);
+    let recent = monitor.recent_failures.lock().unwrap();
+    let list = List::new(
+        recent
+            .iter()
+            .map(|(n, outcome)| format!("{:>12}  {}", n, outcome.as_str())),
+    )
+    .block(Block::bordered().title("Recent failures"));
+    frame.render_widget(list, failures);
+}
@@ -0,0 +1,208 @@
+//! Products of the adjacency matrix with 64 entries at a time, for the
+//! walks between the vertices.
+//!
+//! A set of vertices, or a row of a matrix, is a bitset of `u64`s, where
+//! bit i of word i / 64 stands for the 0-based vertex i, like in
+//! [`Hankel::get_0_based`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hankel;
+
+impl Hankel {
+    /// Returns the product of the adjacency matrix with the bitset `x` over
+    /// GF(2), so bit i is the parity of the number of neighbors of i in `x`.
+    ///
+    /// If `x` is the set of a single vertex, applying this k times gives the
+    /// parity of the number of walks of length k from it to every vertex.
+    ///
+    /// # Panics
+    ///
+    /// If `x` has less than n / 64 words, rounded up.
+    pub fn mul_vec_gf2(&self, x: &[u64]) -> Vec<u64> {
+        let words = self.size.div_ceil(64);
+        let x = &x[..words];
+        let mut y = vec![0; words];
+        for (i, row) in self.bit_rows().enumerate() {
+            let ones = row
+                .iter()
+                .zip(x)
+                .map(|(r, x)| (r & x).count_ones())
+                .sum::<u32>();
+            y[i / 64] |= u64::from(ones % 2) << (i % 64);
+        }
+        y
+    }
+
+    /// Returns the rows of the k-th power of the adjacency matrix over the
+    /// booleans, as bitsets. Bit j of row i is 1 if there is a walk with k
+    /// edges from i to j, where the vertices are 0-based.
+    ///
+    /// The power is computed by repeated squaring, with about n³ / 64
+    /// operations for every product. The 0th power is the identity.
+    pub fn bool_power(&self, k: u32) -> Vec<Vec<u64>> {
+        let n = self.size;
+        let mut power = (0..n)
+            .map(|i| {
+                let mut row = vec![0; n.div_ceil(64)];
+                row[i / 64] |= 1 << (i % 64);
+                row
+            })
+            .collect::<Vec<_>>();
+        let mut square = self.bit_rows().collect::<Vec<_>>();
+        let mut k = k;
+        while k > 0 {
+            if k % 2 == 1 {
+                power = bool_product(&power, &square);
+            }
+            k /= 2;
+            if k > 0 {
+                square = bool_product(&square, &square);
+            }
+        }
+        power
+    }
+
+    /// Returns the rows of the reachability matrix as bitsets, where bit j of
+    /// row i is 1 if there is a walk from i to j. Every vertex reaches itself.
+    ///
+    /// The rows of the vertices in a connected component are the same, so
+    /// the graph is connected if the first row has all n bits.
+    pub fn transitive_closure(&self) -> Vec<Vec<u64>> {
+        let n = self.size;
+        let rows = self.bit_rows().collect::<Vec<_>>();
+        let mut closure: Vec<Vec<u64>> = vec![Vec::new(); n];
+        for v in 0..n {
+            if !closure[v].is_empty() {
+                continue;
+            }
+            // Add the neighbors of the new vertices until there are none.
+            let mut component = vec![0; n.div_ceil(64)];
+            component[v / 64] |= 1 << (v % 64);
+            let mut frontier = component.clone();
+            while frontier.iter().any(|&w| w != 0) {
+                let reached = bool_product(&[frontier], &rows).remove(0);
+                frontier = reached
+                    .iter()
+                    .zip(&component)
+                    .map(|(r, c)| r & !c)
+                    .collect();
+                for (c, f) in component.iter_mut().zip(&frontier) {
+                    *c |= f;
+                }
+            }
+            for w in (0..n).filter(|&w| component[w / 64] >> (w % 64) & 1 == 1) {
+                closure[w].clone_from(&component);
+            }
+        }
+        closure
+    }
+
+    /// Returns the rows of the adjacency matrix as bitsets.
+    pub(crate) fn bit_rows(&self) -> impl Iterator<Item = Vec<u64>> + '_ {
+        let n = self.size;
+        (0..n).map(move |i| {
+            let mut row = vec![0; n.div_ceil(64)];
+            for j in (0..n).filter(|&j| self.get_0_based(i, j) != 0) {
+                row[j / 64] |= 1 << (j % 64);
+            }
+            row
+        })
+    }
+}
+
+/// Returns the boolean product of the matrices with rows `a` and `b`, where
+/// a row of the product is the union of the rows of `b` chosen by `a`.
+fn bool_product(a: &[Vec<u64>], b: &[Vec<u64>]) -> Vec<Vec<u64>> {
+    a.iter()
+        .map(|row| {
+            let mut product = vec![0; row.len()];
+            for (w, &word) in row.iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    let j = 64 * w + bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    for (p, &r) in product.iter_mut().zip(&b[j]) {
+                        *p |= r;
+                    }
+                }
+            }
+            product
+        })
+        .collect()
+}
+
+#[test]
+fn walks_with_bits() {
+    for mat in [
+        Hankel::prime_sum_matrix(6, None),
+        Hankel::prime_sum_matrix(70, None),
+        Hankel::from_differences(9, &[0, 2, 3]),
+    ] {
+        let n = mat.size;
+        // Whether there is a walk of length k from i to j, and the parity
+        // of their number, computed entry by entry.
+        let mut walks = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| (i == j, u8::from(i == j)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for k in 0..5 {
+            let power = mat.bool_power(k);
+            for i in 0..n {
+                for j in 0..n {
+                    assert_eq!(power[i][j / 64] >> (j % 64) & 1 == 1, walks[i][j].0);
+                }
+            }
+            let mut x = vec![0; n.div_ceil(64)];
+            x[0] = 1;
+            for _ in 0..k {
+                x = mat.mul_vec_gf2(&x);
+            }
+            for j in 0..n {
+                assert_eq!(x[j / 64] >> (j % 64) & 1, u64::from(walks[0][j].1));
+            }
+            walks = walks
+                .iter()
+                .map(|row| {
+                    (0..n)
+                        .map(|j| {
+                            let before = (0..n).filter(|&l| mat.get_0_based(l, j) != 0);
+                            (
+                                before.clone().any(|l| row[l].0),
+                                before.map(|l| row[l].1).sum::<u8>() % 2,
+                            )
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+    }
+    assert!(Hankel::from_sequence(0, &[]).bool_power(3).is_empty());
+}
+
+#[test]
+fn reachability() {
+    for mat in [
+        Hankel::prime_sum_matrix(80, None),
+        Hankel::from_sequence(9, &[5, 13]),
+        Hankel::from_differences(10, &[4]),
+    ] {
+        let closure = mat.transitive_closure();
+        for (i, row) in closure.iter().enumerate() {
+            let distances = mat.bfs_distances(i + 1);
+            for (j, distance) in distances.iter().enumerate() {
+                assert_eq!(row[j / 64] >> (j % 64) & 1 == 1, distance.is_some());
+            }
+        }
+    }
+    // The prime sum graphs are connected, unlike those with the sums 5 and 13,
+    // where 1 only reaches 4 and 9.
+    let closure = Hankel::prime_sum_matrix(80, None).transitive_closure();
+    assert_eq!(closure[0], [u64::MAX, (1 << 16) - 1]);
+    let closure = Hankel::from_sequence(9, &[5, 13]).transitive_closure();
+    assert_eq!(closure[0], [0b1_0000_1001]);
+}
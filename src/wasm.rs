@@ -0,0 +1,83 @@
+//! Bindings for JavaScript, enabled with the `wasm-bindgen` feature.
+//!
+//! The `wasm` crate builds them as a `cdylib`, without the default features,
+//! since there are no threads for `rayon` in the browser:
+//!
+//! ```text
+//! wasm-pack build --target web wasm
+//! ```
+
+use std::io;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Hankel;
+
+/// Returns a Hamiltonian cycle in the prime sum graph of size `n`,
+/// or an empty array if there is none.
+#[wasm_bindgen(js_name = findCycle)]
+pub fn find_cycle(n: u32) -> Vec<u32> {
+    Hankel::try_prime_sum_matrix(n as usize, None)
+        .ok()
+        .and_then(|mat| mat.is_hamiltonian())
+        .map_or_else(Vec::new, |cycle| {
+            cycle.into_iter().map(|vertex| vertex as u32).collect()
+        })
+}
+
+/// Returns the prime sum graph of size `n` in the Graphviz DOT format,
+/// see [`Hankel::write_dot`].
+#[wasm_bindgen(js_name = exportDot)]
+pub fn export_dot(n: u32, cycle: Option<Vec<u32>>) -> Result<String, JsError> {
+    export(n, cycle, |mat, output, cycle| mat.write_dot(output, cycle))
+}
+
+/// Returns the prime sum graph of size `n` in the GraphML format,
+/// see [`Hankel::write_graphml`].
+#[wasm_bindgen(js_name = exportGraphml)]
+pub fn export_graphml(n: u32, cycle: Option<Vec<u32>>) -> Result<String, JsError> {
+    export(n, cycle, |mat, output, cycle| {
+        mat.write_graphml(output, cycle)
+    })
+}
+
+/// Returns the adjacency matrix of size `n` in the Matrix Market format,
+/// see [`Hankel::write_matrix_market`].
+#[wasm_bindgen(js_name = exportMatrixMarket)]
+pub fn export_matrix_market(n: u32, cycle: Option<Vec<u32>>) -> Result<String, JsError> {
+    export(n, cycle, |mat, output, cycle| {
+        mat.write_matrix_market(output, cycle)
+    })
+}
+
+/// Returns a drawing of the prime sum graph of size `n` in the SVG format,
+/// see [`Hankel::write_svg`].
+#[wasm_bindgen(js_name = exportSvg)]
+pub fn export_svg(n: u32, cycle: Option<Vec<u32>>) -> Result<String, JsError> {
+    export(n, cycle, |mat, output, cycle| mat.write_svg(output, cycle))
+}
+
+/// Writes the prime sum graph of size `n` with `write`, and returns the text.
+fn export(
+    n: u32,
+    cycle: Option<Vec<u32>>,
+    write: impl FnOnce(&Hankel, &mut Vec<u8>, Option<&[usize]>) -> io::Result<()>,
+) -> Result<String, JsError> {
+    let mat = Hankel::try_prime_sum_matrix(n as usize, None)?;
+    let cycle = cycle.map(|c| c.into_iter().map(|v| v as usize).collect::<Vec<_>>());
+    let mut output = Vec::new();
+    write(&mat, &mut output, cycle.as_deref())?;
+    Ok(String::from_utf8(output)?)
+}
+
+#[test]
+fn cycle_and_export() {
+    let cycle = find_cycle(10);
+    let mat = Hankel::prime_sum_matrix(10, None);
+    let cycle = cycle.iter().map(|&v| v as usize).collect::<Vec<_>>();
+    assert!(mat.valid_cycle(&cycle));
+    assert!(find_cycle(0).is_empty());
+    assert!(export_dot(6, Some(vec![1, 4, 3, 2, 5, 6]))
+        .unwrap()
+        .contains("1 -- 4 [color=red, penwidth=2];"));
+}
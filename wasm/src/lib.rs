@@ -0,0 +1,7 @@
+//! The JavaScript bindings of `primes`, built with:
+//!
+//! ```text
+//! wasm-pack build --target web wasm
+//! ```
+
+pub use primes::wasm::*;